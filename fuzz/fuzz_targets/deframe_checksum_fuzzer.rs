@@ -1,7 +1,7 @@
 #![no_main]
 use flatstream::checksum::Checksum;
 use flatstream::{
-    ChecksumDeframer, ChecksumFramer, Crc16, Crc32, Framer, StreamReader, XxHash64,
+    ChecksumDeframer, ChecksumFramer, Crc16, Crc32, Crc32c, Framer, StreamReader, XxHash64,
 };
 use libfuzzer_sys::fuzz_target;
 use std::io::Cursor;
@@ -44,5 +44,6 @@ fn exercise_checksum<C: Checksum + Copy>(data: &[u8], checksum: C) {
 fuzz_target!(|data: &[u8]| {
     exercise_checksum(data, XxHash64::new());
     exercise_checksum(data, Crc32::new());
+    exercise_checksum(data, Crc32c::new());
     exercise_checksum(data, Crc16::new());
 });
@@ -7,7 +7,11 @@
 #   test matrix all_checksums (full suite incl. doctests), no-features, and a
 #               single-feature build (crc16) that catches #[cfg] gaps; plus
 #               the opt-in unsafe_typed integration test so that public feature
-#               cannot bit-rot outside the default unsafe-free build
+#               cannot bit-rot outside the default unsafe-free build; plus the
+#               optional I/O subsystems (mmap, tokio), compression codecs
+#               (lz4, zstd), authenticated frames (hmac, aead) and wide
+#               digests (sha256, blake3), each of those features also alone,
+#               which no other step enables
 #   rustdoc     broken intra-doc links and doc warnings, as errors
 #   bench check benches are compile-checked so they can't bit-rot between runs
 #               (actually *running* benches is a separate, deliberate act — see
@@ -32,7 +36,7 @@ echo "== clippy (all targets, all_checksums, -D warnings)"
 cargo clippy --locked --all-targets --features all_checksums -- -D warnings
 
 echo "== clippy: individual checksum feature configurations"
-for feature in xxhash crc32 crc16; do
+for feature in xxhash crc32 crc16 crc32c; do
     cargo clippy --locked --all-targets --no-default-features --features "$feature" -- -D warnings
 done
 
@@ -51,6 +55,14 @@ cargo test --locked --no-default-features --features crc16
 echo "== test: unsafe_typed opt-in"
 cargo test --locked --features all_checksums,unsafe_typed --test stream_deserialize_integration_tests
 
+echo "== clippy + test: optional subsystems"
+OPTIONAL_FEATURES="all_checksums,mmap,tokio,tokio_codec,lz4,zstd,hmac,aead,sha256,blake3"
+cargo clippy --locked --all-targets --features "$OPTIONAL_FEATURES" -- -D warnings
+for feature in mmap tokio tokio_codec lz4 zstd hmac aead sha256 blake3; do
+    cargo clippy --locked --all-targets --no-default-features --features "$feature" -- -D warnings
+done
+cargo test --locked --features "$OPTIONAL_FEATURES"
+
 echo "== rustdoc (-D warnings)"
 RUSTDOCFLAGS="-D warnings" cargo doc --locked --no-deps --features all_checksums
 
@@ -0,0 +1,685 @@
+//! Async readers and writers for tokio (feature `tokio`).
+//!
+//! [`AsyncStreamReader`] and [`AsyncStreamWriter`] are the `AsyncRead` /
+//! `AsyncWrite` counterparts of [`StreamReader`](crate::StreamReader) and
+//! [`StreamWriter`](crate::StreamWriter). The wire format is unchanged
+//! (`docs/WIRE_FORMAT_SPEC.md`): a stream written by one side can be read by
+//! either, sync or async.
+//!
+//! Framing stays synchronous and allocation-free. The writer frames each
+//! message into a reusable staging buffer with the ordinary [`Framer`] — so
+//! checksum, bounded, validating and observer framers all apply unchanged —
+//! and then hands the staged frame to the `AsyncWrite` in one `write_all`.
+//! The reader fills a [`FrameDecoder`] from the `AsyncRead` and deframes with
+//! [`SliceDeframer`], which the built-in deframers and their validating and
+//! observer adapters implement. Deframers that rebuild the payload instead of
+//! locating it in the input — decompression, blocks, authentication,
+//! [`BoxDeframer`](crate::BoxDeframer) — have no [`SliceDeframer`]
+//! implementation; [`AsyncDeframingReader`] runs any [`Deframer`] over the
+//! buffered bytes instead.
+
+use crate::decoder::{FrameDecoder, READ_CHUNK};
+use crate::error::{ErrorKind, Result};
+use crate::framing::{Deframer, Framer, SliceDeframer};
+use crate::policy::MemoryPolicy;
+use crate::reader::PolicySlot as ReaderPolicySlot;
+use crate::traits::{StreamDeserialize, StreamSerialize};
+use crate::writer::PolicySlot as WriterPolicySlot;
+use flatbuffers::{DefaultAllocator, FlatBufferBuilder};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// An async reader for streaming messages from a `flatstream`.
+///
+/// Reads are buffered: each `read` from the source appends to an internal
+/// buffer, and frames are decoded from it as they complete, so a frame may
+/// arrive in any number of pieces. End of input is handled as in the sync
+/// reader (spec §6): EOF on a frame boundary is a clean end (`Ok(None)`),
+/// EOF inside a frame is `ErrorKind::UnexpectedEof`.
+///
+/// Payloads are returned in place, so the deframer must implement
+/// [`SliceDeframer`]: `DefaultDeframer`, `ChecksumDeframer`,
+/// `HeaderChecksumDeframer`, `ChecksumKindDeframer`, `AutoDeframer`,
+/// `SyncDeframer`, and the validating and observer adapters over them.
+/// For `DecompressingDeframer`, `BlockDeframer`, `HmacDeframer`,
+/// `AeadDeframer` or a `BoxDeframer`, use [`AsyncDeframingReader`].
+///
+/// ```rust
+/// use flatstream::{AsyncStreamReader, DefaultDeframer, Result};
+///
+/// async fn count(socket: impl tokio::io::AsyncRead + Unpin) -> Result<u64> {
+///     let deframer = DefaultDeframer::new().with_max_frame_len(1 << 20);
+///     let mut reader = AsyncStreamReader::new(socket, deframer);
+///     let mut frames = 0;
+///     while let Some(_payload) = reader.read_message().await? {
+///         frames += 1;
+///     }
+///     Ok(frames)
+/// }
+/// ```
+///
+/// ## Cancellation
+///
+/// [`read_message`](Self::read_message) is cancel-safe: bytes read before
+/// the future is dropped stay buffered, so it can be used in `select!` loops
+/// without losing data.
+///
+/// ## Memory Reclamation
+///
+/// As with `StreamReader`, an optional [`MemoryPolicy`] shrinks the internal
+/// buffer back to the policy's baseline after a burst of large frames. The
+/// shrink is deferred to the start of the next read so a payload already
+/// returned is never invalidated.
+pub struct AsyncStreamReader<R, D: SliceDeframer> {
+    reader: R,
+    decoder: FrameDecoder<D>,
+    policy: Option<ReaderPolicySlot>,
+    pending_shrink: bool,
+}
+
+impl<R: AsyncRead + Unpin, D: SliceDeframer> AsyncStreamReader<R, D> {
+    /// Creates a new `AsyncStreamReader` with the given reader and deframing
+    /// strategy.
+    pub fn new(reader: R, deframer: D) -> Self {
+        Self::with_capacity(reader, deframer, 0)
+    }
+
+    /// Creates a new `AsyncStreamReader` with a pre-allocated buffer capacity.
+    pub fn with_capacity(reader: R, deframer: D, capacity: usize) -> Self {
+        Self {
+            reader,
+            decoder: FrameDecoder::with_capacity(deframer, capacity),
+            policy: None,
+            pending_shrink: false,
+        }
+    }
+
+    /// Installs a memory reclamation policy on this reader. Behaves as
+    /// [`StreamReader::with_memory_policy`](crate::StreamReader::with_memory_policy).
+    pub fn with_memory_policy<P: MemoryPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policy = Some(ReaderPolicySlot::new(policy));
+        self
+    }
+
+    /// Reads the next message. Returns `Ok(Some(payload))` on success and
+    /// `Ok(None)` on clean EOF.
+    ///
+    /// The payload borrows the reader's buffer and is valid until the next
+    /// read.
+    pub async fn read_message(&mut self) -> Result<Option<&[u8]>> {
+        if self.pending_shrink {
+            self.apply_pending_shrink();
+        }
+        loop {
+            if let Some(payload) = self.decoder.decode_range()? {
+                if self.policy.is_some() {
+                    self.evaluate_memory_policy(payload.len());
+                }
+                return Ok(Some(self.decoder.payload(payload)));
+            }
+            if self.reader.read_buf(self.decoder.append_buffer()).await? == 0 {
+                // EOF: clean only on a frame boundary.
+                self.decoder.finish()?;
+                return Ok(None);
+            }
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn apply_pending_shrink(&mut self) {
+        if let Some(slot) = self.policy.as_ref() {
+            self.decoder.shrink_to(slot.baseline_capacity);
+        }
+        self.pending_shrink = false;
+    }
+
+    #[inline(never)]
+    fn evaluate_memory_policy(&mut self, last_message_size: usize) {
+        let capacity = self.decoder.buffer_capacity();
+        if let Some(slot) = self.policy.as_mut() {
+            if slot.should_shrink(last_message_size, capacity) {
+                self.pending_shrink = true;
+            }
+        }
+    }
+
+    /// Processes all messages in the stream using a closure. Return `Ok(())`
+    /// from the closure to continue or an error to stop.
+    pub async fn process_all<F>(&mut self, mut processor: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        while let Some(payload) = self.read_message().await? {
+            processor(payload)?;
+        }
+        Ok(())
+    }
+
+    /// Processes all messages, deserializing each into a strongly-typed
+    /// FlatBuffer root (see [`StreamReader::process_typed`](crate::StreamReader::process_typed)).
+    pub async fn process_typed<T, F>(&mut self, mut processor: F) -> Result<()>
+    where
+        for<'p> T: StreamDeserialize<'p>,
+        for<'p> F: FnMut(<T as StreamDeserialize<'p>>::Root) -> Result<()>,
+    {
+        self.process_all(|payload| {
+            let root = <T as StreamDeserialize<'_>>::from_payload(payload)?;
+            processor(root)
+        })
+        .await
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader. Reading from it
+    /// directly bypasses the buffered bytes and desynchronizes the stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns a reference to the deframer strategy.
+    pub fn deframer(&self) -> &D {
+        self.decoder.deframer()
+    }
+
+    /// Returns the current capacity of the internal buffer.
+    pub fn buffer_capacity(&self) -> usize {
+        self.decoder.buffer_capacity()
+    }
+
+    /// Number of bytes read from the source but not yet returned as frames.
+    pub fn buffered_len(&self) -> usize {
+        self.decoder.buffered_len()
+    }
+
+    /// Consumes the reader, returning the underlying reader. Any
+    /// [`buffered_len`](Self::buffered_len) bytes are discarded.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// An async reader for any [`Deframer`], including those that decompress,
+/// unpack blocks or authenticate payloads.
+///
+/// Bytes read from the source are buffered, and the deframer runs over them
+/// as over a `&[u8]` reader; a frame it finds incomplete is retried once more
+/// bytes arrive. Each read asks for at least as many bytes as are already
+/// buffered, so a large frame is re-parsed a logarithmic number of times
+/// when the source keeps up. Deframers must leave their state untouched when
+/// a frame turns out to be incomplete; the built-in ones do. End of input and
+/// cancellation behave as for [`AsyncStreamReader`], which should be
+/// preferred where the deframer allows it: it returns payloads without the
+/// copy this reader makes.
+///
+/// ```rust
+/// # #[cfg(feature = "lz4")] {
+/// use flatstream::compression::{Compression, DecompressingDeframer};
+/// use flatstream::{AsyncDeframingReader, DefaultDeframer, Result};
+///
+/// async fn count(socket: impl tokio::io::AsyncRead + Unpin) -> Result<u64> {
+///     let deframer = DecompressingDeframer::new(DefaultDeframer::new().with_max_frame_len(1 << 20));
+///     let mut reader = AsyncDeframingReader::new(socket, deframer);
+///     let mut frames = 0;
+///     while let Some(_payload) = reader.read_message().await? {
+///         frames += 1;
+///     }
+///     Ok(frames)
+/// }
+/// # }
+/// ```
+pub struct AsyncDeframingReader<R, D: Deframer> {
+    reader: R,
+    deframer: D,
+    input: Vec<u8>,
+    consumed: usize,
+    eof: bool,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin, D: Deframer> AsyncDeframingReader<R, D> {
+    /// Creates a new `AsyncDeframingReader` with the given reader and
+    /// deframing strategy.
+    pub fn new(reader: R, deframer: D) -> Self {
+        Self {
+            reader,
+            deframer,
+            input: Vec::new(),
+            consumed: 0,
+            eof: false,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Reads the next message. Returns `Ok(Some(payload))` on success and
+    /// `Ok(None)` on clean EOF.
+    ///
+    /// The payload borrows the reader's buffer and is valid until the next
+    /// read.
+    pub async fn read_message(&mut self) -> Result<Option<&[u8]>> {
+        loop {
+            let available = &self.input[self.consumed..];
+            let mut rest = available;
+            let result = self.deframer.read_and_deframe(&mut rest, &mut self.buffer);
+            let incomplete = match &result {
+                Ok(None) => true,
+                Err(e) => matches!(e.kind(), ErrorKind::UnexpectedEof),
+                Ok(Some(_)) => false,
+            };
+            if !incomplete || self.eof {
+                // As on the `Read` path, a rejected frame is consumed too.
+                self.consumed += available.len() - rest.len();
+                return Ok(result?.map(|len| &self.buffer[..len]));
+            }
+            self.input.drain(..self.consumed);
+            self.consumed = 0;
+            self.input.reserve(READ_CHUNK.max(self.input.len()));
+            self.eof = self.reader.read_buf(&mut self.input).await? == 0;
+        }
+    }
+
+    /// Processes all messages in the stream using a closure. Return `Ok(())`
+    /// from the closure to continue or an error to stop.
+    pub async fn process_all<F>(&mut self, mut processor: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        while let Some(payload) = self.read_message().await? {
+            processor(payload)?;
+        }
+        Ok(())
+    }
+
+    /// Processes all messages, deserializing each into a strongly-typed
+    /// FlatBuffer root (see [`StreamReader::process_typed`](crate::StreamReader::process_typed)).
+    pub async fn process_typed<T, F>(&mut self, mut processor: F) -> Result<()>
+    where
+        for<'p> T: StreamDeserialize<'p>,
+        for<'p> F: FnMut(<T as StreamDeserialize<'p>>::Root) -> Result<()>,
+    {
+        self.process_all(|payload| {
+            let root = <T as StreamDeserialize<'_>>::from_payload(payload)?;
+            processor(root)
+        })
+        .await
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader. Reading from it
+    /// directly bypasses the buffered bytes and desynchronizes the stream.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns a reference to the deframer strategy.
+    pub fn deframer(&self) -> &D {
+        &self.deframer
+    }
+
+    /// Number of bytes read from the source but not yet deframed.
+    pub fn buffered_len(&self) -> usize {
+        self.input.len() - self.consumed
+    }
+
+    /// Consumes the reader, returning the underlying reader. Any
+    /// [`buffered_len`](Self::buffered_len) bytes are discarded.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// An async writer for streaming FlatBuffer messages.
+///
+/// Offers the same two modes as [`StreamWriter`](crate::StreamWriter):
+/// `write()` with an internal builder (simple mode, where an optional
+/// [`MemoryPolicy`] applies) and `write_finished()` with a caller-owned
+/// builder (expert mode).
+///
+/// ```rust
+/// use flatstream::{AsyncStreamWriter, DefaultFramer, Result};
+///
+/// async fn journal(out: impl tokio::io::AsyncWrite + Unpin, events: &[String]) -> Result<()> {
+///     let mut writer = AsyncStreamWriter::new(out, DefaultFramer);
+///     for event in events {
+///         writer.write(event).await?;
+///     }
+///     writer.flush().await
+/// }
+/// ```
+///
+/// **Copy behavior**: unlike the sync writer, each frame is staged — header
+/// and payload copied once into a reusable buffer — before the single
+/// `write_all`. The staging buffer is what lets synchronous framers run
+/// unchanged, and it means a framing error (a bound or validator rejecting
+/// the payload) never leaves a partial frame on the wire. Each write issues
+/// at least one write to the target; wrap small-message streams in
+/// `tokio::io::BufWriter`.
+///
+/// ## Cancellation
+///
+/// `write` and `write_finished` are **not** cancel-safe: dropping the future
+/// mid-`write_all` can leave a torn frame on the target. Readers detect that
+/// as `UnexpectedEof` (see journal recovery), but the stream cannot be
+/// continued after it.
+pub struct AsyncStreamWriter<'a, W, F: Framer, A = DefaultAllocator>
+where
+    A: flatbuffers::Allocator,
+{
+    writer: W,
+    framer: F,
+    builder: FlatBufferBuilder<'a, A>,
+    staging: Vec<u8>,
+    policy: Option<WriterPolicySlot<'a, A>>,
+}
+
+impl<'a, W: AsyncWrite + Unpin, F: Framer> AsyncStreamWriter<'a, W, F> {
+    /// Creates a new `AsyncStreamWriter` with a default `FlatBufferBuilder`
+    /// (simple mode).
+    pub fn new(writer: W, framer: F) -> Self {
+        Self::with_builder_alloc(writer, framer, FlatBufferBuilder::new())
+    }
+
+    /// Creates a new `AsyncStreamWriter` with an internal builder
+    /// pre-allocated to `capacity` bytes.
+    pub fn with_capacity(writer: W, framer: F, capacity: usize) -> Self {
+        Self::with_builder_alloc(writer, framer, FlatBufferBuilder::with_capacity(capacity))
+    }
+
+    /// Installs a memory reclamation policy on this writer (simple mode
+    /// only). Behaves as
+    /// [`StreamWriter::with_memory_policy`](crate::StreamWriter::with_memory_policy);
+    /// a reclaim also shrinks the staging buffer.
+    pub fn with_memory_policy<P: MemoryPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policy = Some(WriterPolicySlot::new(
+            policy,
+            FlatBufferBuilder::with_capacity,
+        ));
+        self
+    }
+}
+
+impl<'a, W: AsyncWrite + Unpin, F: Framer, A> AsyncStreamWriter<'a, W, F, A>
+where
+    A: flatbuffers::Allocator,
+{
+    /// Creates a new `AsyncStreamWriter` with a user-provided
+    /// `FlatBufferBuilder`, e.g. one backed by a custom allocator.
+    pub fn with_builder_alloc(writer: W, framer: F, builder: FlatBufferBuilder<'a, A>) -> Self {
+        Self {
+            writer,
+            framer,
+            builder,
+            staging: Vec::new(),
+            policy: None,
+        }
+    }
+
+    /// Installs a memory reclamation policy together with a builder factory
+    /// (see [`StreamWriter::with_memory_policy_and_factory`](crate::StreamWriter::with_memory_policy_and_factory)).
+    pub fn with_memory_policy_and_factory<P, M>(mut self, policy: P, make_builder: M) -> Self
+    where
+        P: MemoryPolicy + 'static,
+        M: FnMut(usize) -> FlatBufferBuilder<'a, A> + Send + 'a,
+    {
+        self.policy = Some(WriterPolicySlot::new(policy, make_builder));
+        self
+    }
+
+    /// Writes a serializable item to the stream using the internally managed
+    /// builder (simple mode).
+    pub async fn write<T: StreamSerialize>(&mut self, item: &T) -> Result<()> {
+        self.builder.reset();
+        item.serialize(&mut self.builder)?;
+        let payload = self.builder.finished_data();
+        let last_message_size = payload.len();
+        stage(&self.framer, &mut self.staging, payload)?;
+        self.writer.write_all(&self.staging).await?;
+
+        if let Some(slot) = self.policy.as_mut() {
+            if slot.reclaim_if_needed(&mut self.builder, last_message_size) {
+                self.staging.clear();
+                self.staging.shrink_to(slot.baseline_capacity);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a finished FlatBuffer message to the stream (expert mode). Any
+    /// installed [`MemoryPolicy`] does not apply: the caller owns the builder.
+    pub async fn write_finished<A2: flatbuffers::Allocator>(
+        &mut self,
+        builder: &mut FlatBufferBuilder<'_, A2>,
+    ) -> Result<()> {
+        stage(&self.framer, &mut self.staging, builder.finished_data())?;
+        self.writer.write_all(&self.staging).await?;
+        Ok(())
+    }
+
+    /// Writes out any frames the framer is holding back (see
+    /// [`Framer::flush_pending`]), then flushes the underlying writer.
+    pub async fn flush(&mut self) -> Result<()> {
+        self.staging.clear();
+        self.framer.flush_pending(&mut self.staging)?;
+        self.writer.write_all(&self.staging).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    /// Flushes and shuts down the underlying writer (for sockets, half-closes
+    /// the write side so the peer sees a clean EOF).
+    pub async fn shutdown(&mut self) -> Result<()> {
+        self.flush().await?;
+        self.writer.shutdown().await?;
+        Ok(())
+    }
+
+    /// Consumes the writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns a reference to the framer strategy.
+    pub fn framer(&self) -> &F {
+        &self.framer
+    }
+}
+
+/// Frames `payload` into `staging`, replacing its previous contents.
+#[inline]
+fn stage<F: Framer>(framer: &F, staging: &mut Vec<u8>, payload: &[u8]) -> Result<()> {
+    staging.clear();
+    framer.frame_and_write(staging, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::policy::SizeThresholdPolicy;
+    use crate::StreamWriter;
+
+    #[cfg(feature = "xxhash")]
+    use crate::{
+        ChecksumDeframer, ChecksumFramer, DeframerExt, FramerExt, SizeValidator, XxHash64,
+    };
+
+    fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn async_writer_output_matches_sync_writer() {
+        let messages = ["alpha", "beta", "gamma"].map(String::from);
+        let mut sync_wire = Vec::new();
+        let mut sync_writer = StreamWriter::new(&mut sync_wire, DefaultFramer);
+        for m in &messages {
+            sync_writer.write(m).unwrap();
+        }
+
+        let async_wire = block_on(async {
+            let mut writer = AsyncStreamWriter::new(Vec::new(), DefaultFramer);
+            for m in &messages {
+                writer.write(m).await.unwrap();
+            }
+            writer.flush().await.unwrap();
+            writer.into_inner()
+        });
+        assert_eq!(async_wire, sync_wire);
+    }
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn checksummed_frames_round_trip_through_a_small_pipe() {
+        // A 7-byte duplex forces every frame to cross in several pieces.
+        let (client, server) = tokio::io::duplex(7);
+        let payloads: Vec<Vec<u8>> = block_on(async {
+            let write = async move {
+                let framer = ChecksumFramer::new(XxHash64::new()).bounded(1024);
+                let mut writer = AsyncStreamWriter::new(client, framer);
+                for s in ["one", "two", "three"] {
+                    writer.write(&s).await.unwrap();
+                }
+                writer.shutdown().await.unwrap();
+            };
+            let read = async move {
+                let deframer = ChecksumDeframer::new(XxHash64::new())
+                    .with_validator(SizeValidator::new(1, 1024));
+                let mut reader = AsyncStreamReader::new(server, deframer);
+                let mut out = Vec::new();
+                reader
+                    .process_all(|p| {
+                        out.push(p.to_vec());
+                        Ok(())
+                    })
+                    .await
+                    .unwrap();
+                out
+            };
+            tokio::join!(write, read).1
+        });
+        assert_eq!(payloads.len(), 3);
+        let root = flatbuffers::root::<&str>(&payloads[2]).unwrap();
+        assert_eq!(root, "three");
+    }
+
+    #[test]
+    fn clean_and_torn_endings() {
+        block_on(async {
+            let mut reader = AsyncStreamReader::new(&[][..], DefaultDeframer::new());
+            assert!(reader.read_message().await.unwrap().is_none());
+
+            let torn = [5u8, 0, 0, 0, b'a', b'b'];
+            let mut reader = AsyncStreamReader::new(&torn[..], DefaultDeframer::new());
+            let err = reader.read_message().await.unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+        });
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn deframing_reader_runs_plain_deframers_over_a_small_pipe() {
+        use crate::compression::{CompressingFramer, Compression, DecompressingDeframer};
+        use crate::DeframerBoxExt;
+
+        let framer = CompressingFramer::new(DefaultFramer, Compression::Lz4);
+        let mut wire = Vec::new();
+        let payloads = [vec![1u8; 40_000], b"short".to_vec(), vec![2u8; 3]];
+        for p in &payloads {
+            framer.frame_and_write(&mut wire, p).unwrap();
+        }
+        let (mut client, server) = tokio::io::duplex(7);
+        let read = block_on(async {
+            let write = async move {
+                client.write_all(&wire).await.unwrap();
+                client.shutdown().await.unwrap();
+            };
+            let read = async move {
+                let deframer = DecompressingDeframer::new(DefaultDeframer::new()).boxed();
+                let mut reader = AsyncDeframingReader::new(server, deframer);
+                let mut out = Vec::new();
+                reader
+                    .process_all(|p| {
+                        out.push(p.to_vec());
+                        Ok(())
+                    })
+                    .await
+                    .unwrap();
+                assert_eq!(reader.buffered_len(), 0);
+                out
+            };
+            tokio::join!(write, read).1
+        });
+        assert_eq!(read, payloads);
+    }
+
+    #[test]
+    fn deframing_reader_endings_and_rejected_frames() {
+        block_on(async {
+            let mut reader = AsyncDeframingReader::new(&[][..], DefaultDeframer::new());
+            assert!(reader.read_message().await.unwrap().is_none());
+
+            let torn = [5u8, 0, 0, 0, b'a', b'b'];
+            let mut reader = AsyncDeframingReader::new(&torn[..], DefaultDeframer::new());
+            let err = reader.read_message().await.unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+
+            // Frames the deframer rejects surface, as on the `Read` path.
+            let mut wire = Vec::new();
+            DefaultFramer
+                .frame_and_write(&mut wire, b"too long")
+                .unwrap();
+            DefaultFramer.frame_and_write(&mut wire, b"ok").unwrap();
+            let deframer = DefaultDeframer::new().with_max_frame_len(4);
+            let mut reader = AsyncDeframingReader::new(&wire[..], deframer);
+            let err = reader.read_message().await.unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        });
+    }
+
+    #[test]
+    fn reader_policy_shrinks_buffer_after_burst() {
+        let mut wire = Vec::new();
+        DefaultFramer
+            .frame_and_write(&mut wire, &[7u8; 64 * 1024])
+            .unwrap();
+        for _ in 0..4 {
+            DefaultFramer.frame_and_write(&mut wire, b"small").unwrap();
+        }
+        block_on(async {
+            let policy = SizeThresholdPolicy::new(32 * 1024, 1024, 2).with_baseline(1024);
+            let mut reader = AsyncStreamReader::new(&wire[..], DefaultDeframer::new())
+                .with_memory_policy(policy);
+            let mut sizes = Vec::new();
+            reader
+                .process_all(|p| {
+                    sizes.push(p.len());
+                    Ok(())
+                })
+                .await
+                .unwrap();
+            assert_eq!(sizes, [64 * 1024, 5, 5, 5, 5]);
+            assert!(reader.buffer_capacity() < 64 * 1024);
+        });
+    }
+}
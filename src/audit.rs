@@ -0,0 +1,386 @@
+//! Full-file integrity audits: check every frame, report every failure.
+//!
+//! Reading and recovery stop at the first bad frame, which is right for
+//! consuming a stream and useless for scrubbing one: a single flipped bit
+//! hides the state of everything behind it. An [`Auditor`] walks the whole
+//! stream and records, for every frame, its offset, payload length, checksum
+//! status and validator result in an [`AuditReport`]. Any [`Digest`] works,
+//! so streams framed with the wide digests are audited like the rest.
+//!
+//! A checksum or validation failure does not stop the audit: the length
+//! header that framed the bad payload was within bounds and the payload was
+//! present, so the next frame is assumed to start right after it. Only a
+//! length header that cannot be trusted (one above the configured bound)
+//! ends the audit early — from there on, frame boundaries are unknown. Use
+//! [`salvage`](fn@crate::salvage) to get frames out from behind such a
+//! header.
+
+use crate::digest::{Digest, MAX_DIGEST_LEN};
+use crate::error::{ErrorKind, Result};
+use crate::framing::{check_frame_len, read_header, read_payload, DEFAULT_MAX_FRAME_LEN};
+use crate::validation::{NoValidator, Validator};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Whether a frame's checksum matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// The stored checksum matches the payload.
+    Valid,
+    /// The stored checksum does not match the payload. Both fields hold the
+    /// digest's wire bytes, as in [`ErrorKind::DigestMismatch`]: for the
+    /// integer checksums, the little-endian value.
+    Mismatch {
+        /// Checksum stored in the frame.
+        expected: Box<[u8]>,
+        /// Checksum calculated over the payload.
+        calculated: Box<[u8]>,
+    },
+    /// The format carries no checksum (`NoChecksum`).
+    Absent,
+}
+
+/// The validator's verdict on a frame's payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationStatus {
+    /// The validator accepted the payload.
+    Valid,
+    /// The validator rejected the payload.
+    Failed {
+        /// Name of the rejecting validator.
+        validator: &'static str,
+        /// Why it rejected the payload.
+        reason: Cow<'static, str>,
+    },
+}
+
+/// The audit record for one frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameAudit {
+    /// Offset of the frame's length header.
+    pub offset: u64,
+    /// Declared (and present) payload length.
+    pub payload_len: u32,
+    /// Checksum verdict.
+    pub checksum: ChecksumStatus,
+    /// Validator verdict.
+    pub validation: ValidationStatus,
+}
+
+impl FrameAudit {
+    /// Whether the frame passed both its checksum and its validator.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self.checksum, ChecksumStatus::Mismatch { .. })
+            && self.validation == ValidationStatus::Valid
+    }
+}
+
+/// How an audit ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEnd {
+    /// The stream ended exactly on a frame boundary.
+    CleanEof,
+    /// The stream ends in a torn frame at [`AuditReport::end_offset`].
+    TornTail,
+    /// The length header at [`AuditReport::end_offset`] exceeds the
+    /// configured bound, so no later frame boundary can be trusted.
+    UntrustedLength {
+        /// The declared payload length.
+        declared_len: usize,
+        /// The configured maximum.
+        limit: usize,
+    },
+}
+
+/// The result of an audit: one record per frame, and how the walk ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    /// Every frame found, in stream order.
+    pub frames: Vec<FrameAudit>,
+    /// How the audit ended.
+    pub end: AuditEnd,
+    /// Offset just past the last complete frame: where the stream ends, the
+    /// torn frame starts, or the untrusted length header sits.
+    pub end_offset: u64,
+}
+
+impl AuditReport {
+    /// Whether every frame passed and the stream ended cleanly.
+    pub fn is_clean(&self) -> bool {
+        self.end == AuditEnd::CleanEof && self.frames.iter().all(FrameAudit::is_ok)
+    }
+
+    /// The frames that failed their checksum or validator.
+    pub fn bad_frames(&self) -> impl Iterator<Item = &FrameAudit> {
+        self.frames.iter().filter(|frame| !frame.is_ok())
+    }
+}
+
+/// Walks a stream end to end and records the health of every frame.
+///
+/// Configure it like the deframer that reads the stream: the same checksum
+/// algorithm or digest, optionally a validator, and a maximum frame length.
+///
+/// ```rust
+/// use flatstream::{AuditEnd, Auditor, DefaultFramer, Framer, NoChecksum, Result};
+///
+/// # fn main() -> Result<()> {
+/// let mut stream = Vec::new();
+/// DefaultFramer.frame_and_write(&mut stream, b"one")?;
+/// DefaultFramer.frame_and_write(&mut stream, b"two")?;
+///
+/// let report = Auditor::new(NoChecksum).audit(&stream[..])?;
+/// assert_eq!(report.frames.len(), 2);
+/// assert_eq!(report.end, AuditEnd::CleanEof);
+/// assert!(report.is_clean());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Auditor<C: Digest, V: Validator = NoValidator> {
+    checksum: C,
+    validator: V,
+    max_frame_len: usize,
+}
+
+impl<C: Digest> Auditor<C> {
+    /// An auditor for streams framed with `checksum`, without a validator,
+    /// accepting frames up to [`DEFAULT_MAX_FRAME_LEN`].
+    pub fn new(checksum: C) -> Self {
+        const { assert!(C::LEN <= MAX_DIGEST_LEN, "digest wider than MAX_DIGEST_LEN") };
+        Self {
+            checksum,
+            validator: NoValidator,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+}
+
+impl<C: Digest, V: Validator> Auditor<C, V> {
+    /// Runs `validator` on every payload, whatever its checksum status.
+    pub fn with_validator<V2: Validator>(self, validator: V2) -> Auditor<C, V2> {
+        Auditor {
+            checksum: self.checksum,
+            validator,
+            max_frame_len: self.max_frame_len,
+        }
+    }
+
+    /// Sets the largest payload length whose header is trusted.
+    pub fn with_max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+
+    /// Audits the stream `reader` from its current position; offsets in the
+    /// report are relative to it. Only I/O errors other than EOF fail the
+    /// audit itself.
+    pub fn audit<R: Read>(&self, mut reader: R) -> Result<AuditReport> {
+        let mut header = [0u8; 4 + MAX_DIGEST_LEN];
+        let header = &mut header[..4 + C::LEN];
+        let mut buffer = Vec::new();
+        let mut frames = Vec::new();
+        let mut offset = 0u64;
+        let end = loop {
+            match read_header(&mut reader, header) {
+                Ok(Some(())) => {}
+                Ok(None) => break AuditEnd::CleanEof,
+                Err(e) if matches!(e.kind(), ErrorKind::UnexpectedEof) => break AuditEnd::TornTail,
+                Err(e) => return Err(e),
+            }
+            let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap());
+            let len = payload_len as usize;
+            if check_frame_len(len, self.max_frame_len).is_err() {
+                break AuditEnd::UntrustedLength {
+                    declared_len: len,
+                    limit: self.max_frame_len,
+                };
+            }
+            match read_payload(&mut reader, &mut buffer, len) {
+                Ok(()) => {}
+                Err(e) if matches!(e.kind(), ErrorKind::UnexpectedEof) => break AuditEnd::TornTail,
+                Err(e) => return Err(e),
+            }
+            let payload = &buffer[..len];
+            frames.push(FrameAudit {
+                offset,
+                payload_len,
+                checksum: self.checksum_status(&header[4..], payload),
+                validation: self.validation_status(payload),
+            });
+            offset += (header.len() + len) as u64;
+        };
+        Ok(AuditReport {
+            frames,
+            end,
+            end_offset: offset,
+        })
+    }
+
+    /// Audits the file at `path` from its beginning.
+    pub fn verify_file<P: AsRef<Path>>(&self, path: P) -> Result<AuditReport> {
+        self.audit(BufReader::new(File::open(path)?))
+    }
+
+    fn checksum_status(&self, field: &[u8], payload: &[u8]) -> ChecksumStatus {
+        if C::LEN == 0 {
+            return ChecksumStatus::Absent;
+        }
+        let mut calculated = [0u8; MAX_DIGEST_LEN];
+        let calculated = &mut calculated[..C::LEN];
+        self.checksum.digest_into(payload, calculated);
+        if calculated == field {
+            ChecksumStatus::Valid
+        } else {
+            ChecksumStatus::Mismatch {
+                expected: field.into(),
+                calculated: (*calculated).into(),
+            }
+        }
+    }
+
+    fn validation_status(&self, payload: &[u8]) -> ValidationStatus {
+        match self.validator.validate(payload) {
+            Ok(()) => ValidationStatus::Valid,
+            Err(e) => match e.kind() {
+                ErrorKind::ValidationFailed { validator, reason } => ValidationStatus::Failed {
+                    validator,
+                    reason: reason.clone(),
+                },
+                _ => ValidationStatus::Failed {
+                    validator: self.validator.name(),
+                    reason: e.to_string().into(),
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::NoChecksum;
+    use crate::framing::{DefaultFramer, Framer};
+    use crate::validation::SizeValidator;
+
+    #[cfg(feature = "crc32")]
+    use crate::{checksum::Checksum, framing::ChecksumFramer, Crc32};
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn reports_every_bad_checksum_and_keeps_going() {
+        let framer = ChecksumFramer::new(Crc32::new());
+        let mut stream = Vec::new();
+        let mut offsets = Vec::new();
+        for payload in [&b"alpha"[..], b"bravo", b"charlie", b"delta"] {
+            offsets.push(stream.len() as u64);
+            framer.frame_and_write(&mut stream, payload).unwrap();
+        }
+        stream[offsets[1] as usize + 8] ^= 0x01;
+        stream[offsets[3] as usize + 9] ^= 0x80;
+
+        let report = Auditor::new(Crc32::new()).audit(&stream[..]).unwrap();
+        assert_eq!(report.end, AuditEnd::CleanEof);
+        assert_eq!(report.end_offset, stream.len() as u64);
+        assert_eq!(report.frames.len(), 4);
+        let bad: Vec<u64> = report.bad_frames().map(|f| f.offset).collect();
+        assert_eq!(bad, [offsets[1], offsets[3]]);
+        assert_eq!(report.frames[2].checksum, ChecksumStatus::Valid);
+        assert_eq!(report.frames[2].payload_len, 7);
+        assert!(!report.is_clean());
+
+        let ChecksumStatus::Mismatch {
+            expected,
+            calculated,
+        } = &report.frames[1].checksum
+        else {
+            panic!("expected a mismatch");
+        };
+        assert_eq!(&expected[..], &stream[offsets[1] as usize + 4..][..4]);
+        let damaged = &stream[offsets[1] as usize + 8..offsets[2] as usize];
+        let crc = Crc32::new().calculate(damaged) as u32;
+        assert_eq!(&calculated[..], crc.to_le_bytes());
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn audits_streams_framed_with_a_wide_digest() {
+        use crate::{framing::ChecksumFramer, Sha256};
+
+        let framer = ChecksumFramer::new(Sha256::new());
+        let mut stream = Vec::new();
+        framer.frame_and_write(&mut stream, b"intact").unwrap();
+        let second = stream.len();
+        framer.frame_and_write(&mut stream, b"damaged").unwrap();
+        let last = stream.len() - 1;
+        stream[last] ^= 0x20;
+
+        let report = Auditor::new(Sha256::new()).audit(&stream[..]).unwrap();
+        assert_eq!(report.end, AuditEnd::CleanEof);
+        assert_eq!(report.frames[0].checksum, ChecksumStatus::Valid);
+        let ChecksumStatus::Mismatch {
+            expected,
+            calculated,
+        } = &report.frames[1].checksum
+        else {
+            panic!("expected a mismatch");
+        };
+        assert_eq!(&expected[..], &stream[second + 4..second + 36]);
+        assert_eq!(calculated.len(), 32);
+        assert_ne!(expected, calculated);
+        assert_eq!(report.bad_frames().count(), 1);
+    }
+
+    #[test]
+    fn validator_failures_are_recorded_per_frame() {
+        let mut stream = Vec::new();
+        for payload in [&b"ok"[..], b"far too long", b"ok"] {
+            DefaultFramer.frame_and_write(&mut stream, payload).unwrap();
+        }
+        let report = Auditor::new(NoChecksum)
+            .with_validator(SizeValidator::new(0, 4))
+            .audit(&stream[..])
+            .unwrap();
+        assert_eq!(report.frames.len(), 3);
+        assert_eq!(report.frames[0].checksum, ChecksumStatus::Absent);
+        assert!(matches!(
+            report.frames[1].validation,
+            ValidationStatus::Failed {
+                validator: "SizeValidator",
+                ..
+            }
+        ));
+        assert_eq!(report.bad_frames().count(), 1);
+    }
+
+    #[test]
+    fn untrusted_length_and_torn_tail_end_the_audit() {
+        let mut stream = Vec::new();
+        DefaultFramer.frame_and_write(&mut stream, b"fine").unwrap();
+        let good_end = stream.len() as u64;
+        DefaultFramer
+            .frame_and_write(&mut stream, &[0u8; 100])
+            .unwrap();
+
+        let report = Auditor::new(NoChecksum)
+            .with_max_frame_len(64)
+            .audit(&stream[..])
+            .unwrap();
+        assert_eq!(report.frames.len(), 1);
+        assert_eq!(report.end_offset, good_end);
+        assert_eq!(
+            report.end,
+            AuditEnd::UntrustedLength {
+                declared_len: 100,
+                limit: 64
+            }
+        );
+
+        let report = Auditor::new(NoChecksum)
+            .audit(&stream[..stream.len() - 1])
+            .unwrap();
+        assert_eq!(report.end, AuditEnd::TornTail);
+        assert_eq!(report.end_offset, good_end);
+    }
+}
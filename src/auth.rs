@@ -0,0 +1,963 @@
+//! Authenticated and encrypted frames (features `hmac` and `aead`).
+//!
+//! The built-in checksums catch accidental corruption, but anyone who can
+//! modify a payload can also recompute its checksum. The adapters here key
+//! every frame instead:
+//!
+//! * [`HmacFramer`]/[`HmacDeframer`] append an HMAC-SHA256 tag; payloads
+//!   stay readable.
+//! * [`AeadFramer`]/[`AeadDeframer`] encrypt payloads with ChaCha20-Poly1305
+//!   or AES-256-GCM.
+//!
+//! Like the compression adapters they sit above the wire format: the inner
+//! framer sees the protected bytes as its payload. Those bytes are
+//!
+//! ```text
+//! [payload or ciphertext | u32 LE key id | u64 LE stream id | u64 LE sequence | tag]
+//! ```
+//!
+//! with a 32-byte HMAC tag over everything before it, or a 16-byte AEAD tag
+//! with the key id, stream id and sequence as associated data.
+//!
+//! Every frame carries the id of the key that protects it, so a writer can
+//! [rotate keys](HmacFramer::rotate_key) mid-stream and a reader holding
+//! several keys picks the right one per frame. The stream id is drawn at
+//! random when a framer is created, and sequence numbers count frames from
+//! 0. After verifying a frame's tag, a deframer checks that it continues the
+//! stream it is reading: a frame from another stream, a replayed or
+//! reordered frame, and a gap left by a removed frame are all rejected.
+//! Removing frames from the *end* of a stream cannot be detected by the
+//! frames themselves — compare the final [`position`](HmacDeframer::position)
+//! with what the writer reported if truncation is in scope.
+//!
+//! A frame the inner framer rejects before writing a byte (a bound or a
+//! validator) gives its sequence number back. A frame whose write fails
+//! after bytes reached the writer spends it, since its nonce may have left
+//! the process; the framer then refuses every later frame instead of writing
+//! a stream readers would stop at: start a new stream, or repair the file
+//! and [`resume`](HmacFramer::resume) it.
+//!
+//! Readers report a tag that does not verify as
+//! [`ErrorKind::AuthenticationFailed`](crate::error::ErrorKind::AuthenticationFailed),
+//! a key id they hold no key for as
+//! [`ErrorKind::UnknownKeyId`](crate::error::ErrorKind::UnknownKeyId), and a
+//! frame that does not continue the stream as
+//! [`ErrorKind::SequenceViolation`](crate::error::ErrorKind::SequenceViolation),
+//! so security failures can be told apart from malformed frames.
+//!
+//! AEAD frames are encrypted under a key derived from the configured key,
+//! its id and the stream id (HMAC-SHA256 as the derivation function), with
+//! the sequence number as the nonce. Nonces therefore never repeat under one
+//! derived key, whichever streams share the configured key.
+//!
+//! Deframers copy nothing: a frame is verified (and decrypted) in place in
+//! the reader's buffer. The frame position is deframer state, so there is no
+//! [`SliceDeframer`](crate::SliceDeframer) implementation.
+
+use crate::error::{Error, Result};
+use crate::framing::{Deframer, Framer};
+use crate::index::CountingWriter;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{Read, Write};
+use std::sync::{Mutex, MutexGuard};
+
+#[cfg(feature = "aead")]
+use aes_gcm::Aes256Gcm;
+#[cfg(feature = "aead")]
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+#[cfg(feature = "aead")]
+use chacha20poly1305::ChaCha20Poly1305;
+
+/// A 256-bit key, for HMAC-SHA256 and both AEAD algorithms alike.
+pub type Key = [u8; 32];
+
+/// Length of the fields every protected frame carries after its payload:
+/// key id, stream id and sequence number.
+pub const AUTH_FIELDS_LEN: usize = 20;
+
+/// Length of an HMAC-SHA256 tag.
+#[cfg(feature = "hmac")]
+pub const HMAC_TAG_LEN: usize = 32;
+
+/// Length of a ChaCha20-Poly1305 or AES-256-GCM tag.
+#[cfg(feature = "aead")]
+pub const AEAD_TAG_LEN: usize = 16;
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn keyed_hmac(key: &Key) -> HmacSha256 {
+    <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+/// Ignores poisoning: state is only updated once a frame has passed every
+/// check, so a panic cannot leave it half-changed.
+fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn random_stream_id() -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    getrandom::getrandom(&mut bytes).map_err(std::io::Error::from)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// The fields between a frame's payload and its tag.
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    key_id: u32,
+    stream_id: u64,
+    sequence: u64,
+}
+
+impl Fields {
+    fn to_bytes(self) -> [u8; AUTH_FIELDS_LEN] {
+        let mut bytes = [0u8; AUTH_FIELDS_LEN];
+        bytes[..4].copy_from_slice(&self.key_id.to_le_bytes());
+        bytes[4..12].copy_from_slice(&self.stream_id.to_le_bytes());
+        bytes[12..].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; AUTH_FIELDS_LEN]) -> Self {
+        Self {
+            key_id: u32::from_le_bytes(bytes[..4].try_into().unwrap()),
+            stream_id: u64::from_le_bytes(bytes[4..12].try_into().unwrap()),
+            sequence: u64::from_le_bytes(bytes[12..].try_into().unwrap()),
+        }
+    }
+
+    /// Splits `len` protected bytes into the payload, the fields and the
+    /// `tag_len`-byte tag, returning the payload length and the fields.
+    fn split(stored: &[u8], tag_len: usize) -> Result<(usize, Self)> {
+        let Some(payload_len) = stored.len().checked_sub(AUTH_FIELDS_LEN + tag_len) else {
+            return Err(Error::invalid_frame_with(
+                "authenticated frame too short for its trailer",
+                Some(stored.len()),
+                None,
+                None,
+            ));
+        };
+        let fields = stored[payload_len..payload_len + AUTH_FIELDS_LEN]
+            .try_into()
+            .unwrap();
+        Ok((payload_len, Self::from_bytes(fields)))
+    }
+}
+
+/// Claims the next sequence number of a writer.
+fn claim_sequence(next: &mut u64, failed: bool) -> Result<u64> {
+    if failed {
+        return Err(Error::invalid_frame(
+            "an earlier frame failed after claiming its sequence number; start a new stream",
+        ));
+    }
+    if *next == u64::MAX {
+        return Err(Error::invalid_frame(
+            "stream has used every sequence number; start a new stream",
+        ));
+    }
+    *next += 1;
+    Ok(*next - 1)
+}
+
+/// Settles the sequence number claimed for a frame that failed to write. If
+/// no byte reached the writer — the inner framer rejected the payload — the
+/// tag, nonce and number never left the process and the number is handed
+/// back. Otherwise it is spent: reusing it could repeat a nonce, and
+/// skipping it leaves a gap every reader rejects, so the writer refuses to
+/// continue.
+fn release_sequence(next: &mut u64, failed: &mut bool, bytes_written: u64) {
+    if bytes_written == 0 {
+        *next -= 1;
+    } else {
+        *failed = true;
+    }
+}
+
+/// Checks that an authentic frame continues the stream at `position`
+/// (stream id, next sequence number), returning the position after it.
+fn advance(position: Option<(u64, u64)>, fields: &Fields) -> Result<(u64, u64)> {
+    let found = (fields.stream_id, fields.sequence);
+    let expected = position.unwrap_or((fields.stream_id, 0));
+    if found != expected {
+        return Err(Error::sequence_violation(found, expected));
+    }
+    Ok((fields.stream_id, fields.sequence.saturating_add(1)))
+}
+
+//--- HMAC-SHA256 ---
+
+#[cfg(feature = "hmac")]
+struct HmacWriterState {
+    key_id: u32,
+    mac: HmacSha256,
+    next_sequence: u64,
+    failed: bool,
+    stored: Vec<u8>,
+}
+
+/// A framer adapter that appends an HMAC-SHA256 tag to every payload.
+///
+/// ```rust
+/// # #[cfg(feature = "hmac")] {
+/// use flatstream::auth::{HmacDeframer, HmacFramer};
+/// use flatstream::{DefaultDeframer, DefaultFramer, StreamReader, StreamWriter};
+///
+/// let key = [7u8; 32];
+/// let mut stream = Vec::new();
+/// let mut writer = StreamWriter::new(&mut stream, HmacFramer::new(DefaultFramer, 1, &key).unwrap());
+/// writer.write_payload(b"reading 1").unwrap();
+/// writer.framer().rotate_key(2, &[9u8; 32]);
+/// writer.write_payload(b"reading 2").unwrap();
+///
+/// let deframer = HmacDeframer::new(DefaultDeframer::new())
+///     .with_key(1, &key)
+///     .with_key(2, &[9u8; 32]);
+/// let mut reader = StreamReader::new(&stream[..], deframer);
+/// assert_eq!(reader.read_message().unwrap().unwrap(), b"reading 1");
+/// assert_eq!(reader.read_message().unwrap().unwrap(), b"reading 2");
+/// # }
+/// ```
+#[cfg(feature = "hmac")]
+pub struct HmacFramer<F: Framer> {
+    inner: F,
+    stream_id: u64,
+    state: Mutex<HmacWriterState>,
+}
+
+#[cfg(feature = "hmac")]
+impl<F: Framer> HmacFramer<F> {
+    /// Starts a new stream, with a random stream id, tagging frames with
+    /// `key`. Fails only if the operating system's random source does.
+    pub fn new(inner: F, key_id: u32, key: &Key) -> Result<Self> {
+        Ok(Self::resume(inner, key_id, key, random_stream_id()?, 0))
+    }
+
+    /// Continues an existing stream — e.g. a reopened journal — at
+    /// `next_sequence`, which a deframer that read the stream reports as its
+    /// [`position`](HmacDeframer::position).
+    pub fn resume(inner: F, key_id: u32, key: &Key, stream_id: u64, next_sequence: u64) -> Self {
+        Self {
+            inner,
+            stream_id,
+            state: Mutex::new(HmacWriterState {
+                key_id,
+                mac: keyed_hmac(key),
+                next_sequence,
+                failed: false,
+                stored: Vec::new(),
+            }),
+        }
+    }
+
+    /// Tags every following frame with `key`, recording `key_id` in it.
+    pub fn rotate_key(&self, key_id: u32, key: &Key) {
+        let mut state = lock(&self.state);
+        state.key_id = key_id;
+        state.mac = keyed_hmac(key);
+    }
+
+    /// The id of the stream this framer writes.
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+
+    /// The sequence number of the next frame.
+    pub fn next_sequence(&self) -> u64 {
+        lock(&self.state).next_sequence
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl<F: Framer> Framer for HmacFramer<F> {
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        let mut guard = lock(&self.state);
+        let state = &mut *guard;
+        let fields = Fields {
+            key_id: state.key_id,
+            stream_id: self.stream_id,
+            sequence: claim_sequence(&mut state.next_sequence, state.failed)?,
+        };
+        let mut mac = state.mac.clone();
+        let stored = &mut state.stored;
+        stored.clear();
+        stored.extend_from_slice(payload);
+        stored.extend_from_slice(&fields.to_bytes());
+        mac.update(stored);
+        stored.extend_from_slice(&mac.finalize().into_bytes());
+        let mut counter = CountingWriter::new(writer);
+        let result = self.inner.frame_and_write(&mut counter, stored);
+        if result.is_err() {
+            release_sequence(&mut state.next_sequence, &mut state.failed, counter.count);
+        }
+        result
+    }
+
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        self.inner.holds_back_payloads()
+    }
+}
+
+/// A deframer adapter that verifies frames written by an [`HmacFramer`]
+/// and checks that they continue the stream in order.
+///
+/// A new deframer expects the first frame of a stream (sequence 0) and then
+/// follows that stream; [`with_stream`](Self::with_stream) starts it
+/// elsewhere.
+#[cfg(feature = "hmac")]
+pub struct HmacDeframer<D: Deframer> {
+    inner: D,
+    keys: Vec<(u32, HmacSha256)>,
+    position: Mutex<Option<(u64, u64)>>,
+}
+
+#[cfg(feature = "hmac")]
+impl<D: Deframer> HmacDeframer<D> {
+    /// Wraps `inner`. Add the keys it may meet with
+    /// [`with_key`](Self::with_key).
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            keys: Vec::new(),
+            position: Mutex::new(None),
+        }
+    }
+
+    /// Accepts frames tagged with `key` under `key_id`, replacing any key
+    /// already registered under that id.
+    pub fn with_key(mut self, key_id: u32, key: &Key) -> Self {
+        self.keys.retain(|(id, _)| *id != key_id);
+        self.keys.push((key_id, keyed_hmac(key)));
+        self
+    }
+
+    /// Expects the next frame to be frame `next_sequence` of stream
+    /// `stream_id`, for reading from the middle of a stream.
+    pub fn with_stream(self, stream_id: u64, next_sequence: u64) -> Self {
+        *lock(&self.position) = Some((stream_id, next_sequence));
+        self
+    }
+
+    /// The stream read so far and the sequence number of its next frame, or
+    /// `None` before the first frame.
+    pub fn position(&self) -> Option<(u64, u64)> {
+        *lock(&self.position)
+    }
+
+    fn verify(&self, buffer: &[u8], len: usize) -> Result<usize> {
+        let stored = &buffer[..len];
+        let (payload_len, fields) = Fields::split(stored, HMAC_TAG_LEN)?;
+        let Some((_, mac)) = self.keys.iter().find(|(id, _)| *id == fields.key_id) else {
+            return Err(Error::unknown_key_id(fields.key_id));
+        };
+        let (signed, tag) = stored.split_at(payload_len + AUTH_FIELDS_LEN);
+        let mut mac = mac.clone();
+        mac.update(signed);
+        mac.verify_slice(tag)
+            .map_err(|_| Error::authentication_failed())?;
+
+        let mut position = lock(&self.position);
+        *position = Some(advance(*position, &fields)?);
+        Ok(payload_len)
+    }
+}
+
+#[cfg(feature = "hmac")]
+impl<D: Deframer> Deframer for HmacDeframer<D> {
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        match self.inner.read_and_deframe(reader, buffer)? {
+            Some(len) => self.verify(buffer, len).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        match self.inner.read_after_length(reader, buffer, payload_len)? {
+            Some(len) => self.verify(buffer, len).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+//--- AEAD ---
+
+/// The authenticated encryption algorithm of an [`AeadFramer`].
+#[cfg(feature = "aead")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aead {
+    /// ChaCha20-Poly1305 (RFC 8439): fast everywhere, constant-time in
+    /// software.
+    ChaCha20Poly1305,
+    /// AES-256-GCM: fastest where the CPU has AES instructions.
+    Aes256Gcm,
+}
+
+#[cfg(feature = "aead")]
+enum Cipher {
+    ChaCha20Poly1305(ChaCha20Poly1305),
+    Aes256Gcm(Box<Aes256Gcm>),
+}
+
+#[cfg(feature = "aead")]
+impl Cipher {
+    /// The cipher for one stream: keyed with HMAC-SHA256(key, label | key
+    /// id | stream id).
+    fn for_stream(aead: Aead, key: &Key, key_id: u32, stream_id: u64) -> Self {
+        let mut kdf = keyed_hmac(key);
+        kdf.update(b"flatstream aead stream key v1");
+        kdf.update(&key_id.to_le_bytes());
+        kdf.update(&stream_id.to_le_bytes());
+        let stream_key = kdf.finalize().into_bytes();
+        match aead {
+            Aead::ChaCha20Poly1305 => Cipher::ChaCha20Poly1305(ChaCha20Poly1305::new(&stream_key)),
+            Aead::Aes256Gcm => Cipher::Aes256Gcm(Box::new(Aes256Gcm::new(&stream_key))),
+        }
+    }
+
+    fn nonce(sequence: u64) -> chacha20poly1305::Nonce {
+        let mut nonce = chacha20poly1305::Nonce::default();
+        nonce[..8].copy_from_slice(&sequence.to_le_bytes());
+        nonce
+    }
+
+    fn encrypt(&self, fields: &Fields, data: &mut [u8]) -> Result<[u8; AEAD_TAG_LEN]> {
+        let nonce = Self::nonce(fields.sequence);
+        let aad = fields.to_bytes();
+        let tag = match self {
+            Cipher::ChaCha20Poly1305(c) => c.encrypt_in_place_detached(&nonce, &aad, data),
+            Cipher::Aes256Gcm(c) => c.encrypt_in_place_detached(&nonce, &aad, data),
+        }
+        .map_err(|_| Error::invalid_frame("payload too long to encrypt"))?;
+        Ok(tag.into())
+    }
+
+    fn decrypt(&self, fields: &Fields, data: &mut [u8], tag: &[u8; AEAD_TAG_LEN]) -> Result<()> {
+        let nonce = Self::nonce(fields.sequence);
+        let aad = fields.to_bytes();
+        let tag = chacha20poly1305::Tag::from(*tag);
+        match self {
+            Cipher::ChaCha20Poly1305(c) => c.decrypt_in_place_detached(&nonce, &aad, data, &tag),
+            Cipher::Aes256Gcm(c) => c.decrypt_in_place_detached(&nonce, &aad, data, &tag),
+        }
+        .map_err(|_| Error::authentication_failed())
+    }
+}
+
+#[cfg(feature = "aead")]
+struct AeadWriterState {
+    key_id: u32,
+    cipher: Cipher,
+    next_sequence: u64,
+    failed: bool,
+    stored: Vec<u8>,
+}
+
+/// A framer adapter that encrypts every payload with an AEAD algorithm.
+///
+/// ```rust
+/// # #[cfg(feature = "aead")] {
+/// use flatstream::auth::{Aead, AeadDeframer, AeadFramer};
+/// use flatstream::{DefaultDeframer, DefaultFramer, StreamReader, StreamWriter};
+///
+/// let key = [7u8; 32];
+/// let framer = AeadFramer::new(DefaultFramer, Aead::ChaCha20Poly1305, 1, &key).unwrap();
+/// let mut stream = Vec::new();
+/// StreamWriter::new(&mut stream, framer).write_payload(b"lat=52.52,lon=13.40").unwrap();
+/// assert!(!stream.windows(3).any(|w| w == b"lat"));
+///
+/// let deframer = AeadDeframer::new(DefaultDeframer::new(), Aead::ChaCha20Poly1305).with_key(1, &key);
+/// let mut reader = StreamReader::new(&stream[..], deframer);
+/// assert_eq!(reader.read_message().unwrap().unwrap(), b"lat=52.52,lon=13.40");
+/// # }
+/// ```
+#[cfg(feature = "aead")]
+pub struct AeadFramer<F: Framer> {
+    inner: F,
+    aead: Aead,
+    stream_id: u64,
+    state: Mutex<AeadWriterState>,
+}
+
+#[cfg(feature = "aead")]
+impl<F: Framer> AeadFramer<F> {
+    /// Starts a new stream, with a random stream id, encrypting frames
+    /// under `key`. Fails only if the operating system's random source does.
+    pub fn new(inner: F, aead: Aead, key_id: u32, key: &Key) -> Result<Self> {
+        Ok(Self::resume(
+            inner,
+            aead,
+            key_id,
+            key,
+            random_stream_id()?,
+            0,
+        ))
+    }
+
+    /// Continues an existing stream — e.g. a reopened journal — at
+    /// `next_sequence`, which a deframer that read the stream reports as its
+    /// [`position`](AeadDeframer::position).
+    ///
+    /// Never resume a stream at a sequence number it has already used: the
+    /// frame would reuse a nonce, which breaks the encryption of both.
+    pub fn resume(
+        inner: F,
+        aead: Aead,
+        key_id: u32,
+        key: &Key,
+        stream_id: u64,
+        next_sequence: u64,
+    ) -> Self {
+        Self {
+            inner,
+            aead,
+            stream_id,
+            state: Mutex::new(AeadWriterState {
+                key_id,
+                cipher: Cipher::for_stream(aead, key, key_id, stream_id),
+                next_sequence,
+                failed: false,
+                stored: Vec::new(),
+            }),
+        }
+    }
+
+    /// Encrypts every following frame under `key`, recording `key_id` in it.
+    pub fn rotate_key(&self, key_id: u32, key: &Key) {
+        let cipher = Cipher::for_stream(self.aead, key, key_id, self.stream_id);
+        let mut state = lock(&self.state);
+        state.key_id = key_id;
+        state.cipher = cipher;
+    }
+
+    /// The id of the stream this framer writes.
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+
+    /// The sequence number of the next frame.
+    pub fn next_sequence(&self) -> u64 {
+        lock(&self.state).next_sequence
+    }
+}
+
+#[cfg(feature = "aead")]
+impl<F: Framer> Framer for AeadFramer<F> {
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        let mut guard = lock(&self.state);
+        let state = &mut *guard;
+        let fields = Fields {
+            key_id: state.key_id,
+            stream_id: self.stream_id,
+            sequence: claim_sequence(&mut state.next_sequence, state.failed)?,
+        };
+        let stored = &mut state.stored;
+        stored.clear();
+        stored.extend_from_slice(payload);
+        let mut counter = CountingWriter::new(writer);
+        let result = state.cipher.encrypt(&fields, stored).and_then(|tag| {
+            stored.extend_from_slice(&fields.to_bytes());
+            stored.extend_from_slice(&tag);
+            self.inner.frame_and_write(&mut counter, stored)
+        });
+        if result.is_err() {
+            release_sequence(&mut state.next_sequence, &mut state.failed, counter.count);
+        }
+        result
+    }
+
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        self.inner.holds_back_payloads()
+    }
+}
+
+#[cfg(feature = "aead")]
+struct AeadReaderState {
+    position: Option<(u64, u64)>,
+    /// The cipher of the last frame's key and stream, with their ids.
+    cipher: Option<(u32, u64, Cipher)>,
+}
+
+/// A deframer adapter that decrypts frames written by an [`AeadFramer`]
+/// with the same algorithm and checks that they continue the stream in
+/// order.
+///
+/// A new deframer expects the first frame of a stream (sequence 0) and then
+/// follows that stream; [`with_stream`](Self::with_stream) starts it
+/// elsewhere. Payloads are decrypted in place.
+#[cfg(feature = "aead")]
+pub struct AeadDeframer<D: Deframer> {
+    inner: D,
+    aead: Aead,
+    keys: Vec<(u32, Key)>,
+    state: Mutex<AeadReaderState>,
+}
+
+#[cfg(feature = "aead")]
+impl<D: Deframer> AeadDeframer<D> {
+    /// Wraps `inner`. Add the keys it may meet with
+    /// [`with_key`](Self::with_key).
+    pub fn new(inner: D, aead: Aead) -> Self {
+        Self {
+            inner,
+            aead,
+            keys: Vec::new(),
+            state: Mutex::new(AeadReaderState {
+                position: None,
+                cipher: None,
+            }),
+        }
+    }
+
+    /// Accepts frames encrypted under `key` with `key_id`, replacing any key
+    /// already registered under that id.
+    pub fn with_key(mut self, key_id: u32, key: &Key) -> Self {
+        self.keys.retain(|(id, _)| *id != key_id);
+        self.keys.push((key_id, *key));
+        self
+    }
+
+    /// Expects the next frame to be frame `next_sequence` of stream
+    /// `stream_id`, for reading from the middle of a stream.
+    pub fn with_stream(self, stream_id: u64, next_sequence: u64) -> Self {
+        lock(&self.state).position = Some((stream_id, next_sequence));
+        self
+    }
+
+    /// The stream read so far and the sequence number of its next frame, or
+    /// `None` before the first frame.
+    pub fn position(&self) -> Option<(u64, u64)> {
+        lock(&self.state).position
+    }
+
+    fn open(&self, buffer: &mut [u8], len: usize) -> Result<usize> {
+        let (payload_len, fields) = Fields::split(&buffer[..len], AEAD_TAG_LEN)?;
+        let mut state = lock(&self.state);
+        let cached = matches!(
+            &state.cipher,
+            Some((key_id, stream_id, _))
+                if *key_id == fields.key_id && *stream_id == fields.stream_id
+        );
+        if !cached {
+            let Some((_, key)) = self.keys.iter().find(|(id, _)| *id == fields.key_id) else {
+                return Err(Error::unknown_key_id(fields.key_id));
+            };
+            let cipher = Cipher::for_stream(self.aead, key, fields.key_id, fields.stream_id);
+            state.cipher = Some((fields.key_id, fields.stream_id, cipher));
+        }
+        let (_, _, cipher) = state.cipher.as_ref().unwrap();
+        let (data, trailer) = buffer[..len].split_at_mut(payload_len);
+        let tag = trailer[AUTH_FIELDS_LEN..].try_into().unwrap();
+        cipher.decrypt(&fields, data, tag)?;
+
+        state.position = Some(advance(state.position, &fields)?);
+        Ok(payload_len)
+    }
+}
+
+#[cfg(feature = "aead")]
+impl<D: Deframer> Deframer for AeadDeframer<D> {
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        match self.inner.read_and_deframe(reader, buffer)? {
+            Some(len) => self.open(buffer, len).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        match self.inner.read_after_length(reader, buffer, payload_len)? {
+            Some(len) => self.open(buffer, len).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+
+    const KEY: Key = [0x42; 32];
+    const NEXT_KEY: Key = [0x43; 32];
+
+    /// Writes each payload as a separately returned frame.
+    fn frames<F: Framer>(framer: &F, payloads: &[&[u8]]) -> Vec<Vec<u8>> {
+        payloads
+            .iter()
+            .map(|payload| {
+                let mut frame = Vec::new();
+                framer.frame_and_write(&mut frame, payload).unwrap();
+                frame
+            })
+            .collect()
+    }
+
+    /// Reads the frames in the given order, returning the payloads read
+    /// before the first error and that error's kind.
+    fn read<D: Deframer>(deframer: D, frames: &[&Vec<u8>]) -> (Vec<Vec<u8>>, Option<ErrorKind>) {
+        let stream: Vec<u8> = frames.iter().flat_map(|f| f.iter().copied()).collect();
+        let mut reader = StreamReader::new(&stream[..], deframer);
+        let mut payloads = Vec::new();
+        loop {
+            match reader.read_message() {
+                Ok(Some(payload)) => payloads.push(payload.to_vec()),
+                Ok(None) => return (payloads, None),
+                Err(e) => return (payloads, Some(e.into_kind())),
+            }
+        }
+    }
+
+    #[cfg(feature = "hmac")]
+    fn hmac_deframer() -> HmacDeframer<DefaultDeframer> {
+        HmacDeframer::new(DefaultDeframer::new())
+            .with_key(1, &KEY)
+            .with_key(2, &NEXT_KEY)
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn hmac_frames_round_trip_across_key_rotation() {
+        let framer = HmacFramer::new(DefaultFramer, 1, &KEY).unwrap();
+        let mut all = frames(&framer, &[b"a", b""]);
+        framer.rotate_key(2, &NEXT_KEY);
+        all.extend(frames(&framer, &[b"ccc"]));
+        assert_eq!(framer.next_sequence(), 3);
+        assert_eq!(all[0].len(), 4 + 1 + AUTH_FIELDS_LEN + HMAC_TAG_LEN);
+
+        let deframer = hmac_deframer();
+        let stream: Vec<u8> = all.concat();
+        let mut reader = StreamReader::new(&stream[..], deframer);
+        let mut payloads = Vec::new();
+        reader
+            .process_all(|payload| {
+                payloads.push(payload.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(payloads, [&b"a"[..], b"", b"ccc"]);
+        assert_eq!(reader.deframer().position(), Some((framer.stream_id(), 3)));
+
+        // A reader without the rotated-in key stops at the first frame it
+        // protects.
+        let old_keys_only = HmacDeframer::new(DefaultDeframer::new()).with_key(1, &KEY);
+        let (read, err) = read(old_keys_only, &all.iter().collect::<Vec<_>>());
+        assert_eq!(read.len(), 2);
+        assert!(matches!(err, Some(ErrorKind::UnknownKeyId { key_id: 2 })));
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn hmac_rejects_tampered_frames() {
+        let framer = HmacFramer::new(DefaultFramer, 1, &KEY).unwrap();
+        let frame = frames(&framer, &[b"temperature=21"]).remove(0);
+        // Flip a payload byte, then a sequence byte, then a tag byte.
+        for at in [4, 4 + 14 + 12, frame.len() - 1] {
+            let mut tampered = frame.clone();
+            tampered[at] ^= 0x01;
+            let (read, err) = read(hmac_deframer(), &[&tampered]);
+            assert!(read.is_empty());
+            assert!(matches!(err, Some(ErrorKind::AuthenticationFailed)));
+        }
+        let wrong_key = HmacDeframer::new(DefaultDeframer::new()).with_key(1, &NEXT_KEY);
+        assert!(read(wrong_key, &[&frame]).1.is_some());
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn hmac_rejects_replayed_reordered_and_spliced_frames() {
+        let framer = HmacFramer::new(DefaultFramer, 1, &KEY).unwrap();
+        let f = frames(&framer, &[b"0", b"1", b"2"]);
+
+        let stream = framer.stream_id();
+        let (read_ok, err) = read(hmac_deframer(), &[&f[0], &f[1], &f[1]]);
+        assert_eq!(read_ok.len(), 2);
+        assert!(matches!(
+            err,
+            Some(ErrorKind::SequenceViolation { found, expected })
+                if found == (stream, 1) && expected == (stream, 2)
+        ));
+
+        let (read_ok, err) = read(hmac_deframer(), &[&f[0], &f[2], &f[1]]);
+        assert_eq!(read_ok.len(), 1);
+        assert!(matches!(
+            err,
+            Some(ErrorKind::SequenceViolation { found, expected })
+                if found == (stream, 2) && expected == (stream, 1)
+        ));
+
+        // A stream must start at its first frame, unless told otherwise.
+        assert!(read(hmac_deframer(), &[&f[1]]).1.is_some());
+        let mid = hmac_deframer().with_stream(framer.stream_id(), 1);
+        let (read_ok, err) = read(mid, &[&f[1], &f[2]]);
+        assert_eq!(read_ok, [b"1".to_vec(), b"2".to_vec()]);
+        assert!(err.is_none());
+
+        // A genuine frame of another stream under the same key.
+        let other = HmacFramer::new(DefaultFramer, 1, &KEY).unwrap();
+        let g = frames(&other, &[b"0", b"1"]);
+        let (read_ok, err) = read(hmac_deframer(), &[&f[0], &g[1]]);
+        assert_eq!(read_ok.len(), 1);
+        assert!(matches!(
+            err,
+            Some(ErrorKind::SequenceViolation { found, expected })
+                if found == (other.stream_id(), 1) && expected == (stream, 1)
+        ));
+
+        // Resuming continues the sequence.
+        let resumed = HmacFramer::resume(DefaultFramer, 1, &KEY, framer.stream_id(), 3);
+        let h = frames(&resumed, &[b"3"]);
+        let (read_ok, err) = read(hmac_deframer(), &[&f[0], &f[1], &f[2], &h[0]]);
+        assert_eq!(read_ok.len(), 4);
+        assert!(err.is_none());
+    }
+
+    /// Writes `ok`, a payload the inner bound rejects and `ok` again, then a
+    /// frame that fails after reaching the writer; the framer must give the
+    /// rejected frame's number back but refuse to write past the torn one.
+    fn gives_back_rejected_numbers_only<F: Framer>(framer: F) -> Vec<u8> {
+        let mut stream = Vec::new();
+        framer.frame_and_write(&mut stream, b"ok").unwrap();
+        let err = framer.frame_and_write(&mut stream, &[0; 200]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        framer.frame_and_write(&mut stream, b"ok").unwrap();
+
+        let mut full = [0u8; 8];
+        let err = framer
+            .frame_and_write(&mut &mut full[..], b"ok")
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Io(_)));
+        let err = framer.frame_and_write(&mut stream, b"ok").unwrap_err();
+        assert!(err.to_string().contains("start a new stream"), "{err}");
+        stream
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn hmac_framer_settles_sequence_numbers_of_failed_frames() {
+        use crate::framing::FramerExt;
+
+        let framer = HmacFramer::new(DefaultFramer.bounded(128), 1, &KEY).unwrap();
+        let stream = gives_back_rejected_numbers_only(framer);
+        let (read_ok, err) = read(hmac_deframer(), &[&stream]);
+        assert_eq!(read_ok, [b"ok".to_vec(), b"ok".to_vec()]);
+        assert!(err.is_none());
+    }
+
+    #[cfg(feature = "aead")]
+    #[test]
+    fn aead_framer_settles_sequence_numbers_of_failed_frames() {
+        use crate::framing::FramerExt;
+
+        let aead = Aead::ChaCha20Poly1305;
+        let framer = AeadFramer::new(DefaultFramer.bounded(128), aead, 1, &KEY).unwrap();
+        let stream = gives_back_rejected_numbers_only(framer);
+        let deframer = AeadDeframer::new(DefaultDeframer::new(), aead).with_key(1, &KEY);
+        let (read_ok, err) = read(deframer, &[&stream]);
+        assert_eq!(read_ok, [b"ok".to_vec(), b"ok".to_vec()]);
+        assert!(err.is_none());
+    }
+
+    #[cfg(feature = "aead")]
+    fn aead_round_trip(aead: Aead) {
+        let framer = AeadFramer::new(DefaultFramer, aead, 1, &KEY).unwrap();
+        let secret = b"lat=52.5200,lon=13.4050";
+        let mut f = frames(&framer, &[secret, b""]);
+        framer.rotate_key(2, &NEXT_KEY);
+        f.extend(frames(&framer, &[secret]));
+        assert_eq!(
+            f[0].len(),
+            4 + secret.len() + AUTH_FIELDS_LEN + AEAD_TAG_LEN
+        );
+        // Same plaintext, different sequence and key: unrelated ciphertexts.
+        assert!(!f[0].windows(3).any(|w| w == b"lat"));
+        assert_ne!(f[0][4..4 + secret.len()], f[2][4..4 + secret.len()]);
+
+        let deframer = || {
+            AeadDeframer::new(DefaultDeframer::new(), aead)
+                .with_key(1, &KEY)
+                .with_key(2, &NEXT_KEY)
+        };
+        let (payloads, err) = read(deframer(), &f.iter().collect::<Vec<_>>());
+        assert!(err.is_none());
+        assert_eq!(payloads, [&secret[..], b"", secret]);
+
+        let mut tampered = f[0].clone();
+        tampered[5] ^= 0x01;
+        assert!(matches!(
+            read(deframer(), &[&tampered]).1,
+            Some(ErrorKind::AuthenticationFailed)
+        ));
+
+        let (payloads, err) = read(deframer(), &[&f[0], &f[0]]);
+        assert_eq!(payloads.len(), 1);
+        assert!(matches!(err, Some(ErrorKind::SequenceViolation { .. })));
+    }
+
+    #[cfg(feature = "aead")]
+    #[test]
+    fn chacha20poly1305_round_trips_and_rejects_tampering_and_replay() {
+        aead_round_trip(Aead::ChaCha20Poly1305);
+    }
+
+    #[cfg(feature = "aead")]
+    #[test]
+    fn aes256gcm_round_trips_and_rejects_tampering_and_replay() {
+        aead_round_trip(Aead::Aes256Gcm);
+    }
+
+    #[cfg(feature = "aead")]
+    #[test]
+    fn aead_algorithms_and_streams_do_not_mix() {
+        let framer = AeadFramer::new(DefaultFramer, Aead::Aes256Gcm, 1, &KEY).unwrap();
+        let f = frames(&framer, &[b"payload"]);
+        let chacha =
+            AeadDeframer::new(DefaultDeframer::new(), Aead::ChaCha20Poly1305).with_key(1, &KEY);
+        assert!(matches!(
+            read(chacha, &[&f[0]]).1,
+            Some(ErrorKind::AuthenticationFailed)
+        ));
+
+        // A resumed writer derives the same stream key; a new one does not.
+        let resumed = AeadFramer::resume(
+            DefaultFramer,
+            Aead::Aes256Gcm,
+            1,
+            &KEY,
+            framer.stream_id(),
+            1,
+        );
+        let g = frames(&resumed, &[b"next"]);
+        let gcm = || AeadDeframer::new(DefaultDeframer::new(), Aead::Aes256Gcm).with_key(1, &KEY);
+        assert!(read(gcm(), &[&f[0], &g[0]]).1.is_none());
+        let fresh = AeadFramer::new(DefaultFramer, Aead::Aes256Gcm, 1, &KEY).unwrap();
+        assert_ne!(fresh.stream_id(), framer.stream_id());
+    }
+}
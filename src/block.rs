@@ -0,0 +1,596 @@
+//! Block compression of frame batches (features `lz4` and `zstd`).
+//!
+//! Per-frame compression ([`crate::compression`]) has little to work with in
+//! a 100-byte payload. [`BlockFramer`] instead collects consecutive payloads
+//! until a target block size is reached, compresses the block as a unit and
+//! hands it to an inner framer as one frame; [`BlockDeframer`] reads a block
+//! through the matching inner deframer and yields its payloads one at a
+//! time, so `StreamReader::process_all` and `messages()` see the original
+//! frames.
+//!
+//! The stored bytes of a block frame:
+//!
+//! ```text
+//! [flag u8 | u32 LE frame count N | u32 LE block length | N × u32 LE end offset | body]
+//! ```
+//!
+//! The block is the concatenation of the payloads; payload `i` spans
+//! `end[i-1]..end[i]` of it (`0..end[0]` for the first), and the last end
+//! offset equals the block length. The body is the block stored as is (flag
+//! `0x00`, chosen whenever compression would not make it smaller) or
+//! compressed (`0x01` LZ4 block, `0x02` zstd frame). The header stays
+//! uncompressed so the block length is bounded, and the offsets checked,
+//! before anything is decompressed.
+//!
+//! The block is the unit of everything below the adapters: one checksum
+//! covers a block, a torn tail loses the whole last block, and offsets
+//! reported by recovery or audits are block boundaries. Payloads are held
+//! back until a block fills up or the writer is flushed
+//! ([`Framer::flush_pending`]), so a crash loses the unflushed part of the
+//! current block. A [`FrameIndex`](crate::FrameIndex) needs one frame per
+//! payload, so an indexing `StreamWriter` rejects a block framer.
+
+use crate::compression::{decompress_to_vec, Compression, FLAG_STORED};
+use crate::error::{Error, Result};
+use crate::framing::{Deframer, Framer};
+use std::io::{Read, Write};
+use std::sync::{Mutex, MutexGuard};
+
+/// Block size at which a [`BlockFramer`] writes a block, unless configured
+/// otherwise: 64 KiB.
+pub const DEFAULT_TARGET_BLOCK_LEN: usize = 64 * 1024;
+
+/// Largest uncompressed block a [`BlockDeframer`] accepts unless configured
+/// otherwise: 64 MiB, a thousand default-sized blocks.
+pub const DEFAULT_MAX_BLOCK_LEN: usize = 64 * 1024 * 1024;
+
+/// Length of the fixed part of a block header: flag, frame count and block
+/// length.
+const FIXED_HEADER_LEN: usize = 9;
+
+/// Locks adapter state. Every update leaves the buffers consistent before
+/// anything that can panic runs, so poisoning is ignored.
+fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Default)]
+struct PendingBlock {
+    block: Vec<u8>,
+    ends: Vec<u32>,
+    compressed: Vec<u8>,
+    stored: Vec<u8>,
+}
+
+/// A framer adapter that batches payloads into compressed blocks, each
+/// written through the inner framer as one frame.
+///
+/// A block is written once it reaches the target length (so it may exceed
+/// the target by up to one payload), or when the writer is flushed. Writers
+/// do that from [`flush`](crate::StreamWriter::flush), from
+/// [`DurableWriter`](crate::DurableWriter) and
+/// [`GroupCommitWriter`](crate::GroupCommitWriter) syncs, and when a
+/// [`RollingStreamWriter`](crate::RollingStreamWriter) closes a segment; a
+/// writer dropped without a flush loses its partial block.
+///
+/// ```rust
+/// # #[cfg(feature = "lz4")] {
+/// use flatstream::block::{BlockDeframer, BlockFramer};
+/// use flatstream::compression::Compression;
+/// use flatstream::{DefaultDeframer, DefaultFramer, StreamReader, StreamWriter};
+///
+/// let mut stream = Vec::new();
+/// let mut writer = StreamWriter::new(&mut stream, BlockFramer::new(DefaultFramer, Compression::Lz4));
+/// for i in 0..100u32 {
+///     writer.write_payload(format!("sensor-7 reading {i}").as_bytes()).unwrap();
+/// }
+/// writer.flush().unwrap(); // writes the partial block
+///
+/// let mut reader = StreamReader::new(&stream[..], BlockDeframer::new(DefaultDeframer::new()));
+/// let mut messages = reader.messages();
+/// let mut count = 0;
+/// while let Some(payload) = messages.next().unwrap() {
+///     assert!(payload.starts_with(b"sensor-7"));
+///     count += 1;
+/// }
+/// assert_eq!(count, 100);
+/// # }
+/// ```
+pub struct BlockFramer<F: Framer> {
+    inner: F,
+    compression: Compression,
+    target_block_len: usize,
+    pending: Mutex<PendingBlock>,
+}
+
+impl<F: Framer> BlockFramer<F> {
+    /// Wraps `inner`, writing blocks of about [`DEFAULT_TARGET_BLOCK_LEN`].
+    pub fn new(inner: F, compression: Compression) -> Self {
+        Self {
+            inner,
+            compression,
+            target_block_len: DEFAULT_TARGET_BLOCK_LEN,
+            pending: Mutex::new(PendingBlock::default()),
+        }
+    }
+
+    /// Sets the uncompressed block length at which a block is written.
+    /// Larger blocks compress better; smaller ones bound what a reader must
+    /// decompress to reach a frame and what a crash loses before a flush.
+    pub fn with_target_block_len(mut self, len: usize) -> Self {
+        self.target_block_len = len;
+        self
+    }
+
+    /// Compresses the pending payloads and writes them as one block frame.
+    /// The pending block is cleared even if the write fails: retrying after
+    /// a partial write would duplicate frames.
+    fn write_block<W: Write>(&self, pending: &mut PendingBlock, writer: &mut W) -> Result<()> {
+        if pending.ends.is_empty() {
+            return Ok(());
+        }
+        let PendingBlock {
+            block,
+            ends,
+            compressed,
+            stored,
+        } = pending;
+        let result = self.compression.compress(block, compressed).and_then(|()| {
+            let (flag, body) = if compressed.len() < block.len() {
+                (self.compression.flag(), &compressed[..])
+            } else {
+                (FLAG_STORED, &block[..])
+            };
+            stored.clear();
+            stored.push(flag);
+            stored.extend_from_slice(&(ends.len() as u32).to_le_bytes());
+            stored.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            for end in ends.iter() {
+                stored.extend_from_slice(&end.to_le_bytes());
+            }
+            stored.extend_from_slice(body);
+            self.inner.frame_and_write(writer, stored)
+        });
+        block.clear();
+        ends.clear();
+        result
+    }
+}
+
+impl<F: Framer> Framer for BlockFramer<F> {
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        if payload.len() > u32::MAX as usize {
+            return Err(Error::invalid_frame_with(
+                "payload length exceeds 32-bit header limit",
+                Some(payload.len()),
+                None,
+                Some(u32::MAX as usize),
+            ));
+        }
+        let mut pending = lock(&self.pending);
+        // Block offsets are u32 too: start a new block rather than overflow.
+        if pending.block.len() + payload.len() > u32::MAX as usize {
+            self.write_block(&mut pending, writer)?;
+        }
+        pending.block.extend_from_slice(payload);
+        let end = pending.block.len() as u32;
+        pending.ends.push(end);
+        if pending.block.len() >= self.target_block_len {
+            self.write_block(&mut pending, writer)?;
+        }
+        Ok(())
+    }
+
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut pending = lock(&self.pending);
+        self.write_block(&mut pending, writer)?;
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default)]
+struct OpenBlock {
+    stored: Vec<u8>,
+    block: Vec<u8>,
+    ends: Vec<u32>,
+    next: usize,
+}
+
+impl OpenBlock {
+    /// Copies the next payload of the block into `buffer`, if any remain.
+    fn next_payload(&mut self, buffer: &mut Vec<u8>) -> Option<usize> {
+        let end = *self.ends.get(self.next)? as usize;
+        let start = match self.next {
+            0 => 0,
+            i => self.ends[i - 1] as usize,
+        };
+        self.next += 1;
+        let len = end - start;
+        if len > buffer.len() {
+            buffer.resize(len, 0);
+        }
+        buffer[..len].copy_from_slice(&self.block[start..end]);
+        Some(len)
+    }
+}
+
+/// A deframer adapter that reads blocks written by a [`BlockFramer`] over a
+/// matching inner framer and yields their payloads one at a time.
+///
+/// Each payload is copied out of the decompressed block into the caller's
+/// buffer. Both the stored block and the decompressed one live in internal
+/// buffers that are reused across blocks. Payloads are decompressed into a
+/// buffer, so there is no [`SliceDeframer`](crate::SliceDeframer)
+/// implementation.
+pub struct BlockDeframer<D: Deframer> {
+    inner: D,
+    max_block_len: usize,
+    open: Mutex<OpenBlock>,
+}
+
+impl<D: Deframer> BlockDeframer<D> {
+    /// Wraps `inner`, accepting uncompressed blocks up to
+    /// [`DEFAULT_MAX_BLOCK_LEN`].
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            max_block_len: DEFAULT_MAX_BLOCK_LEN,
+            open: Mutex::new(OpenBlock::default()),
+        }
+    }
+
+    /// Sets the maximum accepted uncompressed block length, checked against
+    /// the block header before any buffer is sized. Bound it to the writer's
+    /// target block length plus its largest payload. Within the bound, a
+    /// declared length the compressed body cannot produce is rejected before
+    /// memory is committed to it.
+    pub fn with_max_block_len(mut self, max: usize) -> Self {
+        self.max_block_len = max;
+        self
+    }
+
+    /// Parses and decompresses the `len` stored bytes of a block frame. A
+    /// rejected block leaves nothing to yield, so the next read starts on a
+    /// fresh frame instead of indexing into a half-filled block.
+    fn open_block(&self, open: &mut OpenBlock, len: usize) -> Result<()> {
+        let result = self.parse_block(open, len);
+        if result.is_err() {
+            open.ends.clear();
+            open.next = 0;
+        }
+        result
+    }
+
+    fn parse_block(&self, open: &mut OpenBlock, len: usize) -> Result<()> {
+        let OpenBlock {
+            stored,
+            block,
+            ends,
+            next,
+        } = open;
+        ends.clear();
+        *next = 0;
+        let stored = &stored[..len];
+        let Some((fixed, rest)) = stored.split_first_chunk::<FIXED_HEADER_LEN>() else {
+            return Err(Error::invalid_frame("block frame too short for its header"));
+        };
+        let flag = fixed[0];
+        let count = u32::from_le_bytes([fixed[1], fixed[2], fixed[3], fixed[4]]) as usize;
+        let block_len = u32::from_le_bytes([fixed[5], fixed[6], fixed[7], fixed[8]]) as usize;
+        if block_len > self.max_block_len {
+            return Err(Error::invalid_frame_with(
+                "block length exceeds configured limit",
+                Some(block_len),
+                None,
+                Some(self.max_block_len),
+            ));
+        }
+        if count > rest.len() / 4 {
+            return Err(Error::invalid_frame(format!(
+                "block header declares {count} frames but has room for {} offsets",
+                rest.len() / 4
+            )));
+        }
+        let (offsets, body) = rest.split_at(count * 4);
+        let mut start = 0;
+        for chunk in offsets.chunks_exact(4) {
+            let end = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            if end < start || end as usize > block_len {
+                return Err(Error::invalid_frame(
+                    "block frame offsets are out of order or past the block",
+                ));
+            }
+            ends.push(end);
+            start = end;
+        }
+        if start as usize != block_len {
+            return Err(Error::invalid_frame(
+                "block frame offsets do not cover the block",
+            ));
+        }
+
+        block.clear();
+        if flag == FLAG_STORED {
+            if body.len() != block_len {
+                return Err(Error::invalid_frame_with(
+                    "stored block length does not match the declared length",
+                    Some(block_len),
+                    Some(body.len()),
+                    None,
+                ));
+            }
+            block.extend_from_slice(body);
+            return Ok(());
+        }
+        decompress_to_vec(flag, body, block, block_len)
+    }
+}
+
+impl<D: Deframer> Deframer for BlockDeframer<D> {
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        let mut open = lock(&self.open);
+        loop {
+            if let Some(len) = open.next_payload(buffer) {
+                return Ok(Some(len));
+            }
+            let Some(len) = self.inner.read_and_deframe(reader, &mut open.stored)? else {
+                return Ok(None);
+            };
+            self.open_block(&mut open, len)?;
+        }
+    }
+
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        let mut open = lock(&self.open);
+        if open.next < open.ends.len() {
+            return Err(Error::invalid_frame(
+                "next block's length was read before the open block was drained",
+            ));
+        }
+        let Some(len) = self
+            .inner
+            .read_after_length(reader, &mut open.stored, payload_len)?
+        else {
+            return Ok(None);
+        };
+        self.open_block(&mut open, len)?;
+        // An empty block has no payload to yield; read on to the next one.
+        match open.next_payload(buffer) {
+            Some(len) => Ok(Some(len)),
+            None => {
+                drop(open);
+                self.read_and_deframe(reader, buffer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+    use crate::writer::StreamWriter;
+
+    fn events() -> Vec<Vec<u8>> {
+        (0..500u32)
+            .map(|i| format!("{{\"sensor\":{},\"reading\":{}}}", i % 8, i * 3).into_bytes())
+            .collect()
+    }
+
+    #[cfg(feature = "lz4")]
+    const COMPRESSION: Compression = Compression::Lz4;
+    #[cfg(not(feature = "lz4"))]
+    const COMPRESSION: Compression = Compression::Zstd { level: 3 };
+
+    fn write(events: &[Vec<u8>], framer: BlockFramer<DefaultFramer>) -> Vec<u8> {
+        let mut stream = Vec::new();
+        let mut writer = StreamWriter::new(&mut stream, framer);
+        for event in events {
+            writer.write_payload(event).unwrap();
+        }
+        writer.flush().unwrap();
+        stream
+    }
+
+    #[test]
+    fn small_frames_round_trip_through_process_all_and_messages() {
+        let events = events();
+        let raw_len: usize = events.iter().map(|e| e.len() + 4).sum();
+        let framer = BlockFramer::new(DefaultFramer, COMPRESSION).with_target_block_len(4096);
+        let stream = write(&events, framer);
+        assert!(stream.len() < raw_len / 2, "{} vs {raw_len}", stream.len());
+
+        let mut reader = StreamReader::new(&stream[..], BlockDeframer::new(DefaultDeframer::new()));
+        let mut seen = Vec::new();
+        reader
+            .process_all(|payload| {
+                seen.push(payload.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, events);
+
+        let mut reader = StreamReader::new(&stream[..], BlockDeframer::new(DefaultDeframer::new()));
+        let mut messages = reader.messages();
+        for event in &events {
+            assert_eq!(messages.next().unwrap().unwrap(), &event[..]);
+        }
+        assert!(messages.next().unwrap().is_none());
+    }
+
+    #[test]
+    fn blocks_are_written_at_the_target_length_and_on_flush() {
+        let framer = BlockFramer::new(DefaultFramer, COMPRESSION).with_target_block_len(100);
+        let mut stream = Vec::new();
+        framer.frame_and_write(&mut stream, &[1u8; 60]).unwrap();
+        assert!(stream.is_empty());
+        framer.frame_and_write(&mut stream, &[2u8; 60]).unwrap();
+        let first_block = stream.len();
+        assert!(first_block > 0);
+        framer.frame_and_write(&mut stream, b"").unwrap();
+        assert_eq!(stream.len(), first_block);
+        framer.flush_pending(&mut stream).unwrap();
+        framer.flush_pending(&mut stream).unwrap(); // nothing left to write
+
+        // The flushed block holds only the empty payload and is stored.
+        let tail = &stream[first_block..];
+        assert_eq!(&tail[..4], &13u32.to_le_bytes());
+        assert_eq!(
+            &tail[4..],
+            [FLAG_STORED, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let mut reader = StreamReader::new(&stream[..], BlockDeframer::new(DefaultDeframer::new()));
+        let mut lens = Vec::new();
+        while let Some(payload) = reader.read_message().unwrap() {
+            lens.push(payload.len());
+        }
+        assert_eq!(lens, [60, 60, 0]);
+    }
+
+    #[test]
+    fn block_length_is_bounded_and_offsets_checked_before_decompressing() {
+        let framer = BlockFramer::new(DefaultFramer, COMPRESSION);
+        let stream = write(&[vec![0u8; 1 << 16]], framer);
+        assert!(stream.len() < 1024);
+
+        let deframer = BlockDeframer::new(DefaultDeframer::new()).with_max_block_len(1 << 12);
+        let err = StreamReader::new(&stream[..], deframer)
+            .read_message()
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::InvalidFrame {
+                declared_len: Some(65536),
+                limit: Some(4096),
+                ..
+            }
+        ));
+
+        // An end offset past the block is rejected.
+        let mut corrupt = stream.clone();
+        corrupt[4 + FIXED_HEADER_LEN..4 + FIXED_HEADER_LEN + 4]
+            .copy_from_slice(&(1u32 << 20).to_le_bytes());
+        let err = StreamReader::new(&corrupt[..], BlockDeframer::new(DefaultDeframer::new()))
+            .read_message()
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[test]
+    fn indexing_writer_rejects_held_back_payloads() {
+        use crate::index::FrameIndex;
+        use crate::FramerExt;
+
+        let framer = BlockFramer::new(DefaultFramer, COMPRESSION).observed(|_: &[u8]| {});
+        let mut stream = Vec::new();
+        let mut writer = StreamWriter::new(&mut stream, framer).with_index(FrameIndex::new());
+        let err = writer.write_payload(b"event").unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        writer.flush().unwrap();
+        assert_eq!(writer.index().unwrap().frame_count(), 0);
+        drop(writer);
+        assert!(stream.is_empty());
+    }
+
+    /// A `DefaultFramer` frame around a hand-built block.
+    fn block_frame(flag: u8, ends: &[u32], block_len: u32, body: &[u8]) -> Vec<u8> {
+        let mut stored = vec![flag];
+        stored.extend_from_slice(&(ends.len() as u32).to_le_bytes());
+        stored.extend_from_slice(&block_len.to_le_bytes());
+        for end in ends {
+            stored.extend_from_slice(&end.to_le_bytes());
+        }
+        stored.extend_from_slice(body);
+        let mut frame = (stored.len() as u32).to_le_bytes().to_vec();
+        frame.extend_from_slice(&stored);
+        frame
+    }
+
+    #[test]
+    fn rejected_block_leaves_nothing_to_yield() {
+        let good = block_frame(FLAG_STORED, &[2, 5], 5, b"okyes");
+        let rejected = [
+            // Offsets out of order.
+            block_frame(FLAG_STORED, &[100, 50], 100, &[7; 100]),
+            // Stored body shorter than the declared block.
+            block_frame(FLAG_STORED, &[4, 8], 8, b"abcd"),
+            // Body that does not decompress.
+            block_frame(COMPRESSION.flag(), &[4, 8], 8, &[0xff; 16]),
+        ];
+        for bad in rejected {
+            // Open a good block first, so stale bytes are there to leak.
+            let mut stream = good.clone();
+            stream.extend_from_slice(&bad);
+            stream.extend_from_slice(&good);
+            let mut reader =
+                StreamReader::new(&stream[..], BlockDeframer::new(DefaultDeframer::new()));
+            assert_eq!(reader.read_message().unwrap(), Some(&b"ok"[..]));
+            assert_eq!(reader.read_message().unwrap(), Some(&b"yes"[..]));
+            assert!(reader.read_message().is_err());
+            assert_eq!(reader.read_message().unwrap(), Some(&b"ok"[..]));
+            assert_eq!(reader.read_message().unwrap(), Some(&b"yes"[..]));
+            assert_eq!(reader.read_message().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn forged_block_length_is_rejected_without_allocating() {
+        let forged = [
+            // Above the default ceiling.
+            block_frame(COMPRESSION.flag(), &[1 << 30], 1 << 30, &[0; 16]),
+            // Within it, but more than 16 compressed bytes can produce.
+            block_frame(COMPRESSION.flag(), &[1 << 24], 1 << 24, &[0; 16]),
+        ];
+        for frame in forged {
+            let deframer = BlockDeframer::new(DefaultDeframer::new());
+            let mut buffer = Vec::new();
+            let err = deframer
+                .read_and_deframe(&mut &frame[..], &mut buffer)
+                .unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+            assert_eq!(lock(&deframer.open).block.capacity(), 0);
+            assert!(buffer.capacity() < 1 << 10);
+        }
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn checksum_covers_the_whole_block() {
+        use crate::checksum::Crc32;
+        use crate::framing::{ChecksumDeframer, ChecksumFramer};
+
+        let mut stream = Vec::new();
+        let mut writer = StreamWriter::new(
+            &mut stream,
+            BlockFramer::new(ChecksumFramer::new(Crc32::new()), COMPRESSION),
+        );
+        for event in events() {
+            writer.write_payload(&event).unwrap();
+        }
+        writer.flush().unwrap();
+        let last = stream.len() - 1;
+        stream[last] ^= 0x01;
+
+        let deframer = BlockDeframer::new(ChecksumDeframer::new(Crc32::new()));
+        let err = StreamReader::new(&stream[..], deframer)
+            .read_message()
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+    }
+}
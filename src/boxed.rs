@@ -0,0 +1,370 @@
+//! Object-safe framers and deframers for pipelines assembled at runtime.
+//!
+//! [`Framer`] and [`Deframer`] take their I/O as generic `W: Write` /
+//! `R: Read` parameters, which keeps the hot path statically dispatched but
+//! rules out `dyn Framer`. [`DynFramer`] and [`DynDeframer`] are their
+//! object-safe mirrors over `dyn Write` / `dyn Read`, implemented for every
+//! `Send + Sync` framer and deframer, so any static strategy — adapters
+//! included — erases into a [`BoxFramer`] / [`BoxDeframer`] (or the `Arc`
+//! forms). The boxed types implement [`Framer`]/[`Deframer`] themselves, so
+//! they go straight into `StreamWriter`/`StreamReader` and compose with the
+//! [`FramerExt`]/[`DeframerExt`] adapters like any other strategy:
+//!
+//! ```rust
+//! use flatstream::boxed::{BoxDeframer, BoxFramer, DeframerBoxExt, FramerBoxExt};
+//! use flatstream::{
+//!     DefaultDeframer, DefaultFramer, DeframerExt, FramerExt, Result, SizeValidator,
+//!     StreamReader, StreamWriter,
+//! };
+//!
+//! # fn main() -> Result<()> {
+//! // Chosen from configuration or a plugin; the concrete types stop here.
+//! let framer: BoxFramer = DefaultFramer.bounded(1 << 20).boxed();
+//! let deframer: BoxDeframer = DefaultDeframer::new()
+//!     .with_validator(SizeValidator::new(1, 1 << 20))
+//!     .boxed();
+//!
+//! let mut stream = Vec::new();
+//! let mut writer = StreamWriter::new(&mut stream, framer);
+//! writer.write_payload(b"hello")?;
+//!
+//! let mut reader = StreamReader::new(&stream[..], deframer);
+//! assert_eq!(reader.read_message()?, Some(&b"hello"[..]));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Payloads stay borrowed slices; the cost is a virtual call per frame, plus
+//! one per `read`/`write` the strategy issues on the erased I/O object.
+//! [`SliceDeframer`](crate::framing::SliceDeframer) has no generic methods
+//! and is already usable as `dyn SliceDeframer`.
+
+use crate::error::Result;
+use crate::framing::{
+    BoundedFramer, Deframer, Framer, ObserverDeframer, ObserverFramer, ValidatingDeframer,
+    ValidatingFramer,
+};
+use crate::validation::Validator;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+#[cfg(doc)]
+use crate::framing::{DeframerExt, FramerExt};
+
+/// Object-safe mirror of [`Framer`], implemented for every `Send + Sync`
+/// framer.
+pub trait DynFramer: Send + Sync {
+    /// [`Framer::frame_and_write`] over an erased writer.
+    fn frame_and_write_dyn(&self, writer: &mut dyn Write, payload: &[u8]) -> Result<()>;
+
+    /// [`Framer::flush_pending`] over an erased writer.
+    fn flush_pending_dyn(&self, writer: &mut dyn Write) -> Result<()>;
+
+    /// [`Framer::holds_back_payloads`] of the erased framer.
+    fn holds_back_payloads_dyn(&self) -> bool;
+}
+
+impl<T: Framer + Send + Sync> DynFramer for T {
+    fn frame_and_write_dyn(&self, mut writer: &mut dyn Write, payload: &[u8]) -> Result<()> {
+        self.frame_and_write(&mut writer, payload)
+    }
+
+    fn flush_pending_dyn(&self, mut writer: &mut dyn Write) -> Result<()> {
+        self.flush_pending(&mut writer)
+    }
+
+    fn holds_back_payloads_dyn(&self) -> bool {
+        self.holds_back_payloads()
+    }
+}
+
+/// Object-safe mirror of [`Deframer`], implemented for every `Send + Sync`
+/// deframer. Both methods are forwarded, so deframers that override
+/// [`Deframer::read_and_deframe`] keep their own header handling.
+pub trait DynDeframer: Send + Sync {
+    /// [`Deframer::read_and_deframe`] over an erased reader.
+    fn read_and_deframe_dyn(
+        &self,
+        reader: &mut dyn Read,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>>;
+
+    /// [`Deframer::read_after_length`] over an erased reader.
+    fn read_after_length_dyn(
+        &self,
+        reader: &mut dyn Read,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>>;
+}
+
+impl<T: Deframer + Send + Sync> DynDeframer for T {
+    fn read_and_deframe_dyn(
+        &self,
+        mut reader: &mut dyn Read,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        self.read_and_deframe(&mut reader, buffer)
+    }
+
+    fn read_after_length_dyn(
+        &self,
+        mut reader: &mut dyn Read,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        self.read_after_length(&mut reader, buffer, payload_len)
+    }
+}
+
+/// An owned, type-erased framer.
+pub type BoxFramer = Box<dyn DynFramer>;
+
+/// A shared, type-erased framer, for one strategy across several writers.
+pub type ArcFramer = Arc<dyn DynFramer>;
+
+/// An owned, type-erased deframer.
+pub type BoxDeframer = Box<dyn DynDeframer>;
+
+/// A shared, type-erased deframer, for one strategy across several readers.
+pub type ArcDeframer = Arc<dyn DynDeframer>;
+
+/// A type-erased validator. Implements [`Validator`], so it plugs into
+/// `with_validator` like a concrete one.
+pub type BoxValidator = Box<dyn Validator>;
+
+/// A type-erased payload observer, as taken by the `observed` adapters.
+pub type BoxObserver = Box<dyn Fn(&[u8]) + Send + Sync>;
+
+/// [`BoundedFramer`] over a boxed framer.
+pub type BoundedBoxFramer = BoundedFramer<BoxFramer>;
+
+/// [`ObserverFramer`] over a boxed framer and callback.
+pub type ObserverBoxFramer = ObserverFramer<BoxFramer, BoxObserver>;
+
+/// [`ValidatingFramer`] over a boxed framer and validator.
+pub type ValidatingBoxFramer = ValidatingFramer<BoxFramer, BoxValidator>;
+
+/// [`ObserverDeframer`] over a boxed deframer and callback.
+pub type ObserverBoxDeframer = ObserverDeframer<BoxDeframer, BoxObserver>;
+
+/// [`ValidatingDeframer`] over a boxed deframer and validator.
+pub type ValidatingBoxDeframer = ValidatingDeframer<BoxDeframer, BoxValidator>;
+
+impl Framer for BoxFramer {
+    #[inline]
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        (**self).frame_and_write_dyn(writer, payload)
+    }
+
+    #[inline]
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        (**self).flush_pending_dyn(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        (**self).holds_back_payloads_dyn()
+    }
+}
+
+impl Framer for ArcFramer {
+    #[inline]
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        (**self).frame_and_write_dyn(writer, payload)
+    }
+
+    #[inline]
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        (**self).flush_pending_dyn(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        (**self).holds_back_payloads_dyn()
+    }
+}
+
+impl Deframer for BoxDeframer {
+    #[inline]
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        (**self).read_and_deframe_dyn(reader, buffer)
+    }
+
+    #[inline]
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        (**self).read_after_length_dyn(reader, buffer, payload_len)
+    }
+}
+
+impl Deframer for ArcDeframer {
+    #[inline]
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        (**self).read_and_deframe_dyn(reader, buffer)
+    }
+
+    #[inline]
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        (**self).read_after_length_dyn(reader, buffer, payload_len)
+    }
+}
+
+impl Validator for BoxValidator {
+    #[inline]
+    fn validate(&self, payload: &[u8]) -> Result<()> {
+        (**self).validate(payload)
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+}
+
+/// Erases a framer into a [`BoxFramer`] or [`ArcFramer`].
+pub trait FramerBoxExt: Framer + Sized + Send + Sync + 'static {
+    /// Boxes this framer. (Boxing a `BoxFramer` again adds a layer; it
+    /// already is one.)
+    fn boxed(self) -> BoxFramer {
+        Box::new(self)
+    }
+
+    /// Shares this framer behind an `Arc`.
+    fn shared(self) -> ArcFramer {
+        Arc::new(self)
+    }
+}
+
+impl<T: Framer + Send + Sync + 'static> FramerBoxExt for T {}
+
+/// Erases a deframer into a [`BoxDeframer`] or [`ArcDeframer`].
+pub trait DeframerBoxExt: Deframer + Sized + Send + Sync + 'static {
+    /// Boxes this deframer. (Boxing a `BoxDeframer` again adds a layer; it
+    /// already is one.)
+    fn boxed(self) -> BoxDeframer {
+        Box::new(self)
+    }
+
+    /// Shares this deframer behind an `Arc`.
+    fn shared(self) -> ArcDeframer {
+        Arc::new(self)
+    }
+}
+
+impl<T: Deframer + Send + Sync + 'static> DeframerBoxExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer, DeframerExt, FramerExt};
+    use crate::reader::StreamReader;
+    use crate::validation::SizeValidator;
+    use crate::writer::StreamWriter;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn write_all<F: Framer>(framer: F, payloads: &[&[u8]]) -> Result<Vec<u8>> {
+        let mut stream = Vec::new();
+        let mut writer = StreamWriter::new(&mut stream, framer);
+        for payload in payloads {
+            writer.write_payload(payload)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        Ok(stream)
+    }
+
+    fn read_all<D: Deframer>(deframer: D, stream: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut reader = StreamReader::new(stream, deframer);
+        let mut out = Vec::new();
+        reader.process_all(|payload| {
+            out.push(payload.to_vec());
+            Ok(())
+        })?;
+        Ok(out)
+    }
+
+    #[test]
+    fn boxed_strategies_write_and_read_the_static_bytes() {
+        let payloads: [&[u8]; 3] = [b"one", b"", b"three"];
+        let expected = write_all(DefaultFramer, &payloads).unwrap();
+        assert_eq!(
+            write_all(DefaultFramer.boxed(), &payloads).unwrap(),
+            expected
+        );
+        assert_eq!(
+            write_all(DefaultFramer.shared(), &payloads).unwrap(),
+            expected
+        );
+
+        let deframers: [BoxDeframer; 2] = [
+            DefaultDeframer::new().boxed(),
+            Box::new(DefaultDeframer::new().shared()),
+        ];
+        for deframer in deframers {
+            assert_eq!(read_all(deframer, &expected).unwrap(), payloads);
+        }
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn checksummed_header_handling_survives_erasure() {
+        use crate::checksum::Crc32;
+        use crate::framing::{ChecksumDeframer, ChecksumFramer};
+
+        let stream = write_all(ChecksumFramer::new(Crc32).boxed(), &[b"payload"]).unwrap();
+        let deframer = ChecksumDeframer::new(Crc32).shared();
+        assert_eq!(
+            read_all(Arc::clone(&deframer), &stream).unwrap(),
+            [b"payload"]
+        );
+
+        let mut corrupt = stream.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        let err = read_all(deframer, &corrupt).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn every_adapter_has_a_boxed_form() {
+        let written = Arc::new(AtomicUsize::new(0));
+        let read = Arc::new(AtomicUsize::new(0));
+        let counter = |count: &Arc<AtomicUsize>| -> BoxObserver {
+            let count = Arc::clone(count);
+            Box::new(move |payload| {
+                count.fetch_add(payload.len(), Ordering::Relaxed);
+            })
+        };
+        let validator = || -> BoxValidator { Box::new(SizeValidator::new(1, 8)) };
+
+        let bounded: BoundedBoxFramer = DefaultFramer.boxed().bounded(8);
+        let observed: ObserverBoxFramer = bounded.boxed().observed(counter(&written));
+        let validating: ValidatingBoxFramer = observed.boxed().with_validator(validator());
+        let framer = validating.shared();
+        let stream = write_all(Arc::clone(&framer), &[b"abc", b"defgh"]).unwrap();
+        assert_eq!(written.load(Ordering::Relaxed), 8);
+        let err = write_all(framer, &[b"too long!"]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ValidationFailed { .. }));
+
+        let observed: ObserverBoxDeframer = DefaultDeframer::new().boxed().observed(counter(&read));
+        let validating: ValidatingBoxDeframer = observed.boxed().with_validator(validator());
+        let payloads = read_all(validating.boxed(), &stream).unwrap();
+        assert_eq!(payloads, [&b"abc"[..], &b"defgh"[..]]);
+        assert_eq!(read.load(Ordering::Relaxed), 8);
+    }
+}
@@ -16,6 +16,13 @@ pub trait Checksum {
     /// works in (enforced at compile time where framers are constructed).
     const SIZE: usize;
 
+    /// Identifier recorded for this algorithm by self-describing formats
+    /// (e.g. the sealed container header), so a reader can reject a stream
+    /// written with a different algorithm of the same width. Built-in
+    /// algorithms have fixed ids; custom implementations default to
+    /// [`CUSTOM_ALGORITHM_ID`].
+    const ALGORITHM_ID: u8 = CUSTOM_ALGORITHM_ID;
+
     /// Calculates the checksum for the given payload.
     fn calculate(&self, payload: &[u8]) -> u64;
 
@@ -58,6 +65,10 @@ pub trait Checksum {
     }
 }
 
+/// [`Checksum::ALGORITHM_ID`] of every non-built-in algorithm. Readers of
+/// self-describing formats can only check the width of such streams.
+pub const CUSTOM_ALGORITHM_ID: u8 = 0xFF;
+
 /// The value mask a `size`-byte wire field can carry: low `8 * size` bits.
 const fn width_mask(size: usize) -> u64 {
     if size >= 8 {
@@ -83,6 +94,7 @@ impl XxHash64 {
 impl Checksum for XxHash64 {
     /// XXH3 produces a 64-bit (8-byte) hash.
     const SIZE: usize = 8;
+    const ALGORITHM_ID: u8 = 3;
 
     fn calculate(&self, payload: &[u8]) -> u64 {
         xxhash_rust::xxh3::xxh3_64(payload)
@@ -95,8 +107,8 @@ impl Checksum for XxHash64 {
 /// NOT CRC-32C (Castagnoli). An earlier doc comment misnamed this; the
 /// implementation — and therefore the wire format — has always been
 /// ISO-HDLC (`"123456789"` → `0xCBF43926`), and the known-answer test below
-/// pins it. If Castagnoli (hardware `crc32c` instructions) is ever wanted,
-/// it must be a new algorithm, not a silent swap: the bytes differ.
+/// pins it. Castagnoli is the separate [`Crc32c`] algorithm (feature
+/// `crc32c`), never a silent swap: the bytes differ.
 #[cfg(feature = "crc32")]
 #[derive(Default, Clone, Copy)]
 pub struct Crc32;
@@ -112,6 +124,7 @@ impl Crc32 {
 impl Checksum for Crc32 {
     /// CRC32 produces a 32-bit (4-byte) hash.
     const SIZE: usize = 4;
+    const ALGORITHM_ID: u8 = 2;
 
     fn calculate(&self, payload: &[u8]) -> u64 {
         // crc32fast returns a u32, so we cast it to u64 for trait compatibility.
@@ -119,6 +132,35 @@ impl Checksum for Crc32 {
     }
 }
 
+/// Provides an implementation of the CRC-32C (Castagnoli, polynomial
+/// 0x1EDC6F41; `"123456789"` → `0xE3069283`, pinned by the known-answer test)
+/// checksum algorithm, as computed by the `crc32c` crate: SSE4.2 on x86-64 and
+/// the ARMv8 CRC instructions on aarch64 when the CPU has them (detected at
+/// runtime), a table-driven fallback elsewhere. The CRC-32 of iSCSI, ext4,
+/// Btrfs and many storage formats — a distinct algorithm from [`Crc32`] of
+/// the same width, recorded under its own [`ALGORITHM_ID`](Checksum::ALGORITHM_ID).
+#[cfg(feature = "crc32c")]
+#[derive(Default, Clone, Copy)]
+pub struct Crc32c;
+
+#[cfg(feature = "crc32c")]
+impl Crc32c {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "crc32c")]
+impl Checksum for Crc32c {
+    /// CRC-32C produces a 32-bit (4-byte) hash.
+    const SIZE: usize = 4;
+    const ALGORITHM_ID: u8 = 4;
+
+    fn calculate(&self, payload: &[u8]) -> u64 {
+        crc32c::crc32c(payload) as u64
+    }
+}
+
 /// Provides an implementation of the CRC-16/XMODEM checksum algorithm
 /// (polynomial 0x1021, init 0x0000; `"123456789"` → `0x31C3`, pinned by the
 /// known-answer test). Note: this is the XMODEM variant, not CRC-16/CCITT-FALSE
@@ -139,6 +181,7 @@ impl Crc16 {
 impl Checksum for Crc16 {
     /// CRC16 produces a 16-bit (2-byte) hash.
     const SIZE: usize = 2;
+    const ALGORITHM_ID: u8 = 1;
 
     fn calculate(&self, payload: &[u8]) -> u64 {
         // crc16 returns a u16, so we cast it to u64 for trait compatibility.
@@ -160,6 +203,7 @@ impl NoChecksum {
 impl Checksum for NoChecksum {
     /// No checksum bytes are written.
     const SIZE: usize = 0;
+    const ALGORITHM_ID: u8 = 0;
 
     fn calculate(&self, _payload: &[u8]) -> u64 {
         0
@@ -218,6 +262,18 @@ mod tests {
         assert_eq!(Crc32::SIZE, 4);
     }
 
+    #[cfg(feature = "crc32c")]
+    #[test]
+    fn test_crc32c_checksum() {
+        let checksum = Crc32c::new();
+        let payload = b"test data";
+        let result = checksum.calculate(payload);
+        assert_ne!(result, 0);
+        assert!(checksum.verify(result, payload).is_ok());
+        assert!(checksum.verify(result + 1, payload).is_err());
+        assert_eq!(Crc32c::SIZE, 4);
+    }
+
     #[cfg(feature = "crc16")]
     #[test]
     fn test_crc16_checksum() {
@@ -240,6 +296,9 @@ mod tests {
         #[cfg(feature = "crc32")]
         assert_eq!(Crc32::SIZE, 4);
 
+        #[cfg(feature = "crc32c")]
+        assert_eq!(Crc32c::SIZE, 4);
+
         #[cfg(feature = "crc16")]
         assert_eq!(Crc16::SIZE, 2);
     }
@@ -255,6 +314,12 @@ mod tests {
             0xCBF4_3926,
             "CRC-32/ISO-HDLC (NOT Castagnoli, which is 0xE3069283)"
         );
+        #[cfg(feature = "crc32c")]
+        assert_eq!(
+            Crc32c::new().calculate(b"123456789"),
+            0xE306_9283,
+            "CRC-32C/Castagnoli"
+        );
         #[cfg(feature = "crc16")]
         assert_eq!(
             Crc16::new().calculate(b"123456789"),
@@ -269,6 +334,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn algorithm_ids_are_distinct_and_stable() {
+        // Recorded in sealed container headers: these values are wire format.
+        assert_eq!(NoChecksum::ALGORITHM_ID, 0);
+        #[cfg(feature = "crc16")]
+        assert_eq!(Crc16::ALGORITHM_ID, 1);
+        #[cfg(feature = "crc32")]
+        assert_eq!(Crc32::ALGORITHM_ID, 2);
+        #[cfg(feature = "xxhash")]
+        assert_eq!(XxHash64::ALGORITHM_ID, 3);
+        #[cfg(feature = "crc32c")]
+        assert_eq!(Crc32c::ALGORITHM_ID, 4);
+    }
+
     #[test]
     fn test_write_read_bytes_roundtrip_and_width() {
         // The default helpers must be byte-exact for every width: exactly
@@ -293,6 +372,8 @@ mod tests {
         roundtrip(&XxHash64::new(), probe);
         #[cfg(feature = "crc32")]
         roundtrip(&Crc32::new(), probe);
+        #[cfg(feature = "crc32c")]
+        roundtrip(&Crc32c::new(), probe);
         #[cfg(feature = "crc16")]
         roundtrip(&Crc16::new(), probe);
     }
@@ -0,0 +1,510 @@
+//! Checksum algorithms selected at runtime.
+//!
+//! [`ChecksumFramer<C>`](crate::framing::ChecksumFramer) fixes its algorithm
+//! at compile time, which is what lets the header width constant-fold — and
+//! also why [`ChecksumKind`] cannot itself implement [`Checksum`]: the wire
+//! width is the associated const [`Checksum::SIZE`], not a runtime value.
+//! Instead, a `ChecksumKind` parsed from configuration builds a
+//! [`ChecksumKindFramer`] or [`ChecksumKindDeframer`], one concrete type for
+//! every algorithm, which dispatches each frame to the statically-typed
+//! framer of the selected algorithm. The bytes on the wire are identical to
+//! those of the static framers.
+//!
+//! ```rust
+//! use flatstream::{ChecksumKind, Result, StreamReader, StreamWriter};
+//!
+//! # fn main() -> Result<()> {
+//! let kind: ChecksumKind = "none".parse()?; // e.g. read from a config file
+//! let mut stream = Vec::new();
+//! let mut writer = StreamWriter::new(&mut stream, kind.framer());
+//! writer.write_payload(b"hello")?;
+//!
+//! let mut reader = StreamReader::new(&stream[..], kind.deframer());
+//! assert_eq!(reader.read_message()?, Some(&b"hello"[..]));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Checksum`]: crate::checksum::Checksum
+//! [`Checksum::SIZE`]: crate::checksum::Checksum::SIZE
+
+use crate::error::{Error, Result};
+use crate::framing::{
+    DefaultDeframer, DefaultFramer, Deframer, Framer, SliceDeframer, SliceFrame,
+    DEFAULT_MAX_FRAME_LEN,
+};
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+#[cfg(any(
+    feature = "xxhash",
+    feature = "crc32",
+    feature = "crc16",
+    feature = "crc32c",
+    feature = "sha256",
+    feature = "blake3"
+))]
+use crate::framing::{ChecksumDeframer, ChecksumFramer};
+
+#[cfg(feature = "crc16")]
+use crate::checksum::Crc16;
+#[cfg(feature = "crc32")]
+use crate::checksum::Crc32;
+#[cfg(feature = "crc32c")]
+use crate::checksum::Crc32c;
+#[cfg(feature = "xxhash")]
+use crate::checksum::XxHash64;
+#[cfg(feature = "blake3")]
+use crate::digest::Blake3;
+#[cfg(feature = "sha256")]
+use crate::digest::Sha256;
+#[cfg(feature = "xxhash")]
+use crate::digest::XxHash128;
+
+/// A checksum or digest algorithm chosen at runtime. Each variant exists
+/// when its feature is enabled; [`None`](Self::None) is always available.
+///
+/// Parses from (and displays as) the lowercase [`name`](Self::name); parsing
+/// ignores ASCII case, `-` and `_`, so `"CRC-32C"` is `crc32c`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumKind {
+    /// No checksum: frames in the `DefaultFramer` layout.
+    #[default]
+    None,
+    /// CRC-16/XMODEM, 2 bytes.
+    #[cfg(feature = "crc16")]
+    Crc16,
+    /// CRC-32/ISO-HDLC, 4 bytes.
+    #[cfg(feature = "crc32")]
+    Crc32,
+    /// CRC-32C/Castagnoli, 4 bytes.
+    #[cfg(feature = "crc32c")]
+    Crc32c,
+    /// XXH3-64, 8 bytes.
+    #[cfg(feature = "xxhash")]
+    XxHash64,
+    /// XXH3-128, 16 bytes.
+    #[cfg(feature = "xxhash")]
+    XxHash128,
+    /// SHA-256, 32 bytes.
+    #[cfg(feature = "sha256")]
+    Sha256,
+    /// BLAKE3, 32 bytes.
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+/// Every algorithm name `FromStr` knows, with the feature that enables it,
+/// so a name whose feature is compiled out gets a useful error.
+const KNOWN: [(&str, &str); 7] = [
+    ("crc16", "crc16"),
+    ("crc32", "crc32"),
+    ("crc32c", "crc32c"),
+    ("xxhash64", "xxhash"),
+    ("xxhash128", "xxhash"),
+    ("sha256", "sha256"),
+    ("blake3", "blake3"),
+];
+
+impl ChecksumKind {
+    /// Every kind compiled into this build, narrowest first.
+    pub const ALL: &'static [ChecksumKind] = &[
+        ChecksumKind::None,
+        #[cfg(feature = "crc16")]
+        ChecksumKind::Crc16,
+        #[cfg(feature = "crc32")]
+        ChecksumKind::Crc32,
+        #[cfg(feature = "crc32c")]
+        ChecksumKind::Crc32c,
+        #[cfg(feature = "xxhash")]
+        ChecksumKind::XxHash64,
+        #[cfg(feature = "xxhash")]
+        ChecksumKind::XxHash128,
+        #[cfg(feature = "sha256")]
+        ChecksumKind::Sha256,
+        #[cfg(feature = "blake3")]
+        ChecksumKind::Blake3,
+    ];
+
+    /// The canonical configuration name, e.g. `"crc32c"` or `"xxhash64"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumKind::None => "none",
+            #[cfg(feature = "crc16")]
+            ChecksumKind::Crc16 => "crc16",
+            #[cfg(feature = "crc32")]
+            ChecksumKind::Crc32 => "crc32",
+            #[cfg(feature = "crc32c")]
+            ChecksumKind::Crc32c => "crc32c",
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash64 => "xxhash64",
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash128 => "xxhash128",
+            #[cfg(feature = "sha256")]
+            ChecksumKind::Sha256 => "sha256",
+            #[cfg(feature = "blake3")]
+            ChecksumKind::Blake3 => "blake3",
+        }
+    }
+
+    /// Width in bytes of the checksum field on the wire.
+    pub fn width(&self) -> usize {
+        match self {
+            ChecksumKind::None => 0,
+            #[cfg(feature = "crc16")]
+            ChecksumKind::Crc16 => 2,
+            #[cfg(feature = "crc32")]
+            ChecksumKind::Crc32 => 4,
+            #[cfg(feature = "crc32c")]
+            ChecksumKind::Crc32c => 4,
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash64 => 8,
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash128 => 16,
+            #[cfg(feature = "sha256")]
+            ChecksumKind::Sha256 => 32,
+            #[cfg(feature = "blake3")]
+            ChecksumKind::Blake3 => 32,
+        }
+    }
+
+    /// A framer writing frames checksummed with this algorithm.
+    pub fn framer(self) -> ChecksumKindFramer {
+        ChecksumKindFramer::new(self)
+    }
+
+    /// A deframer reading frames checksummed with this algorithm, bounded by
+    /// [`DEFAULT_MAX_FRAME_LEN`].
+    pub fn deframer(self) -> ChecksumKindDeframer {
+        ChecksumKindDeframer::new(self)
+    }
+}
+
+impl fmt::Display for ChecksumKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ChecksumKind {
+    type Err = Error;
+
+    /// Parses a [`name`](Self::name). Fails with `InvalidFrame` for an
+    /// unknown name, or a known one whose feature is not enabled.
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized: String = s
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        if let Some(kind) = Self::ALL.iter().find(|kind| kind.name() == normalized) {
+            return Ok(*kind);
+        }
+        match KNOWN.iter().find(|(name, _)| *name == normalized) {
+            Some((name, feature)) => Err(Error::invalid_frame(format!(
+                "checksum {name} requires the `{feature}` feature"
+            ))),
+            None => Err(Error::invalid_frame(format!(
+                "unknown checksum {s:?}; expected none, {}",
+                KNOWN.map(|(name, _)| name).join(", ")
+            ))),
+        }
+    }
+}
+
+/// A framer whose checksum algorithm is a runtime [`ChecksumKind`].
+///
+/// Writes exactly the bytes of `DefaultFramer` (for
+/// [`ChecksumKind::None`]) or `ChecksumFramer<C>` for the selected `C`; the
+/// per-frame cost over the static framer is one `match`.
+#[derive(Debug, Clone, Copy)]
+pub struct ChecksumKindFramer {
+    kind: ChecksumKind,
+}
+
+impl ChecksumKindFramer {
+    pub fn new(kind: ChecksumKind) -> Self {
+        Self { kind }
+    }
+
+    /// The algorithm this framer writes.
+    pub fn kind(&self) -> ChecksumKind {
+        self.kind
+    }
+}
+
+impl Framer for ChecksumKindFramer {
+    #[inline]
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        match self.kind {
+            ChecksumKind::None => DefaultFramer.frame_and_write(writer, payload),
+            #[cfg(feature = "crc16")]
+            ChecksumKind::Crc16 => ChecksumFramer::new(Crc16).frame_and_write(writer, payload),
+            #[cfg(feature = "crc32")]
+            ChecksumKind::Crc32 => ChecksumFramer::new(Crc32).frame_and_write(writer, payload),
+            #[cfg(feature = "crc32c")]
+            ChecksumKind::Crc32c => ChecksumFramer::new(Crc32c).frame_and_write(writer, payload),
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash64 => {
+                ChecksumFramer::new(XxHash64).frame_and_write(writer, payload)
+            }
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash128 => {
+                ChecksumFramer::new(XxHash128).frame_and_write(writer, payload)
+            }
+            #[cfg(feature = "sha256")]
+            ChecksumKind::Sha256 => ChecksumFramer::new(Sha256).frame_and_write(writer, payload),
+            #[cfg(feature = "blake3")]
+            ChecksumKind::Blake3 => ChecksumFramer::new(Blake3).frame_and_write(writer, payload),
+        }
+    }
+}
+
+/// A deframer whose checksum algorithm is a runtime [`ChecksumKind`].
+///
+/// Reads streams written with a matching [`ChecksumKindFramer`] or with the
+/// corresponding static framer, applying the same length policy as
+/// `ChecksumDeframer`: [`DEFAULT_MAX_FRAME_LEN`] unless tightened with
+/// [`with_max_frame_len`](Self::with_max_frame_len).
+#[derive(Debug, Clone, Copy)]
+pub struct ChecksumKindDeframer {
+    kind: ChecksumKind,
+    max_frame_len: usize,
+}
+
+impl ChecksumKindDeframer {
+    pub fn new(kind: ChecksumKind) -> Self {
+        Self {
+            kind,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Sets the maximum accepted payload length (enforced before allocation).
+    pub fn with_max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+
+    /// The algorithm this deframer verifies.
+    pub fn kind(&self) -> ChecksumKind {
+        self.kind
+    }
+
+    fn default_deframer(&self) -> DefaultDeframer {
+        DefaultDeframer::new().with_max_frame_len(self.max_frame_len)
+    }
+
+    #[cfg(any(
+        feature = "xxhash",
+        feature = "crc32",
+        feature = "crc16",
+        feature = "crc32c",
+        feature = "sha256",
+        feature = "blake3"
+    ))]
+    fn checksum_deframer<C: crate::digest::Digest>(&self, alg: C) -> ChecksumDeframer<C> {
+        ChecksumDeframer::new(alg).with_max_frame_len(self.max_frame_len)
+    }
+}
+
+impl Deframer for ChecksumKindDeframer {
+    #[inline]
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        match self.kind {
+            ChecksumKind::None => self.default_deframer().read_and_deframe(reader, buffer),
+            #[cfg(feature = "crc16")]
+            ChecksumKind::Crc16 => self
+                .checksum_deframer(Crc16)
+                .read_and_deframe(reader, buffer),
+            #[cfg(feature = "crc32")]
+            ChecksumKind::Crc32 => self
+                .checksum_deframer(Crc32)
+                .read_and_deframe(reader, buffer),
+            #[cfg(feature = "crc32c")]
+            ChecksumKind::Crc32c => self
+                .checksum_deframer(Crc32c)
+                .read_and_deframe(reader, buffer),
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash64 => self
+                .checksum_deframer(XxHash64)
+                .read_and_deframe(reader, buffer),
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash128 => self
+                .checksum_deframer(XxHash128)
+                .read_and_deframe(reader, buffer),
+            #[cfg(feature = "sha256")]
+            ChecksumKind::Sha256 => self
+                .checksum_deframer(Sha256)
+                .read_and_deframe(reader, buffer),
+            #[cfg(feature = "blake3")]
+            ChecksumKind::Blake3 => self
+                .checksum_deframer(Blake3)
+                .read_and_deframe(reader, buffer),
+        }
+    }
+
+    #[inline]
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        match self.kind {
+            ChecksumKind::None => {
+                self.default_deframer()
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "crc16")]
+            ChecksumKind::Crc16 => {
+                self.checksum_deframer(Crc16)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "crc32")]
+            ChecksumKind::Crc32 => {
+                self.checksum_deframer(Crc32)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "crc32c")]
+            ChecksumKind::Crc32c => {
+                self.checksum_deframer(Crc32c)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash64 => {
+                self.checksum_deframer(XxHash64)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash128 => {
+                self.checksum_deframer(XxHash128)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "sha256")]
+            ChecksumKind::Sha256 => {
+                self.checksum_deframer(Sha256)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+            #[cfg(feature = "blake3")]
+            ChecksumKind::Blake3 => {
+                self.checksum_deframer(Blake3)
+                    .read_after_length(reader, buffer, payload_len)
+            }
+        }
+    }
+}
+
+impl SliceDeframer for ChecksumKindDeframer {
+    #[inline]
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        match self.kind {
+            ChecksumKind::None => self.default_deframer().deframe_slice(input),
+            #[cfg(feature = "crc16")]
+            ChecksumKind::Crc16 => self.checksum_deframer(Crc16).deframe_slice(input),
+            #[cfg(feature = "crc32")]
+            ChecksumKind::Crc32 => self.checksum_deframer(Crc32).deframe_slice(input),
+            #[cfg(feature = "crc32c")]
+            ChecksumKind::Crc32c => self.checksum_deframer(Crc32c).deframe_slice(input),
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash64 => self.checksum_deframer(XxHash64).deframe_slice(input),
+            #[cfg(feature = "xxhash")]
+            ChecksumKind::XxHash128 => self.checksum_deframer(XxHash128).deframe_slice(input),
+            #[cfg(feature = "sha256")]
+            ChecksumKind::Sha256 => self.checksum_deframer(Sha256).deframe_slice(input),
+            #[cfg(feature = "blake3")]
+            ChecksumKind::Blake3 => self.checksum_deframer(Blake3).deframe_slice(input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::reader::StreamReader;
+
+    #[test]
+    fn names_round_trip_and_parse_leniently() {
+        for kind in ChecksumKind::ALL {
+            assert_eq!(kind.name().parse::<ChecksumKind>().unwrap(), *kind);
+            assert_eq!(kind.to_string(), kind.name());
+        }
+        assert_eq!(
+            " None ".parse::<ChecksumKind>().unwrap(),
+            ChecksumKind::None
+        );
+        #[cfg(feature = "crc32c")]
+        assert_eq!(
+            "CRC-32C".parse::<ChecksumKind>().unwrap(),
+            ChecksumKind::Crc32c
+        );
+        #[cfg(feature = "xxhash")]
+        assert_eq!(
+            "xx_hash_64".parse::<ChecksumKind>().unwrap(),
+            ChecksumKind::XxHash64
+        );
+
+        let err = "adler32".parse::<ChecksumKind>().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        assert!(err.to_string().contains("expected none, crc16"), "{err}");
+    }
+
+    #[cfg(not(feature = "blake3"))]
+    #[test]
+    fn names_of_disabled_features_say_which_feature() {
+        let err = "blake3".parse::<ChecksumKind>().unwrap_err();
+        assert!(err.to_string().contains("`blake3` feature"), "{err}");
+    }
+
+    #[test]
+    fn frames_match_the_static_framers_and_read_back() {
+        fn stat<F: Framer>(framer: F) -> Vec<u8> {
+            let mut out = Vec::new();
+            framer.frame_and_write(&mut out, b"payload").unwrap();
+            out
+        }
+
+        for kind in ChecksumKind::ALL {
+            let bytes = stat(kind.framer());
+            assert_eq!(bytes.len(), 4 + kind.width() + 7, "{kind}");
+            let expected = match kind {
+                ChecksumKind::None => stat(DefaultFramer),
+                #[cfg(feature = "crc16")]
+                ChecksumKind::Crc16 => stat(ChecksumFramer::new(Crc16)),
+                #[cfg(feature = "crc32")]
+                ChecksumKind::Crc32 => stat(ChecksumFramer::new(Crc32)),
+                #[cfg(feature = "crc32c")]
+                ChecksumKind::Crc32c => stat(ChecksumFramer::new(Crc32c)),
+                #[cfg(feature = "xxhash")]
+                ChecksumKind::XxHash64 => stat(ChecksumFramer::new(XxHash64)),
+                #[cfg(feature = "xxhash")]
+                ChecksumKind::XxHash128 => stat(ChecksumFramer::new(XxHash128)),
+                #[cfg(feature = "sha256")]
+                ChecksumKind::Sha256 => stat(ChecksumFramer::new(Sha256)),
+                #[cfg(feature = "blake3")]
+                ChecksumKind::Blake3 => stat(ChecksumFramer::new(Blake3)),
+            };
+            assert_eq!(bytes, expected, "{kind}");
+
+            let deframer = kind.deframer();
+            let frame = deframer.deframe_slice(&bytes).unwrap().unwrap();
+            assert_eq!(&bytes[frame.payload], b"payload");
+            let mut reader = StreamReader::new(&bytes[..], deframer);
+            assert_eq!(reader.read_message().unwrap(), Some(&b"payload"[..]));
+
+            let err = kind
+                .deframer()
+                .with_max_frame_len(6)
+                .deframe_slice(&bytes)
+                .unwrap_err();
+            assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! A `tokio_util::codec` implementation for framed sockets (feature `tokio_codec`).
+
+use crate::checksum::{Checksum, NoChecksum};
+use crate::error::{Error, Result};
+use crate::framing::{ChecksumDeframer, ChecksumFramer, Framer, SliceDeframer};
+use crate::validation::{NoValidator, Validator};
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Largest payload a [`FlatStreamCodec`] accepts unless configured otherwise:
+/// 8 MiB, the default of tokio's `LengthDelimitedCodec`. A socket peer is
+/// untrusted, so the codec does not default to the 2 GiB file ceiling.
+pub const DEFAULT_CODEC_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// Most buffer space the decoder reserves beyond the bytes in hand while a
+/// frame is incomplete. A declared length commits memory only as the peer
+/// actually sends bytes.
+const RESERVE_CHUNK: usize = 64 * 1024;
+
+/// An [`Encoder`]/[`Decoder`] pair speaking the flatstream wire format, for use
+/// with `tokio_util::codec::Framed` and friends.
+///
+/// Frames are `[4-byte LE length | C::SIZE-byte checksum | payload]` — with
+/// the default `NoChecksum` exactly the `DefaultFramer` format, otherwise the
+/// `ChecksumFramer<C>` format. Decoded payloads are [`Bytes`] split off the
+/// read buffer: no copy is made. The decoder checks the declared length
+/// against [`DEFAULT_CODEC_MAX_FRAME_LEN`] (8 MiB, or the value set with
+/// [`with_max_frame_len`](Self::with_max_frame_len)) as soon as a header
+/// arrives, then verifies the checksum and runs the validator `V`. While a
+/// frame is incomplete, the read buffer grows at most 64 KiB beyond the bytes
+/// received, whatever the header declares. The encoder rejects payloads above
+/// the same ceiling, so a peer configured alike never sees an oversized frame.
+///
+/// ```rust
+/// use flatstream::{FlatStreamCodec, SizeValidator};
+/// use flatstream::NoChecksum;
+///
+/// // Default framing, 1 MiB ceiling, and a size check on every decoded frame.
+/// let codec = FlatStreamCodec::new(NoChecksum, SizeValidator::new(1, 1 << 20))
+///     .with_max_frame_len(1 << 20);
+/// // let framed = tokio_util::codec::Framed::new(tcp_stream, codec);
+/// # let _ = codec;
+/// ```
+///
+/// The validator runs on decode only: payloads from the peer are the
+/// untrusted ones. Validate on the sending side with a `ValidatingFramer` if
+/// required.
+pub struct FlatStreamCodec<C: Checksum = NoChecksum, V: Validator = NoValidator> {
+    framer: ChecksumFramer<C>,
+    deframer: ChecksumDeframer<C>,
+    validator: V,
+    max_frame_len: usize,
+}
+
+impl<C: Checksum + Clone, V: Validator> FlatStreamCodec<C, V> {
+    /// Creates a codec with the given checksum algorithm and validator,
+    /// accepting payloads up to [`DEFAULT_CODEC_MAX_FRAME_LEN`].
+    pub fn new(checksum: C, validator: V) -> Self {
+        Self {
+            framer: ChecksumFramer::new(checksum.clone()),
+            deframer: ChecksumDeframer::new(checksum)
+                .with_max_frame_len(DEFAULT_CODEC_MAX_FRAME_LEN),
+            validator,
+            max_frame_len: DEFAULT_CODEC_MAX_FRAME_LEN,
+        }
+    }
+}
+
+impl<C: Checksum, V: Validator> FlatStreamCodec<C, V> {
+    /// Sets the maximum payload length accepted by the decoder and produced
+    /// by the encoder.
+    pub fn with_max_frame_len(mut self, max: usize) -> Self {
+        self.deframer = self.deframer.with_max_frame_len(max);
+        self.max_frame_len = max;
+        self
+    }
+
+    /// The configured maximum payload length.
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+
+    /// Returns a reference to the validator.
+    pub fn validator(&self) -> &V {
+        &self.validator
+    }
+}
+
+impl<C: Checksum + Clone + Default, V: Validator + Default> Default for FlatStreamCodec<C, V> {
+    fn default() -> Self {
+        Self::new(C::default(), V::default())
+    }
+}
+
+impl<C: Checksum, V: Validator> Decoder for FlatStreamCodec<C, V> {
+    type Item = Bytes;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>> {
+        let Some(frame) = self.deframer.deframe_slice(src)? else {
+            // Once the header is in (and has passed the length check), make
+            // room for more of the frame, but never more than a chunk beyond
+            // what the peer has sent: a declared length is only a claim.
+            let header_len = 4 + C::SIZE;
+            if let Some(len_bytes) = src.get(..4).filter(|_| src.len() >= header_len) {
+                let payload_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+                src.reserve((header_len + payload_len - src.len()).min(RESERVE_CHUNK));
+            }
+            return Ok(None);
+        };
+        self.validator.validate(&src[frame.payload.clone()])?;
+        Ok(Some(src.split_to(frame.len).freeze().slice(frame.payload)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>> {
+        match self.decode(src)? {
+            Some(payload) => Ok(Some(payload)),
+            None if src.is_empty() => Ok(None),
+            // The peer closed mid-frame: a torn frame, as on the `Read` path.
+            None => Err(Error::unexpected_eof()),
+        }
+    }
+}
+
+impl<C: Checksum, V: Validator> Encoder<&[u8]> for FlatStreamCodec<C, V> {
+    type Error = Error;
+
+    fn encode(&mut self, payload: &[u8], dst: &mut BytesMut) -> Result<()> {
+        if payload.len() > self.max_frame_len {
+            return Err(Error::invalid_frame_with(
+                "payload length exceeds codec max_frame_len",
+                Some(payload.len()),
+                None,
+                Some(self.max_frame_len),
+            ));
+        }
+        dst.reserve(4 + C::SIZE + payload.len());
+        self.framer
+            .frame_and_write(&mut BufMut::writer(&mut *dst), payload)
+    }
+}
+
+impl<C: Checksum, V: Validator> Encoder<Bytes> for FlatStreamCodec<C, V> {
+    type Error = Error;
+
+    fn encode(&mut self, payload: Bytes, dst: &mut BytesMut) -> Result<()> {
+        self.encode(&payload[..], dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::validation::SizeValidator;
+    use crate::StreamReader;
+
+    #[cfg(feature = "crc32")]
+    use crate::Crc32;
+
+    #[test]
+    fn default_codec_speaks_default_framing() {
+        let mut codec = FlatStreamCodec::<NoChecksum, NoValidator>::default();
+        let mut wire = BytesMut::new();
+        codec.encode(&b"alpha"[..], &mut wire).unwrap();
+        codec
+            .encode(Bytes::from_static(b"beta"), &mut wire)
+            .unwrap();
+
+        let mut expected = Vec::new();
+        DefaultFramer
+            .frame_and_write(&mut expected, b"alpha")
+            .unwrap();
+        DefaultFramer
+            .frame_and_write(&mut expected, b"beta")
+            .unwrap();
+        assert_eq!(&wire[..], &expected[..]);
+
+        let mut reader = StreamReader::new(&expected[..], DefaultDeframer::new());
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"alpha");
+    }
+
+    #[test]
+    fn decoded_payloads_share_the_read_buffer() {
+        let mut codec = FlatStreamCodec::<NoChecksum, NoValidator>::default();
+        let mut src = BytesMut::new();
+        codec.encode(&b"zero-copy"[..], &mut src).unwrap();
+        let base = src.as_ptr();
+        let payload = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(&payload[..], b"zero-copy");
+        assert_eq!(payload.as_ptr(), base.wrapping_add(4));
+        assert!(src.is_empty());
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn byte_at_a_time_decode_with_checksum_and_validator() {
+        let mut codec = FlatStreamCodec::new(Crc32, SizeValidator::new(1, 64));
+        let mut wire = BytesMut::new();
+        for p in [&b"one"[..], b"two", b"three"] {
+            codec.encode(p, &mut wire).unwrap();
+        }
+        let mut src = BytesMut::new();
+        let mut out = Vec::new();
+        for byte in wire.iter() {
+            src.put_u8(*byte);
+            while let Some(payload) = codec.decode(&mut src).unwrap() {
+                out.push(payload);
+            }
+        }
+        assert_eq!(out, [&b"one"[..], b"two", b"three"]);
+
+        let mut corrupt = wire.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        let err = codec.decode(&mut corrupt.split_off(22)).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn length_ceiling_applies_both_ways() {
+        let mut codec =
+            FlatStreamCodec::new(NoChecksum, SizeValidator::new(0, 1024)).with_max_frame_len(8);
+        let mut dst = BytesMut::new();
+        let err = codec.encode(&[0u8; 9][..], &mut dst).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        assert!(dst.is_empty());
+
+        let mut src = BytesMut::from(&9u32.to_le_bytes()[..]);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[test]
+    fn declared_length_reserves_a_chunk_at_a_time() {
+        let mut codec = FlatStreamCodec::<NoChecksum, NoValidator>::default();
+        assert_eq!(codec.max_frame_len(), DEFAULT_CODEC_MAX_FRAME_LEN);
+        let mut src = BytesMut::from(&((8u32 << 20) + 1).to_le_bytes()[..]);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+
+        let mut codec =
+            FlatStreamCodec::<NoChecksum, NoValidator>::default().with_max_frame_len(1 << 30);
+        let mut src = BytesMut::from(&(1u32 << 30).to_le_bytes()[..]);
+        src.put_slice(&[0u8; 1000]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert!(src.capacity() <= src.len() + 2 * RESERVE_CHUNK);
+
+        // A frame that arrives in pieces still decodes.
+        let payload = vec![7u8; 3 * RESERVE_CHUNK];
+        let mut wire = BytesMut::new();
+        codec.encode(&payload[..], &mut wire).unwrap();
+        let mut src = BytesMut::new();
+        let mut decoded = None;
+        for piece in wire.chunks(RESERVE_CHUNK / 2) {
+            src.put_slice(piece);
+            decoded = codec.decode(&mut src).unwrap();
+            // `BytesMut` doubles on its own; the declared length adds nothing.
+            if decoded.is_none() {
+                assert!(src.capacity() <= 2 * (src.len() + RESERVE_CHUNK));
+            }
+        }
+        assert_eq!(&decoded.unwrap()[..], &payload[..]);
+    }
+
+    #[test]
+    fn eof_mid_frame_is_unexpected_eof() {
+        let mut codec = FlatStreamCodec::<NoChecksum, NoValidator>::default();
+        let mut src = BytesMut::from(&[4u8, 0, 0, 0, b'a'][..]);
+        let err = codec.decode_eof(&mut src).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+        assert!(codec.decode_eof(&mut BytesMut::new()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,726 @@
+//! Per-frame payload compression (features `lz4` and `zstd`).
+//!
+//! [`CompressingFramer`] compresses each payload and hands the result to an
+//! inner framer; [`DecompressingDeframer`] reads through an inner deframer
+//! and restores the payload. Because the adapters sit *above* the wire
+//! format, they compose with everything below them: a `ChecksumFramer`
+//! checksums the stored (compressed) bytes, and a `BoundedFramer` bounds
+//! them.
+//!
+//! The stored bytes of a frame end in a one-byte flag:
+//!
+//! ```text
+//! stored:      [payload | 0x00]
+//! compressed:  [compressed bytes | u32 LE payload length | flag]   flag 0x01 LZ4 block, 0x02 zstd
+//! dictionary:  [compressed bytes | u32 LE dictionary id | u32 LE payload length | 0x03]
+//! ```
+//!
+//! A payload is stored as is whenever compression would not make it smaller,
+//! so incompressible frames cost one byte. The flag trails the data so a
+//! stored payload is yielded in place, without shifting it.
+//!
+//! Decompression is bounded separately from the stored length:
+//! [`with_max_decompressed_len`](DecompressingDeframer::with_max_decompressed_len)
+//! rejects a declared payload length before any buffer is sized from it, and
+//! decompression into exactly that many bytes fails rather than overrun it —
+//! a small frame cannot expand into an unbounded allocation.
+//!
+//! Small frames of one schema share most of their structure with each other
+//! and little with themselves. A [`ZstdDictionary`] trained on a sample of a
+//! stream captures that structure, so a framer built with
+//! [`CompressingFramer::with_dictionary`] compresses even a single small
+//! frame well while every frame stays independently decodable. Such frames
+//! carry the dictionary's id, and a deframer given the dictionary with
+//! [`DecompressingDeframer::with_dictionary`] refuses frames compressed
+//! against any other.
+//!
+//! A deframer decodes every algorithm compiled into the crate, whichever one
+//! the writer chose. Payloads are decompressed into a buffer, so there is no
+//! [`SliceDeframer`](crate::SliceDeframer) implementation.
+
+use crate::error::{Error, Result};
+use crate::framing::{Deframer, Framer, DEFAULT_MAX_FRAME_LEN};
+#[cfg(feature = "zstd")]
+use crate::reader::StreamReader;
+use std::io::{Read, Write};
+use std::sync::{Mutex, MutexGuard};
+
+/// Flag of a payload stored without compression.
+pub const FLAG_STORED: u8 = 0;
+
+/// Flag of a payload compressed as one LZ4 block.
+pub const FLAG_LZ4: u8 = 1;
+
+/// Flag of a payload compressed as one zstd frame.
+pub const FLAG_ZSTD: u8 = 2;
+
+/// Flag of a payload compressed as one zstd frame against a trained
+/// [`ZstdDictionary`].
+pub const FLAG_ZSTD_DICT: u8 = 3;
+
+/// The compression algorithm a [`CompressingFramer`] applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// LZ4 block compression: fast, moderate ratio.
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// zstd at the given level (`0` selects zstd's default, currently 3).
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// Compression level, 1 (fastest) to 22 (smallest).
+        level: i32,
+    },
+}
+
+impl Compression {
+    pub(crate) fn flag(&self) -> u8 {
+        match self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => FLAG_LZ4,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => FLAG_ZSTD,
+        }
+    }
+
+    /// Replaces `out` with the compressed form of `payload`.
+    pub(crate) fn compress(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        match *self {
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => {
+                out.resize(lz4_flex::block::get_maximum_output_size(payload.len()), 0);
+                let n = lz4_flex::block::compress_into(payload, out)
+                    .map_err(|e| Error::invalid_frame(format!("lz4 compression failed: {e}")))?;
+                out.truncate(n);
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                out.resize(zstd::zstd_safe::compress_bound(payload.len()), 0);
+                let n = zstd::bulk::compress_to_buffer(payload, out, level)?;
+                out.truncate(n);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locks a scratch buffer or codec context. A panic elsewhere cannot leave
+/// either in a state that matters (a zstd context is reset at the start of
+/// every operation), so poisoning is ignored.
+fn lock<T>(scratch: &Mutex<T>) -> MutexGuard<'_, T> {
+    scratch.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A zstd dictionary trained on sample payloads, shared by the framer that
+/// compresses against it and the deframers that decompress.
+///
+/// Store the dictionary alongside the archive ([`as_bytes`](Self::as_bytes)
+/// and [`from_bytes`](Self::from_bytes)): frames compressed against it
+/// cannot be read without it.
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZstdDictionary {
+    bytes: Vec<u8>,
+    id: u32,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdDictionary {
+    /// Trains a dictionary of at most `max_len` bytes on up to
+    /// `max_samples` payloads read from `reader`.
+    ///
+    /// A few thousand representative payloads and a dictionary of 16–112
+    /// KiB are typical. Training fails if the sample is too small to learn
+    /// from.
+    pub fn train<R: Read, D: Deframer>(
+        reader: &mut StreamReader<R, D>,
+        max_samples: usize,
+        max_len: usize,
+    ) -> Result<Self> {
+        let mut samples = Vec::new();
+        let mut sizes = Vec::new();
+        while sizes.len() < max_samples {
+            let Some(payload) = reader.read_message()? else {
+                break;
+            };
+            samples.extend_from_slice(payload);
+            sizes.push(payload.len());
+        }
+        Self::from_bytes(zstd::dict::from_continuous(&samples, &sizes, max_len)?)
+    }
+
+    /// Wraps a dictionary previously saved with [`as_bytes`](Self::as_bytes),
+    /// rejecting bytes that are not a zstd dictionary with an id.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let Some(id) = zstd::zstd_safe::get_dict_id_from_dict(&bytes) else {
+            return Err(Error::invalid_frame("not a zstd dictionary"));
+        };
+        Ok(Self {
+            bytes,
+            id: id.get(),
+        })
+    }
+
+    /// The id recorded in every frame compressed against this dictionary.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The dictionary bytes, for storing alongside the stream.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A framer adapter that compresses each payload before passing it to the
+/// inner framer.
+///
+/// The compression output is staged in an internal scratch buffer that is
+/// reused across frames (it grows to the largest frame and stays there).
+///
+/// ```rust
+/// # #[cfg(feature = "lz4")] {
+/// use flatstream::compression::{Compression, CompressingFramer, DecompressingDeframer};
+/// use flatstream::{DefaultDeframer, DefaultFramer, StreamReader, StreamWriter};
+///
+/// let mut stream = Vec::new();
+/// let mut writer = StreamWriter::new(&mut stream, CompressingFramer::new(DefaultFramer, Compression::Lz4));
+/// writer.write_payload(&[7u8; 4096]).unwrap();
+/// assert!(stream.len() < 100);
+///
+/// let deframer = DecompressingDeframer::new(DefaultDeframer::new()).with_max_decompressed_len(1 << 20);
+/// let mut reader = StreamReader::new(&stream[..], deframer);
+/// assert_eq!(reader.read_message().unwrap().unwrap(), &[7u8; 4096][..]);
+/// # }
+/// ```
+pub struct CompressingFramer<F: Framer> {
+    inner: F,
+    compression: Compression,
+    scratch: Mutex<Vec<u8>>,
+    #[cfg(feature = "zstd")]
+    dictionary: Option<(u32, Mutex<zstd::bulk::Compressor<'static>>)>,
+}
+
+impl<F: Framer> CompressingFramer<F> {
+    pub fn new(inner: F, compression: Compression) -> Self {
+        Self {
+            inner,
+            compression,
+            scratch: Mutex::new(Vec::new()),
+            #[cfg(feature = "zstd")]
+            dictionary: None,
+        }
+    }
+
+    /// Compresses with zstd at `level` against `dictionary`. Fails only if
+    /// zstd cannot allocate the compression context.
+    #[cfg(feature = "zstd")]
+    pub fn with_dictionary(inner: F, level: i32, dictionary: &ZstdDictionary) -> Result<Self> {
+        let compressor = zstd::bulk::Compressor::with_dictionary(level, dictionary.as_bytes())?;
+        Ok(Self {
+            inner,
+            compression: Compression::Zstd { level },
+            scratch: Mutex::new(Vec::new()),
+            dictionary: Some((dictionary.id(), Mutex::new(compressor))),
+        })
+    }
+
+    /// Replaces `out` with the compressed form of `payload` and any fields
+    /// that precede the payload length, returning the frame's flag.
+    fn compress(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<u8> {
+        #[cfg(feature = "zstd")]
+        if let Some((id, compressor)) = &self.dictionary {
+            out.resize(zstd::zstd_safe::compress_bound(payload.len()), 0);
+            let n = lock(compressor).compress_to_buffer(payload, &mut out[..])?;
+            out.truncate(n);
+            out.extend_from_slice(&id.to_le_bytes());
+            return Ok(FLAG_ZSTD_DICT);
+        }
+        self.compression.compress(payload, out)?;
+        Ok(self.compression.flag())
+    }
+}
+
+impl<F: Framer> Framer for CompressingFramer<F> {
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        let Ok(payload_len) = u32::try_from(payload.len()) else {
+            return Err(Error::invalid_frame_with(
+                "payload length exceeds 32-bit header limit",
+                Some(payload.len()),
+                None,
+                Some(u32::MAX as usize),
+            ));
+        };
+        let mut stored = lock(&self.scratch);
+        let flag = self.compress(payload, &mut stored)?;
+        if stored.len() + 4 < payload.len() {
+            stored.extend_from_slice(&payload_len.to_le_bytes());
+            stored.push(flag);
+        } else {
+            stored.clear();
+            stored.extend_from_slice(payload);
+            stored.push(FLAG_STORED);
+        }
+        self.inner.frame_and_write(writer, &stored)
+    }
+
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        self.inner.holds_back_payloads()
+    }
+}
+
+/// A deframer adapter that restores payloads written by a
+/// [`CompressingFramer`] over a matching inner framer.
+///
+/// Stored payloads are yielded in place; compressed ones are decompressed
+/// into the caller's buffer, with the stored bytes read into an internal
+/// scratch buffer that is reused across frames.
+pub struct DecompressingDeframer<D: Deframer> {
+    inner: D,
+    max_decompressed_len: usize,
+    scratch: Mutex<Vec<u8>>,
+    #[cfg(feature = "zstd")]
+    dictionary: Option<(u32, Mutex<zstd::bulk::Decompressor<'static>>)>,
+}
+
+impl<D: Deframer> DecompressingDeframer<D> {
+    /// Wraps `inner`, accepting decompressed payloads up to
+    /// [`DEFAULT_MAX_FRAME_LEN`].
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            max_decompressed_len: DEFAULT_MAX_FRAME_LEN,
+            scratch: Mutex::new(Vec::new()),
+            #[cfg(feature = "zstd")]
+            dictionary: None,
+        }
+    }
+
+    /// Decompresses frames compressed against `dictionary`. Frames that
+    /// name a different dictionary are rejected. Fails only if zstd cannot
+    /// allocate the decompression context.
+    #[cfg(feature = "zstd")]
+    pub fn with_dictionary(mut self, dictionary: &ZstdDictionary) -> Result<Self> {
+        let decompressor = zstd::bulk::Decompressor::with_dictionary(dictionary.as_bytes())?;
+        self.dictionary = Some((dictionary.id(), Mutex::new(decompressor)));
+        Ok(self)
+    }
+
+    /// Decompresses a [`FLAG_ZSTD_DICT`] frame's `data` (compressed bytes
+    /// and dictionary id) into `out`.
+    #[cfg(feature = "zstd")]
+    fn decompress_with_dictionary(&self, data: &[u8], out: &mut [u8]) -> Result<usize> {
+        let Some((data, id)) = data.split_last_chunk::<4>() else {
+            return Err(Error::invalid_frame(
+                "dictionary-compressed frame too short for its dictionary id",
+            ));
+        };
+        let id = u32::from_le_bytes(*id);
+        match &self.dictionary {
+            Some((expected, decompressor)) if *expected == id => lock(decompressor)
+                .decompress_to_buffer(data, out)
+                .map_err(|e| Error::invalid_frame(format!("zstd decompression failed: {e}"))),
+            Some((expected, _)) => Err(Error::invalid_frame(format!(
+                "frame is compressed against zstd dictionary {id}, but the deframer has dictionary {expected}"
+            ))),
+            None => Err(Error::invalid_frame(format!(
+                "frame is compressed against zstd dictionary {id}, but the deframer has none"
+            ))),
+        }
+    }
+
+    /// Sets the maximum accepted decompressed payload length, checked
+    /// against the frame's declared length before any buffer is sized.
+    /// Bound it to the largest payload the writer produces: a compressed
+    /// frame far below the inner deframer's limit can still declare a huge
+    /// payload.
+    pub fn with_max_decompressed_len(mut self, max: usize) -> Self {
+        self.max_decompressed_len = max;
+        self
+    }
+
+    /// Turns the `len` stored bytes in `stored` into the payload in
+    /// `buffer[..n]`, returning `n`.
+    fn restore(&self, stored: &mut Vec<u8>, len: usize, buffer: &mut Vec<u8>) -> Result<usize> {
+        let Some((&flag, body)) = stored[..len].split_last() else {
+            return Err(Error::invalid_frame("compressed frame has no flag byte"));
+        };
+        if flag == FLAG_STORED {
+            std::mem::swap(stored, buffer);
+            return Ok(len - 1);
+        }
+        let Some((data, declared)) = body.split_last_chunk::<4>() else {
+            return Err(Error::invalid_frame(
+                "compressed frame too short for its length field",
+            ));
+        };
+        let payload_len = u32::from_le_bytes(*declared) as usize;
+        if payload_len > self.max_decompressed_len {
+            return Err(Error::invalid_frame_with(
+                "decompressed length exceeds configured limit",
+                Some(payload_len),
+                None,
+                Some(self.max_decompressed_len),
+            ));
+        }
+        if payload_len > buffer.len() {
+            buffer.resize(payload_len, 0);
+        }
+        let out = &mut buffer[..payload_len];
+        let n = match flag {
+            #[cfg(feature = "zstd")]
+            FLAG_ZSTD_DICT => self.decompress_with_dictionary(data, out)?,
+            #[cfg(not(feature = "zstd"))]
+            FLAG_ZSTD_DICT => return Err(feature_disabled("zstd", "zstd")),
+            _ => decompress_into(flag, data, out)?,
+        };
+        if n != payload_len {
+            return Err(Error::invalid_frame_with(
+                "decompressed length does not match the declared length",
+                Some(payload_len),
+                Some(n),
+                None,
+            ));
+        }
+        Ok(payload_len)
+    }
+}
+
+impl<D: Deframer> Deframer for DecompressingDeframer<D> {
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        let mut stored = lock(&self.scratch);
+        match self.inner.read_and_deframe(reader, &mut stored)? {
+            Some(len) => self.restore(&mut stored, len, buffer).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        let mut stored = lock(&self.scratch);
+        match self
+            .inner
+            .read_after_length(reader, &mut stored, payload_len)?
+        {
+            Some(len) => self.restore(&mut stored, len, buffer).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Decompresses `data`, compressed with the algorithm `flag` names, into
+/// `out`, returning the number of bytes written. Output that would not fit
+/// in `out` is an error, never an overrun.
+pub(crate) fn decompress_into(flag: u8, data: &[u8], out: &mut [u8]) -> Result<usize> {
+    match flag {
+        #[cfg(feature = "lz4")]
+        FLAG_LZ4 => lz4_flex::block::decompress_into(data, out)
+            .map_err(|e| Error::invalid_frame(format!("lz4 decompression failed: {e}"))),
+        #[cfg(feature = "zstd")]
+        FLAG_ZSTD => zstd::bulk::decompress_to_buffer(data, out)
+            .map_err(|e| Error::invalid_frame(format!("zstd decompression failed: {e}"))),
+        #[cfg(not(feature = "lz4"))]
+        FLAG_LZ4 => Err(feature_disabled("LZ4", "lz4")),
+        #[cfg(not(feature = "zstd"))]
+        FLAG_ZSTD => Err(feature_disabled("zstd", "zstd")),
+        _ => Err(Error::invalid_frame(format!(
+            "unknown compression flag {flag:#04x}"
+        ))),
+    }
+}
+
+/// Decompresses `data`, compressed with the algorithm `flag` names, into
+/// `out`, replacing its contents with exactly `len` bytes.
+///
+/// `len` usually comes from an untrusted header, so it is checked against
+/// what `data` can produce before any memory is committed: LZ4 expands at
+/// most 255-fold, and a zstd frame must declare the same content size. zstd
+/// then decompresses into reserved, uninitialized capacity; only LZ4, whose
+/// safe decoder needs an initialized buffer, zero-fills — at most 255 times
+/// the bytes actually received.
+pub(crate) fn decompress_to_vec(
+    flag: u8,
+    data: &[u8],
+    out: &mut Vec<u8>,
+    len: usize,
+) -> Result<()> {
+    out.clear();
+    let n = match flag {
+        #[cfg(feature = "lz4")]
+        FLAG_LZ4 => {
+            if len > data.len().saturating_mul(255) {
+                return Err(implausible_len(len, data.len()));
+            }
+            out.resize(len, 0);
+            lz4_flex::block::decompress_into(data, out)
+                .map_err(|e| Error::invalid_frame(format!("lz4 decompression failed: {e}")))?
+        }
+        #[cfg(feature = "zstd")]
+        FLAG_ZSTD => {
+            match zstd::zstd_safe::get_frame_content_size(data) {
+                Ok(Some(size)) if size == len as u64 => {}
+                Ok(None) => {}
+                _ => return Err(implausible_len(len, data.len())),
+            }
+            out.reserve_exact(len);
+            zstd::bulk::Decompressor::new()?
+                .decompress_to_buffer(data, out)
+                .map_err(|e| Error::invalid_frame(format!("zstd decompression failed: {e}")))?
+        }
+        // Disabled and unknown algorithms: `decompress_into` reports them.
+        _ => decompress_into(flag, data, &mut [])?,
+    };
+    if n != len {
+        return Err(Error::invalid_frame_with(
+            "decompressed length does not match the declared length",
+            Some(len),
+            Some(n),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+#[cold]
+fn implausible_len(len: usize, compressed_len: usize) -> Error {
+    Error::invalid_frame_with(
+        "declared length cannot come from the compressed bytes",
+        Some(len),
+        Some(compressed_len),
+        None,
+    )
+}
+
+#[cfg(not(all(feature = "lz4", feature = "zstd")))]
+#[cold]
+fn feature_disabled(algorithm: &str, feature: &str) -> Error {
+    Error::invalid_frame(format!(
+        "frame is {algorithm}-compressed, but the `{feature}` feature is not enabled"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "lz4")]
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+
+    fn telemetry() -> Vec<u8> {
+        (0..2048u32).flat_map(|i| (i % 16).to_le_bytes()).collect()
+    }
+
+    fn round_trip(compression: Compression) {
+        let framer = CompressingFramer::new(DefaultFramer, compression);
+        let compressible = telemetry();
+        let incompressible: Vec<u8> = (0..64u64)
+            .map(|i| (i.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 56) as u8)
+            .collect();
+        let mut stream = Vec::new();
+        for payload in [&compressible[..], &incompressible, b""] {
+            framer.frame_and_write(&mut stream, payload).unwrap();
+        }
+        assert!(stream.len() < compressible.len() / 4);
+        // Incompressible and empty payloads are stored with a trailing flag.
+        let tail = &stream[stream.len() - 5..];
+        assert_eq!(tail, [1, 0, 0, 0, FLAG_STORED]);
+
+        let deframer = DecompressingDeframer::new(DefaultDeframer::new());
+        let mut reader = StreamReader::new(&stream[..], deframer);
+        assert_eq!(reader.read_message().unwrap().unwrap(), &compressible[..]);
+        assert_eq!(reader.read_message().unwrap().unwrap(), &incompressible[..]);
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"");
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4_round_trips_and_stores_what_does_not_compress() {
+        round_trip(Compression::Lz4);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trips_and_stores_what_does_not_compress() {
+        round_trip(Compression::Zstd { level: 3 });
+    }
+
+    #[cfg(feature = "zstd")]
+    fn sensor_events(seed: u32, n: u32) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for i in seed..seed + n {
+            let event = format!(
+                "{{\"sensor\":\"probe-{:02}\",\"seq\":{i},\"temp_c\":{}.{},\"status\":\"{}\"}}",
+                i % 16,
+                18 + i % 7,
+                i % 10,
+                ["nominal", "degraded", "offline"][(i % 3) as usize]
+            );
+            DefaultFramer
+                .frame_and_write(&mut stream, event.as_bytes())
+                .unwrap();
+        }
+        stream
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn trained_dictionary_compresses_small_frames() {
+        let sample = sensor_events(0, 2000);
+        let mut reader = StreamReader::new(&sample[..], DefaultDeframer::new());
+        let dictionary = ZstdDictionary::train(&mut reader, 1000, 4096).unwrap();
+        assert!(dictionary.as_bytes().len() <= 4096);
+        let reloaded = ZstdDictionary::from_bytes(dictionary.as_bytes().to_vec()).unwrap();
+        assert_eq!(reloaded, dictionary);
+
+        // Re-frame fresh events: alone they barely compress, against the
+        // dictionary they shrink by more than a third, frame headers included.
+        let plain = CompressingFramer::new(DefaultFramer, Compression::Zstd { level: 3 });
+        let framer = CompressingFramer::with_dictionary(DefaultFramer, 3, &dictionary).unwrap();
+        let source = sensor_events(5000, 200);
+        let (mut without, mut with) = (Vec::new(), Vec::new());
+        let mut reader = StreamReader::new(&source[..], DefaultDeframer::new());
+        reader
+            .process_all(|payload| {
+                plain.frame_and_write(&mut without, payload)?;
+                framer.frame_and_write(&mut with, payload)
+            })
+            .unwrap();
+        assert!(
+            with.len() * 3 < without.len() * 2,
+            "{} vs {}",
+            with.len(),
+            without.len()
+        );
+        assert_eq!(with[with.len() - 1], FLAG_ZSTD_DICT);
+        let id_at = with.len() - 9;
+        assert_eq!(with[id_at..id_at + 4], dictionary.id().to_le_bytes());
+
+        let deframer = DecompressingDeframer::new(DefaultDeframer::new())
+            .with_dictionary(&dictionary)
+            .unwrap();
+        let mut restored = Vec::new();
+        let mut reader = StreamReader::new(&with[..], deframer);
+        reader
+            .process_all(|payload| {
+                DefaultFramer.frame_and_write(&mut restored, payload)?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(restored, source);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn frames_are_refused_without_their_dictionary() {
+        let sample = sensor_events(0, 2000);
+        let train = |max_len| {
+            let mut reader = StreamReader::new(&sample[..], DefaultDeframer::new());
+            ZstdDictionary::train(&mut reader, usize::MAX, max_len).unwrap()
+        };
+        let (dictionary, other) = (train(4096), train(2048));
+        assert_ne!(dictionary.id(), other.id());
+
+        let mut stream = Vec::new();
+        CompressingFramer::with_dictionary(DefaultFramer, 3, &dictionary)
+            .unwrap()
+            .frame_and_write(&mut stream, &sample[4..100])
+            .unwrap();
+        for deframer in [
+            DecompressingDeframer::new(DefaultDeframer::new()),
+            DecompressingDeframer::new(DefaultDeframer::new())
+                .with_dictionary(&other)
+                .unwrap(),
+        ] {
+            let err = StreamReader::new(&stream[..], deframer)
+                .read_message()
+                .unwrap_err();
+            assert!(err.to_string().contains("dictionary"), "{err}");
+        }
+
+        assert!(ZstdDictionary::from_bytes(b"not a dictionary".to_vec()).is_err());
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn declared_length_is_bounded_before_decompressing() {
+        let mut stream = Vec::new();
+        CompressingFramer::new(DefaultFramer, Compression::Lz4)
+            .frame_and_write(&mut stream, &[0u8; 1 << 16])
+            .unwrap();
+        assert!(stream.len() < 1024);
+
+        let deframer =
+            DecompressingDeframer::new(DefaultDeframer::new()).with_max_decompressed_len(1 << 12);
+        let mut reader = StreamReader::with_capacity(&stream[..], deframer, 0);
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::InvalidFrame {
+                declared_len: Some(65536),
+                limit: Some(4096),
+                ..
+            }
+        ));
+        assert_eq!(reader.buffer_capacity(), 0);
+
+        // A declared length smaller than the real output fails to decompress
+        // instead of overrunning.
+        let flag_at = stream.len() - 1;
+        stream[flag_at - 4..flag_at].copy_from_slice(&16u32.to_le_bytes());
+        let mut reader = StreamReader::new(
+            &stream[..],
+            DecompressingDeframer::new(DefaultDeframer::new()),
+        );
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[cfg(all(feature = "lz4", feature = "crc32"))]
+    #[test]
+    fn composes_with_checksum_and_bounded_framers() {
+        use crate::checksum::Crc32;
+        use crate::framing::{ChecksumDeframer, ChecksumFramer, FramerExt};
+
+        let payload = telemetry();
+        let framer = CompressingFramer::new(
+            ChecksumFramer::new(Crc32::new()).bounded(1024),
+            Compression::Lz4,
+        );
+        let mut stream = Vec::new();
+        framer.frame_and_write(&mut stream, &payload).unwrap();
+        // The bound applies to the stored bytes: the 8 KiB payload fits
+        // compressed, an incompressible 2 KiB one does not.
+        let noise: Vec<u8> = (0..2048u64)
+            .map(|i| (i.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 56) as u8)
+            .collect();
+        let err = framer.frame_and_write(&mut Vec::new(), &noise).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+
+        let deframer = DecompressingDeframer::new(ChecksumDeframer::new(Crc32::new()));
+        let mut reader = StreamReader::new(&stream[..], deframer);
+        assert_eq!(reader.read_message().unwrap().unwrap(), &payload[..]);
+
+        // The checksum covers the stored (compressed) bytes.
+        let mut corrupt = stream.clone();
+        let last = corrupt.len() - 6;
+        corrupt[last] ^= 0x01;
+        let deframer = DecompressingDeframer::new(ChecksumDeframer::new(Crc32::new()));
+        let err = StreamReader::new(&corrupt[..], deframer)
+            .read_message()
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+    }
+}
@@ -0,0 +1,518 @@
+//! Sealed containers: a finished stream with a header, a footer index and a
+//! trailer, for random access without scanning.
+//!
+//! A journal is append-only and self-delimiting only frame by frame; once it
+//! is complete it can be sealed into a container that describes itself:
+//!
+//! ```text
+//! [header] [frames...] [footer] [trailer]
+//!
+//! header  (8 bytes)   magic "FSCN" | version u16 | checksum algorithm id u8 | checksum width u8
+//! frames              the ordinary wire format, framed with that checksum
+//! footer              frame count u64 | payload bytes u64 | min payload len u32 | max payload len u32
+//!                     | frame index (the sidecar `FrameIndex` format, absolute offsets)
+//! trailer (28 bytes)  footer offset u64 | footer len u64 | footer checksum u64 | magic "FSCE"
+//! ```
+//!
+//! All integers are little-endian. The trailer has a fixed size, so a reader
+//! finds the footer from the end of the file; the footer checksum (the low
+//! `width` bytes of the field, computed with the container's algorithm) covers
+//! the whole footer. [`ContainerReader::open`] validates header, trailer and
+//! footer before reading a single frame, and never scans: a file without a
+//! valid trailer is rejected, not repaired. Conversely, journal recovery must
+//! never be pointed at a sealed container.
+
+use crate::checksum::Checksum;
+use crate::error::{Error, Result};
+use crate::framing::{ChecksumDeframer, ChecksumFramer, Deframer};
+use crate::index::FrameIndex;
+use crate::reader::StreamReader;
+use crate::traits::StreamSerialize;
+use crate::writer::StreamWriter;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// Magic bytes opening every sealed container.
+pub const CONTAINER_MAGIC: [u8; 4] = *b"FSCN";
+
+/// Magic bytes closing every sealed container (the last four bytes of the
+/// trailer).
+pub const CONTAINER_TRAILER_MAGIC: [u8; 4] = *b"FSCE";
+
+/// The container format version written by this crate.
+pub const CONTAINER_VERSION: u16 = 1;
+
+/// Size of the container header; the first frame starts at this offset.
+pub const CONTAINER_HEADER_LEN: usize = 8;
+
+/// Size of the fixed trailer at the end of every container.
+pub const CONTAINER_TRAILER_LEN: usize = 28;
+
+const SUMMARY_LEN: usize = 24;
+
+/// Summary statistics stored in a container's footer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContainerSummary {
+    /// Number of frames in the container.
+    pub frame_count: u64,
+    /// Sum of all payload lengths.
+    pub payload_bytes: u64,
+    /// Smallest payload length (0 for an empty container).
+    pub min_payload_len: u32,
+    /// Largest payload length (0 for an empty container).
+    pub max_payload_len: u32,
+}
+
+impl ContainerSummary {
+    fn of(index: &FrameIndex) -> Self {
+        let lens = index.entries().iter().map(|e| e.payload_len);
+        Self {
+            frame_count: index.frame_count(),
+            payload_bytes: lens.clone().map(u64::from).sum(),
+            min_payload_len: lens.clone().min().unwrap_or(0),
+            max_payload_len: lens.max().unwrap_or(0),
+        }
+    }
+}
+
+/// Writes a sealed container: the header up front, then frames, then on
+/// [`finish`](Self::finish) the footer and trailer.
+///
+/// The frames are `ChecksumFramer<C>` frames; with `NoChecksum` they are
+/// exactly the `DefaultFramer` format. A container that was never finished
+/// has no trailer and is rejected by [`ContainerReader::open`] — its frame
+/// region is still an ordinary stream behind an 8-byte header.
+///
+/// ```rust
+/// use flatstream::{ContainerReader, ContainerWriter, NoChecksum, Result};
+/// use std::io::Cursor;
+///
+/// # fn main() -> Result<()> {
+/// let mut writer = ContainerWriter::new(Cursor::new(Vec::new()), NoChecksum)?;
+/// for i in 0..10 {
+///     writer.write(&format!("event {i}"))?;
+/// }
+/// let sealed = writer.finish()?.into_inner();
+///
+/// let mut reader = ContainerReader::open(Cursor::new(sealed), NoChecksum)?;
+/// assert_eq!(reader.frame_count(), 10);
+/// let root = flatbuffers::root::<&str>(reader.read_frame(7)?)?;
+/// assert_eq!(root, "event 7");
+/// # Ok(())
+/// # }
+/// ```
+pub struct ContainerWriter<'a, W: Write, C: Checksum> {
+    inner: StreamWriter<'a, W, ChecksumFramer<C>>,
+    checksum: C,
+}
+
+impl<'a, W: Write, C: Checksum + Clone> ContainerWriter<'a, W, C> {
+    /// Writes the container header to `writer` and returns a writer for its
+    /// frames. `writer` must be positioned at the start of the container.
+    pub fn new(mut writer: W, checksum: C) -> Result<Self> {
+        let mut header = [0u8; CONTAINER_HEADER_LEN];
+        header[..4].copy_from_slice(&CONTAINER_MAGIC);
+        header[4..6].copy_from_slice(&CONTAINER_VERSION.to_le_bytes());
+        header[6] = C::ALGORITHM_ID;
+        header[7] = C::SIZE as u8;
+        writer.write_all(&header)?;
+        let inner = StreamWriter::new(writer, ChecksumFramer::new(checksum.clone()))
+            .with_index(FrameIndex::starting_at(CONTAINER_HEADER_LEN as u64));
+        Ok(Self { inner, checksum })
+    }
+}
+
+impl<'a, W: Write, C: Checksum> ContainerWriter<'a, W, C> {
+    /// Serializes and writes one item (see [`StreamWriter::write`]).
+    pub fn write<T: StreamSerialize>(&mut self, item: &T) -> Result<()> {
+        self.inner.write(item)
+    }
+
+    /// Writes a finished builder's payload (see
+    /// [`StreamWriter::write_finished`]).
+    pub fn write_finished<A: flatbuffers::Allocator>(
+        &mut self,
+        builder: &mut flatbuffers::FlatBufferBuilder<A>,
+    ) -> Result<()> {
+        self.inner.write_finished(builder)
+    }
+
+    /// Writes already-serialized payload bytes (see
+    /// [`StreamWriter::write_payload`]).
+    pub fn write_payload(&mut self, payload: &[u8]) -> Result<()> {
+        self.inner.write_payload(payload)
+    }
+
+    /// Summary of the frames written so far.
+    pub fn summary(&self) -> ContainerSummary {
+        self.inner
+            .index()
+            .map(ContainerSummary::of)
+            .unwrap_or_default()
+    }
+
+    /// Writes the footer and trailer, flushes, and returns the underlying
+    /// writer. The container is complete only once this returns `Ok`.
+    pub fn finish(mut self) -> Result<W> {
+        let index = self
+            .inner
+            .take_index()
+            .expect("container writer always records an index");
+        let summary = ContainerSummary::of(&index);
+
+        let mut footer = Vec::with_capacity(SUMMARY_LEN + 24 + 12 * index.entries().len());
+        footer.extend_from_slice(&summary.frame_count.to_le_bytes());
+        footer.extend_from_slice(&summary.payload_bytes.to_le_bytes());
+        footer.extend_from_slice(&summary.min_payload_len.to_le_bytes());
+        footer.extend_from_slice(&summary.max_payload_len.to_le_bytes());
+        index.write_to(&mut footer)?;
+
+        let mut trailer = [0u8; CONTAINER_TRAILER_LEN];
+        trailer[..8].copy_from_slice(&index.end_offset().to_le_bytes());
+        trailer[8..16].copy_from_slice(&(footer.len() as u64).to_le_bytes());
+        let mut staged = [0u8; 8];
+        let wire = self
+            .checksum
+            .write_bytes(self.checksum.calculate(&footer), &mut staged);
+        trailer[16..16 + wire.len()].copy_from_slice(wire);
+        trailer[24..].copy_from_slice(&CONTAINER_TRAILER_MAGIC);
+
+        let writer = self.inner.get_mut();
+        writer.write_all(&footer)?;
+        writer.write_all(&trailer)?;
+        self.inner.flush()?;
+        Ok(self.inner.into_inner())
+    }
+}
+
+/// Copies every frame of a complete journal into a sealed container.
+///
+/// `journal` is read with `deframer` (so its checksums and validators apply)
+/// and each payload is re-framed with `checksum` into `output`, which is
+/// returned once the container is finished. The journal must end cleanly: a
+/// torn tail fails with `UnexpectedEof`, so repair a crashed journal with
+/// [`recover_file`](crate::recover_file) first.
+pub fn seal_journal<R, D, W, C>(journal: R, deframer: D, output: W, checksum: C) -> Result<W>
+where
+    R: Read,
+    D: Deframer,
+    W: Write,
+    C: Checksum + Clone,
+{
+    let mut reader = StreamReader::new(journal, deframer);
+    let mut writer = ContainerWriter::new(output, checksum)?;
+    while let Some(payload) = reader.read_message()? {
+        writer.write_payload(payload)?;
+    }
+    writer.finish()
+}
+
+/// Random access to a sealed container.
+///
+/// Opening reads the header, trailer and footer only; frames are read on
+/// demand through a `ChecksumDeframer<C>` bounded by the footer's largest
+/// payload length, so a corrupt frame cannot demand a larger allocation than
+/// any frame the writer produced.
+pub struct ContainerReader<R: Read + Seek, C: Checksum> {
+    reader: StreamReader<R, ChecksumDeframer<C>>,
+    summary: ContainerSummary,
+}
+
+impl<R: Read + Seek, C: Checksum> ContainerReader<R, C> {
+    /// Opens and validates a sealed container.
+    ///
+    /// Fails with `ErrorKind::InvalidFrame` when the file is not a sealed
+    /// container (bad magic, unsupported version, missing or inconsistent
+    /// trailer) or was written with a different checksum algorithm or width
+    /// than `C`, and with `ErrorKind::ChecksumMismatch` when the footer is
+    /// corrupt.
+    pub fn open(mut reader: R, checksum: C) -> Result<Self> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        if file_len < (CONTAINER_HEADER_LEN + CONTAINER_TRAILER_LEN) as u64 {
+            return Err(not_a_container("file shorter than header and trailer"));
+        }
+
+        let mut header = [0u8; CONTAINER_HEADER_LEN];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header)?;
+        if header[..4] != CONTAINER_MAGIC {
+            return Err(not_a_container("bad header magic"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != CONTAINER_VERSION {
+            return Err(Error::invalid_frame_with(
+                "unsupported container version",
+                Some(version as usize),
+                None,
+                Some(CONTAINER_VERSION as usize),
+            ));
+        }
+        if header[6] != C::ALGORITHM_ID || header[7] as usize != C::SIZE {
+            return Err(Error::invalid_frame_with(
+                "container checksum algorithm does not match the reader's",
+                Some(header[6] as usize),
+                Some(header[7] as usize),
+                Some(C::ALGORITHM_ID as usize),
+            ));
+        }
+
+        let mut trailer = [0u8; CONTAINER_TRAILER_LEN];
+        reader.seek(SeekFrom::End(-(CONTAINER_TRAILER_LEN as i64)))?;
+        reader.read_exact(&mut trailer)?;
+        if trailer[24..] != CONTAINER_TRAILER_MAGIC {
+            return Err(not_a_container("missing trailer (never sealed?)"));
+        }
+        let footer_offset = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+        let footer_len = u64::from_le_bytes(trailer[8..16].try_into().unwrap());
+        let footer_end = footer_offset
+            .checked_add(footer_len)
+            .and_then(|end| end.checked_add(CONTAINER_TRAILER_LEN as u64));
+        if footer_offset < CONTAINER_HEADER_LEN as u64 || footer_end != Some(file_len) {
+            return Err(not_a_container("trailer does not point at a footer"));
+        }
+
+        // Bounded by the file length just checked.
+        let mut footer = vec![0u8; footer_len as usize];
+        reader.seek(SeekFrom::Start(footer_offset))?;
+        reader.read_exact(&mut footer)?;
+        checksum.verify(checksum.read_bytes(&trailer[16..24]), &footer)?;
+
+        let Some(summary_bytes) = footer.first_chunk::<SUMMARY_LEN>() else {
+            return Err(not_a_container("footer shorter than its summary"));
+        };
+        let summary = ContainerSummary {
+            frame_count: u64::from_le_bytes(summary_bytes[..8].try_into().unwrap()),
+            payload_bytes: u64::from_le_bytes(summary_bytes[8..16].try_into().unwrap()),
+            min_payload_len: u32::from_le_bytes(summary_bytes[16..20].try_into().unwrap()),
+            max_payload_len: u32::from_le_bytes(summary_bytes[20..24].try_into().unwrap()),
+        };
+        let index = FrameIndex::read_from(&footer[SUMMARY_LEN..])?;
+        let first = index.entries().first().map_or(footer_offset, |e| e.offset);
+        if index.end_offset() != footer_offset
+            || first != CONTAINER_HEADER_LEN as u64
+            || summary != ContainerSummary::of(&index)
+        {
+            return Err(not_a_container("footer index disagrees with the layout"));
+        }
+
+        let deframer =
+            ChecksumDeframer::new(checksum).with_max_frame_len(summary.max_payload_len as usize);
+        let reader = StreamReader::new(reader, deframer).with_index(index);
+        Ok(Self { reader, summary })
+    }
+
+    /// The footer's summary statistics.
+    pub fn summary(&self) -> &ContainerSummary {
+        &self.summary
+    }
+
+    /// Number of frames in the container.
+    pub fn frame_count(&self) -> u64 {
+        self.summary.frame_count
+    }
+
+    /// The container's frame index (absolute offsets).
+    pub fn index(&self) -> &FrameIndex {
+        self.reader
+            .index()
+            .expect("container reader always installs its index")
+    }
+
+    /// Reads frame `n` (zero-based). The payload borrows the reader's buffer
+    /// and is valid until the next read.
+    pub fn read_frame(&mut self, n: u64) -> Result<&[u8]> {
+        if n >= self.summary.frame_count {
+            return Err(Error::invalid_frame_with(
+                "frame number beyond the container",
+                usize::try_from(n).ok(),
+                None,
+                usize::try_from(self.summary.frame_count).ok(),
+            ));
+        }
+        self.reader.seek_to_frame(n)?;
+        self.reader
+            .read_message()?
+            .ok_or_else(Error::unexpected_eof)
+    }
+
+    /// Processes every frame in order. Reading stops at the footer; it is
+    /// never handed to the deframer.
+    pub fn process_all<F>(&mut self, mut processor: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        self.reader.seek_to_frame(0)?;
+        for _ in 0..self.summary.frame_count {
+            let payload = self
+                .reader
+                .read_message()?
+                .ok_or_else(Error::unexpected_eof)?;
+            processor(payload)?;
+        }
+        Ok(())
+    }
+
+    /// Consumes the reader, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+#[cold]
+fn not_a_container(reason: &'static str) -> Error {
+    Error::invalid_frame(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::NoChecksum;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer, Framer};
+    use std::io::Cursor;
+
+    #[cfg(all(feature = "crc32", feature = "crc16"))]
+    use crate::{Crc16, Crc32};
+
+    fn open_err<R: Read + Seek, C: Checksum>(reader: R, checksum: C) -> Error {
+        match ContainerReader::open(reader, checksum) {
+            Ok(_) => panic!("container unexpectedly opened"),
+            Err(e) => e,
+        }
+    }
+
+    fn sealed<C: Checksum + Clone>(checksum: C, payloads: &[&[u8]]) -> Vec<u8> {
+        let mut writer = ContainerWriter::new(Cursor::new(Vec::new()), checksum).unwrap();
+        for p in payloads {
+            writer.write_payload(p).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn layout_is_header_frames_footer_trailer() {
+        let bytes = sealed(NoChecksum, &[b"abc", b"de"]);
+        assert_eq!(&bytes[..4], b"FSCN");
+        assert_eq!(&bytes[4..8], &[1, 0, 0, 0]);
+        let mut frames = Vec::new();
+        DefaultFramer.frame_and_write(&mut frames, b"abc").unwrap();
+        DefaultFramer.frame_and_write(&mut frames, b"de").unwrap();
+        assert_eq!(&bytes[8..8 + frames.len()], &frames[..]);
+        assert_eq!(&bytes[bytes.len() - 4..], b"FSCE");
+    }
+
+    #[test]
+    fn random_access_and_summary() {
+        let payloads: [&[u8]; 4] = [b"zero", b"", b"two-two", b"three"];
+        let mut reader =
+            ContainerReader::open(Cursor::new(sealed(NoChecksum, &payloads)), NoChecksum).unwrap();
+        assert_eq!(
+            *reader.summary(),
+            ContainerSummary {
+                frame_count: 4,
+                payload_bytes: 16,
+                min_payload_len: 0,
+                max_payload_len: 7,
+            }
+        );
+        for n in [2u64, 0, 3, 1] {
+            assert_eq!(reader.read_frame(n).unwrap(), payloads[n as usize]);
+        }
+        let err = reader.read_frame(4).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+
+        let mut seen = Vec::new();
+        reader
+            .process_all(|p| {
+                seen.push(p.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, payloads);
+    }
+
+    #[test]
+    fn empty_container_opens() {
+        let mut reader =
+            ContainerReader::open(Cursor::new(sealed(NoChecksum, &[])), NoChecksum).unwrap();
+        assert_eq!(reader.frame_count(), 0);
+        reader.process_all(|_| panic!("no frames")).unwrap();
+    }
+
+    #[test]
+    fn seal_journal_copies_every_frame() {
+        let mut journal = Vec::new();
+        for p in [&b"one"[..], b"two", b"three"] {
+            DefaultFramer.frame_and_write(&mut journal, p).unwrap();
+        }
+        let out = seal_journal(
+            &journal[..],
+            DefaultDeframer::new(),
+            Cursor::new(Vec::new()),
+            NoChecksum,
+        )
+        .unwrap()
+        .into_inner();
+        let mut reader = ContainerReader::open(Cursor::new(out), NoChecksum).unwrap();
+        assert_eq!(reader.read_frame(2).unwrap(), b"three");
+
+        // A torn journal is not sealed.
+        journal.extend_from_slice(&[9, 0, 0]);
+        let err = seal_journal(
+            &journal[..],
+            DefaultDeframer::new(),
+            Cursor::new(Vec::new()),
+            NoChecksum,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn unsealed_and_truncated_files_are_rejected() {
+        let bytes = sealed(NoChecksum, &[b"abc"]);
+        for cut in [0, 7, bytes.len() - 1] {
+            let err = open_err(Cursor::new(&bytes[..cut]), NoChecksum);
+            assert!(
+                matches!(err.kind(), ErrorKind::InvalidFrame { .. }),
+                "cut at {cut}"
+            );
+        }
+        let mut journal = Vec::new();
+        DefaultFramer
+            .frame_and_write(&mut journal, &[0u8; 64])
+            .unwrap();
+        let err = open_err(Cursor::new(journal), NoChecksum);
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[cfg(all(feature = "crc32", feature = "crc16"))]
+    #[test]
+    fn checksum_algorithm_is_checked_and_covers_the_footer() {
+        let bytes = sealed(Crc32, &[b"abc", b"def"]);
+        assert_eq!(bytes[6], Crc32::ALGORITHM_ID);
+        assert_eq!(bytes[7], 4);
+        let err = open_err(Cursor::new(bytes.clone()), Crc16);
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        let err = open_err(Cursor::new(bytes.clone()), NoChecksum);
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+
+        // Flip a byte inside the footer's summary.
+        let mut corrupt = bytes.clone();
+        let footer = u64::from_le_bytes(
+            corrupt[corrupt.len() - CONTAINER_TRAILER_LEN..][..8]
+                .try_into()
+                .unwrap(),
+        ) as usize;
+        corrupt[footer + 8] ^= 0x01;
+        let err = open_err(Cursor::new(corrupt), Crc32);
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+
+        // A corrupt frame is caught by the frame checksum on read.
+        let mut corrupt = bytes;
+        corrupt[CONTAINER_HEADER_LEN + 8] ^= 0x01;
+        let mut reader = ContainerReader::open(Cursor::new(corrupt), Crc32).unwrap();
+        assert_eq!(reader.read_frame(1).unwrap(), b"def");
+        let err = reader.read_frame(0).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+    }
+}
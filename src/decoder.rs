@@ -0,0 +1,301 @@
+//! A push-based ("sans-IO") frame decoder for event-loop integrations.
+
+use crate::error::{Error, Result};
+use crate::framing::SliceDeframer;
+use std::io::Read;
+use std::ops::Range;
+
+/// The most bytes [`FrameDecoder::read_from`] reads per call. Only this
+/// window is zeroed before the read, however much spare capacity an earlier
+/// large frame left behind.
+pub(crate) const READ_CHUNK: usize = 8 * 1024;
+
+/// A decoder that is fed bytes as they arrive and emits complete frames.
+///
+/// Where [`StreamReader`](crate::StreamReader) pulls from a blocking `Read`
+/// and treats a short read as `UnexpectedEof`, a `FrameDecoder` never performs
+/// I/O itself: the caller pushes whatever bytes arrived with
+/// [`feed`](Self::feed) (or [`read_from`](Self::read_from) on a non-blocking
+/// source) and drains frames with [`decode`](Self::decode), which answers
+/// "need more bytes" with `Ok(None)` instead of an error. Any chunking of the
+/// input decodes identically.
+///
+/// Deframing goes through [`SliceDeframer`], so length bounds, checksum widths
+/// and validators are exactly those of the `Read`-based deframers. Length
+/// headers are checked as soon as they arrive: an oversized declared length is
+/// rejected immediately, so a peer cannot make the decoder buffer toward a
+/// frame it would refuse anyway. Buffered bytes are therefore bounded by one
+/// header plus the configured maximum frame length (plus whatever a single
+/// `feed` call delivers).
+///
+/// ```rust
+/// use flatstream::{DefaultDeframer, DefaultFramer, FrameDecoder, Framer, Result};
+///
+/// # fn main() -> Result<()> {
+/// let mut wire = Vec::new();
+/// DefaultFramer.frame_and_write(&mut wire, b"hello")?;
+///
+/// let mut decoder = FrameDecoder::new(DefaultDeframer::new().with_max_frame_len(1 << 16));
+/// let (first, second) = wire.split_at(3);
+/// decoder.feed(first);
+/// assert!(decoder.decode()?.is_none()); // need more bytes
+/// decoder.feed(second);
+/// assert_eq!(decoder.decode()?.unwrap(), b"hello");
+/// decoder.finish()?; // nothing left over: a clean frame boundary
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A payload returned by `decode` borrows the decoder's buffer and is valid
+/// until the next call that takes `&mut self`. After an error the decoder does
+/// not advance; the built-in formats cannot resynchronize past a bad frame, so
+/// the connection should be dropped.
+pub struct FrameDecoder<D: SliceDeframer> {
+    deframer: D,
+    buffer: Vec<u8>,
+    // Start of the first unconsumed byte; the consumed prefix is compacted
+    // away lazily, on the next write into the buffer.
+    start: usize,
+}
+
+impl<D: SliceDeframer> FrameDecoder<D> {
+    /// Creates a decoder with an empty buffer.
+    pub fn new(deframer: D) -> Self {
+        Self::with_capacity(deframer, 0)
+    }
+
+    /// Creates a decoder whose buffer is pre-allocated to `capacity` bytes.
+    pub fn with_capacity(deframer: D, capacity: usize) -> Self {
+        Self {
+            deframer,
+            buffer: Vec::with_capacity(capacity),
+            start: 0,
+        }
+    }
+
+    /// Appends newly arrived bytes. Frames are not decoded until
+    /// [`decode`](Self::decode) is called.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.compact();
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Performs one `read` of up to 8 KiB from `source` directly into the
+    /// decoder's buffer, returning the byte count (`0` means the source
+    /// reached EOF).
+    ///
+    /// Intended for non-blocking sources: `WouldBlock` and every other I/O
+    /// error is returned untouched for the event loop to handle.
+    pub fn read_from<R: Read>(&mut self, source: &mut R) -> std::io::Result<usize> {
+        self.compact();
+        let filled = self.buffer.len();
+        self.buffer.resize(filled + READ_CHUNK, 0);
+        let result = source.read(&mut self.buffer[filled..]);
+        let n = *result.as_ref().unwrap_or(&0);
+        self.buffer.truncate(filled + n);
+        result
+    }
+
+    /// Decodes the next complete frame.
+    ///
+    /// Returns `Ok(Some(payload))` when a whole frame is buffered, `Ok(None)`
+    /// when more bytes are needed, and an error for a frame that fails its
+    /// length bound, checksum or validator.
+    #[inline]
+    pub fn decode(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.decode_range()?.map(|payload| &self.buffer[payload]))
+    }
+
+    /// Like [`decode`](Self::decode), but returns the payload's range within
+    /// the internal buffer so callers can keep decoding in a loop without
+    /// holding a borrow.
+    #[inline]
+    pub(crate) fn decode_range(&mut self) -> Result<Option<Range<usize>>> {
+        let base = self.start;
+        match self.deframer.deframe_slice(&self.buffer[base..])? {
+            Some(frame) => {
+                self.start += frame.len;
+                Ok(Some(base + frame.payload.start..base + frame.payload.end))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The payload bytes for a range returned by `decode_range`; valid until
+    /// the next write into the buffer.
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub(crate) fn payload(&self, range: Range<usize>) -> &[u8] {
+        &self.buffer[range]
+    }
+
+    /// Compacts the buffer and returns it for appending, with at least
+    /// `READ_CHUNK` bytes of spare capacity. Bytes pushed onto the returned
+    /// vector become input for `decode`.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn append_buffer(&mut self) -> &mut Vec<u8> {
+        self.compact();
+        self.buffer.reserve(READ_CHUNK);
+        &mut self.buffer
+    }
+
+    /// Declares the end of input. Returns `ErrorKind::UnexpectedEof` if a
+    /// partial frame is still buffered — the peer stopped mid-frame — and
+    /// `Ok(())` at a clean frame boundary.
+    ///
+    /// Call this once the source reports EOF and `decode` has returned
+    /// `Ok(None)`; complete frames still buffered are not an error here.
+    pub fn finish(&self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::unexpected_eof())
+        }
+    }
+
+    /// Number of buffered bytes not yet consumed by `decode`.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// Whether no unconsumed bytes are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.buffered_len() == 0
+    }
+
+    /// Returns the current capacity of the internal buffer.
+    pub fn buffer_capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Releases buffer capacity above `capacity` (never below what is still
+    /// buffered). Useful after a burst of large frames on a long-lived
+    /// connection.
+    pub fn shrink_to(&mut self, capacity: usize) {
+        self.compact();
+        self.buffer.shrink_to(capacity);
+    }
+
+    /// Returns a reference to the deframer strategy.
+    pub fn deframer(&self) -> &D {
+        &self.deframer
+    }
+
+    /// Drops the consumed prefix so unconsumed bytes start at index 0.
+    fn compact(&mut self) {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer, DeframerExt, Framer};
+    use crate::validation::SizeValidator;
+
+    #[cfg(feature = "crc16")]
+    use crate::{ChecksumDeframer, ChecksumFramer, Crc16};
+
+    fn wire<F: Framer>(framer: &F, payloads: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for p in payloads {
+            framer.frame_and_write(&mut out, p).unwrap();
+        }
+        out
+    }
+
+    /// Feeds `wire` in `chunk`-sized pieces, draining after each.
+    fn decode_chunked<D: SliceDeframer>(deframer: D, wire: &[u8], chunk: usize) -> Vec<Vec<u8>> {
+        let mut decoder = FrameDecoder::new(deframer);
+        let mut out = Vec::new();
+        for piece in wire.chunks(chunk) {
+            decoder.feed(piece);
+            while let Some(payload) = decoder.decode().unwrap() {
+                out.push(payload.to_vec());
+            }
+        }
+        decoder.finish().unwrap();
+        out
+    }
+
+    #[test]
+    fn any_chunking_decodes_identically() {
+        let payloads: [&[u8]; 4] = [b"a", b"", b"bb-bb", b"ccc-ccc-ccc"];
+        let bytes = wire(&DefaultFramer, &payloads);
+        for chunk in 1..=bytes.len() {
+            let got = decode_chunked(DefaultDeframer::new(), &bytes, chunk);
+            assert_eq!(got, payloads, "chunk size {chunk}");
+        }
+    }
+
+    #[cfg(feature = "crc16")]
+    #[test]
+    fn checksummed_frames_decode_byte_by_byte() {
+        let payloads: [&[u8]; 2] = [b"first", b"second"];
+        let bytes = wire(&ChecksumFramer::new(Crc16::new()), &payloads);
+        let got = decode_chunked(ChecksumDeframer::new(Crc16::new()), &bytes, 1);
+        assert_eq!(got, payloads);
+    }
+
+    #[test]
+    fn oversized_length_rejected_before_payload_arrives() {
+        let mut decoder = FrameDecoder::new(DefaultDeframer::new().with_max_frame_len(16));
+        decoder.feed(&1024u32.to_le_bytes());
+        let err = decoder.decode().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[test]
+    fn validator_failures_surface_from_decode() {
+        let bytes = wire(&DefaultFramer, &[b"too long for the validator"]);
+        let mut decoder =
+            FrameDecoder::new(DefaultDeframer::new().with_validator(SizeValidator::new(0, 4)));
+        decoder.feed(&bytes);
+        let err = decoder.decode().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ValidationFailed { .. }));
+    }
+
+    #[test]
+    fn finish_reports_a_partial_frame() {
+        let bytes = wire(&DefaultFramer, &[b"complete", b"partial"]);
+        let mut decoder = FrameDecoder::new(DefaultDeframer::new());
+        decoder.feed(&bytes[..bytes.len() - 2]);
+        assert_eq!(decoder.decode().unwrap().unwrap(), b"complete");
+        assert!(decoder.decode().unwrap().is_none());
+        let err = decoder.finish().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn read_from_fills_directly_and_reports_eof() {
+        let bytes = wire(&DefaultFramer, &[b"over the socket"]);
+        let mut source = &bytes[..];
+        let mut decoder = FrameDecoder::new(DefaultDeframer::new());
+        assert_eq!(decoder.read_from(&mut source).unwrap(), bytes.len());
+        assert_eq!(decoder.decode().unwrap().unwrap(), b"over the socket");
+        assert_eq!(decoder.read_from(&mut source).unwrap(), 0);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn read_from_reads_one_chunk_whatever_the_spare_capacity() {
+        let payload = vec![7u8; 3 * READ_CHUNK];
+        let bytes = wire(&DefaultFramer, &[&payload]);
+        let mut source = &bytes[..];
+        let mut decoder = FrameDecoder::with_capacity(DefaultDeframer::new(), 1 << 20);
+        let mut reads = 0;
+        let decoded = loop {
+            if let Some(frame) = decoder.decode().unwrap() {
+                break frame.len();
+            }
+            assert!(decoder.read_from(&mut source).unwrap() <= READ_CHUNK);
+            reads += 1;
+        };
+        assert_eq!(decoded, payload.len());
+        assert_eq!(reads, 4);
+    }
+}
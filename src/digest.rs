@@ -0,0 +1,217 @@
+//! Defines the `Digest` trait: integrity fields wider than the 8-byte `u64`
+//! that [`Checksum`] works in.
+//!
+//! [`ChecksumFramer`](crate::framing::ChecksumFramer) and
+//! [`ChecksumDeframer`](crate::framing::ChecksumDeframer) are generic over
+//! `Digest`, and every [`Checksum`] is a `Digest` through a blanket impl — so
+//! the existing algorithms keep their wire bytes and their
+//! `ChecksumMismatch` errors, while the 16- and 32-byte digests here report
+//! [`ErrorKind::DigestMismatch`](crate::error::ErrorKind::DigestMismatch)
+//! with the full digest bytes.
+//!
+//! Digest bytes travel in the algorithm's canonical output order (for
+//! SHA-256 and BLAKE3, the standard hex-printed order); XXH3-128's `u128` is
+//! written little-endian, like every other integer in the format.
+
+use crate::checksum::Checksum;
+use crate::error::{Error, Result};
+
+/// Widest digest any `Digest` may put on the wire; sizes the framers' stack
+/// header scratch.
+pub const MAX_DIGEST_LEN: usize = 32;
+
+/// An integrity field of up to [`MAX_DIGEST_LEN`] bytes per frame.
+///
+/// The width is the associated `LEN` constant, so — as with
+/// [`Checksum::SIZE`] — the framing layer's header length constant-folds.
+pub trait Digest {
+    /// Width in bytes of the digest field on the wire. Must be
+    /// ≤ [`MAX_DIGEST_LEN`] (enforced at compile time where framers are
+    /// constructed).
+    const LEN: usize;
+
+    /// Writes the digest of `payload` into `out`, which is exactly `LEN`
+    /// bytes long.
+    fn digest_into(&self, payload: &[u8], out: &mut [u8]);
+
+    /// Verifies the `LEN`-byte on-wire digest `expected` against `payload`.
+    fn verify_digest(&self, expected: &[u8], payload: &[u8]) -> Result<()> {
+        let mut calculated = [0u8; MAX_DIGEST_LEN];
+        let calculated = &mut calculated[..Self::LEN];
+        self.digest_into(payload, calculated);
+        if calculated == &expected[..Self::LEN] {
+            Ok(())
+        } else {
+            Err(Error::digest_mismatch(&expected[..Self::LEN], calculated))
+        }
+    }
+}
+
+/// Every checksum is a digest of its `SIZE` little-endian bytes; mismatches
+/// stay `ChecksumMismatch`, with the values as integers.
+impl<C: Checksum> Digest for C {
+    const LEN: usize = C::SIZE;
+
+    #[inline]
+    fn digest_into(&self, payload: &[u8], out: &mut [u8]) {
+        let mut field = [0u8; 8];
+        out.copy_from_slice(self.write_bytes(self.calculate(payload), &mut field));
+    }
+
+    #[inline]
+    fn verify_digest(&self, expected: &[u8], payload: &[u8]) -> Result<()> {
+        self.verify(self.read_bytes(expected), payload)
+    }
+}
+
+/// Provides the XXH3 128-bit hash: the fast non-cryptographic choice when
+/// 64 bits are too few (very long-lived archives, or streams large enough
+/// that accidental collisions matter). Shares the `xxhash` feature with
+/// [`XxHash64`](crate::checksum::XxHash64).
+#[cfg(feature = "xxhash")]
+#[derive(Default, Clone, Copy)]
+pub struct XxHash128;
+
+#[cfg(feature = "xxhash")]
+impl XxHash128 {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "xxhash")]
+impl Digest for XxHash128 {
+    const LEN: usize = 16;
+
+    #[inline]
+    fn digest_into(&self, payload: &[u8], out: &mut [u8]) {
+        out.copy_from_slice(&xxhash_rust::xxh3::xxh3_128(payload).to_le_bytes());
+    }
+}
+
+/// Provides SHA-256 (FIPS 180-4), 32 bytes per frame. Cryptographic
+/// collision resistance, but unkeyed: it detects corruption, not forgery —
+/// use `HmacFramer` (feature `hmac`) when an attacker can rewrite the stream.
+#[cfg(feature = "sha256")]
+#[derive(Default, Clone, Copy)]
+pub struct Sha256;
+
+#[cfg(feature = "sha256")]
+impl Sha256 {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "sha256")]
+impl Digest for Sha256 {
+    const LEN: usize = 32;
+
+    #[inline]
+    fn digest_into(&self, payload: &[u8], out: &mut [u8]) {
+        use sha2::Digest as _;
+        out.copy_from_slice(&sha2::Sha256::digest(payload));
+    }
+}
+
+/// Provides BLAKE3, 32 bytes per frame: cryptographic strength at several
+/// times SHA-256's throughput. Unkeyed, like [`Sha256`].
+#[cfg(feature = "blake3")]
+#[derive(Default, Clone, Copy)]
+pub struct Blake3;
+
+#[cfg(feature = "blake3")]
+impl Blake3 {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "blake3")]
+impl Digest for Blake3 {
+    const LEN: usize = 32;
+
+    #[inline]
+    fn digest_into(&self, payload: &[u8], out: &mut [u8]) {
+        out.copy_from_slice(blake3::hash(payload).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::NoChecksum;
+
+    fn digest<D: Digest>(alg: &D, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; D::LEN];
+        alg.digest_into(payload, &mut out);
+        out
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn known_answer_vectors() {
+        // Normative for WIRE_FORMAT_SPEC.md §5, like the checksum KATs.
+        #[cfg(feature = "xxhash")]
+        assert_eq!(
+            u128::from_le_bytes(digest(&XxHash128::new(), b"").try_into().unwrap()),
+            0x99aa_06d3_0147_98d8_6001_c324_468d_497f,
+            "XXH3-128"
+        );
+        #[cfg(feature = "sha256")]
+        assert_eq!(
+            hex(&digest(&Sha256::new(), b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            "SHA-256"
+        );
+        #[cfg(feature = "blake3")]
+        assert_eq!(
+            hex(&digest(&Blake3::new(), b"")),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            "BLAKE3"
+        );
+        assert!(hex(&digest(&NoChecksum::new(), b"abc")).is_empty());
+    }
+
+    #[test]
+    fn checksums_are_digests_of_their_wire_bytes() {
+        #[cfg(feature = "crc32")]
+        {
+            let crc = crate::checksum::Crc32::new();
+            assert_eq!(
+                digest(&crc, b"123456789"),
+                0xCBF4_3926u32.to_le_bytes().to_vec()
+            );
+            let err = crc.verify_digest(&[0; 4], b"123456789").unwrap_err();
+            assert!(matches!(
+                err.kind(),
+                crate::error::ErrorKind::ChecksumMismatch { .. }
+            ));
+        }
+        assert!(NoChecksum::new().verify_digest(&[], b"anything").is_ok());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn wide_mismatch_reports_both_digests() {
+        let good = digest(&Blake3::new(), b"payload");
+        assert!(Blake3.verify_digest(&good, b"payload").is_ok());
+        match Blake3
+            .verify_digest(&good, b"pay1oad")
+            .unwrap_err()
+            .into_kind()
+        {
+            crate::error::ErrorKind::DigestMismatch {
+                expected,
+                calculated,
+            } => {
+                assert_eq!(&*expected, &good[..]);
+                assert_eq!(&*calculated, &digest(&Blake3::new(), b"pay1oad")[..]);
+            }
+            other => panic!("expected DigestMismatch, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,654 @@
+//! Durable journal files: `fsync`/`fdatasync` policies and group commit.
+//!
+//! [`StreamWriter::flush`] hands bytes to the sink; for a `File` that means
+//! the operating system, not the disk. A crash after `flush` can still lose
+//! them. The writers here add the missing step — `File::sync_data`
+//! (`fdatasync`) or `File::sync_all` (`fsync`) — and track the **durable
+//! offset**: every byte before it has been synced and survives a crash,
+//! every byte after it may not.
+//!
+//! - [`DurableWriter`] is a single-owner writer that syncs according to a
+//!   [`SyncPolicy`]: after every frame, every N frames, every T of
+//!   [`Clock`] time, or only when asked.
+//! - [`GroupCommitWriter`] is a cloneable handle for concurrent producers.
+//!   Each [`commit`](GroupCommitWriter::commit) returns once its frame is
+//!   durable, and frames appended while a sync is in flight share the next
+//!   one, so N producers pay for far fewer than N syncs.
+//!
+//! A failed sync poisons the writer: the kernel may already have dropped the
+//! dirty pages it could not write, so retrying cannot prove anything. So
+//! does a write that failed in the file (an I/O error, or any error after
+//! bytes reached it), which may have left part of a frame behind: appending
+//! after it would turn a torn tail into corruption mid-file. Every later
+//! write or sync fails, and the durable offset stays where it was — reopen
+//! the file and run [`recover_file`](crate::recover_file) from there. A
+//! payload that the serializer, a bound or a validator rejects before any
+//! byte is written only fails its own write.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::framing::Framer;
+use crate::policy::{Clock, MonotonicClock};
+use crate::traits::StreamSerialize;
+use crate::writer::StreamWriter;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// When a [`DurableWriter`] syncs on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Sync after every frame: each write returns durable.
+    EveryFrame,
+    /// Sync after every `n`th frame.
+    EveryFrames(u64),
+    /// Sync at the first write at least this long after the previous sync.
+    Interval(Duration),
+    /// Never sync automatically; call [`DurableWriter::sync`].
+    Manual,
+}
+
+/// Which system call makes data durable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// `File::sync_data` (`fdatasync`): file contents and the metadata needed
+    /// to read them back, such as the length. Enough for an append-only
+    /// journal, and usually cheaper.
+    #[default]
+    Data,
+    /// `File::sync_all` (`fsync`): contents and all metadata.
+    All,
+}
+
+impl SyncMode {
+    fn sync(self, file: &File) -> std::io::Result<()> {
+        match self {
+            SyncMode::Data => file.sync_data(),
+            SyncMode::All => file.sync_all(),
+        }
+    }
+}
+
+/// A buffered file sink that knows its end offset.
+struct FileSink {
+    inner: BufWriter<File>,
+    offset: u64,
+}
+
+impl FileSink {
+    fn new(mut file: File) -> Result<Self> {
+        let offset = file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            inner: BufWriter::new(file),
+            offset,
+        })
+    }
+}
+
+impl Write for FileSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Whether a failed write may have left part of a frame in the file: the
+/// sink failed, or bytes reached it before the error.
+fn may_have_torn(err: &Error, before: u64, after: u64) -> bool {
+    after != before || matches!(err.kind(), ErrorKind::Io(_))
+}
+
+#[cold]
+fn poisoned() -> Error {
+    std::io::Error::other("an earlier write or sync failed; the end of the file is unknown").into()
+}
+
+/// A journal file writer that syncs according to a [`SyncPolicy`] and
+/// reports how much of the file is durable.
+///
+/// Frames are appended at the end of the file, whatever its current length,
+/// so reopening an existing journal continues it.
+///
+/// ```rust
+/// use flatstream::{DefaultFramer, DurableWriter, Result, SyncPolicy};
+/// use std::fs::OpenOptions;
+///
+/// # fn main() -> Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("journal.bin");
+/// let file = OpenOptions::new().create(true).append(true).open(&path)?;
+/// let mut writer = DurableWriter::new(file, DefaultFramer, SyncPolicy::EveryFrames(64))?;
+/// writer.write(&"order accepted")?;
+/// let durable = writer.sync()?; // everything written so far survives a crash
+/// assert_eq!(durable, writer.written_offset());
+/// # Ok(())
+/// # }
+/// ```
+pub struct DurableWriter<'a, F: Framer, C: Clock = MonotonicClock> {
+    writer: StreamWriter<'a, FileSink, F>,
+    policy: SyncPolicy,
+    mode: SyncMode,
+    clock: C,
+    durable_offset: u64,
+    frames_since_sync: u64,
+    last_sync: Duration,
+    poisoned: bool,
+}
+
+impl<'a, F: Framer> DurableWriter<'a, F> {
+    /// Wraps `file`, appending after its current end. The bytes already in
+    /// the file count as durable.
+    pub fn new(file: File, framer: F, policy: SyncPolicy) -> Result<Self> {
+        let sink = FileSink::new(file)?;
+        let clock = MonotonicClock::new();
+        Ok(Self {
+            durable_offset: sink.offset,
+            writer: StreamWriter::new(sink, framer),
+            policy,
+            mode: SyncMode::default(),
+            last_sync: clock.now(),
+            clock,
+            frames_since_sync: 0,
+            poisoned: false,
+        })
+    }
+}
+
+impl<'a, F: Framer, C: Clock> DurableWriter<'a, F, C> {
+    /// Replaces the clock that [`SyncPolicy::Interval`] is measured on.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> DurableWriter<'a, F, C2> {
+        DurableWriter {
+            writer: self.writer,
+            policy: self.policy,
+            mode: self.mode,
+            last_sync: clock.now(),
+            clock,
+            durable_offset: self.durable_offset,
+            frames_since_sync: self.frames_since_sync,
+            poisoned: self.poisoned,
+        }
+    }
+
+    /// Selects `sync_data` (default) or `sync_all`.
+    pub fn with_sync_mode(mut self, mode: SyncMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Serializes and writes one item, then syncs if the policy says so.
+    pub fn write<T: StreamSerialize>(&mut self, item: &T) -> Result<()> {
+        self.check()?;
+        let before = self.written_offset();
+        let result = self.writer.write(item);
+        self.poison_on_err(before, result)?;
+        self.wrote()
+    }
+
+    /// Writes a finished builder's payload, then syncs if the policy says so.
+    pub fn write_finished<A: flatbuffers::Allocator>(
+        &mut self,
+        builder: &mut flatbuffers::FlatBufferBuilder<A>,
+    ) -> Result<()> {
+        self.check()?;
+        let before = self.written_offset();
+        let result = self.writer.write_finished(builder);
+        self.poison_on_err(before, result)?;
+        self.wrote()
+    }
+
+    /// Writes already-serialized payload bytes, then syncs if the policy
+    /// says so.
+    pub fn write_payload(&mut self, payload: &[u8]) -> Result<()> {
+        self.check()?;
+        let before = self.written_offset();
+        let result = self.writer.write_payload(payload);
+        self.poison_on_err(before, result)?;
+        self.wrote()
+    }
+
+    /// Flushes buffered frames and syncs the file. Returns the new durable
+    /// offset, which equals [`written_offset`](Self::written_offset).
+    pub fn sync(&mut self) -> Result<u64> {
+        self.check()?;
+        let before = self.written_offset();
+        let result = self.writer.flush();
+        self.poison_on_err(before, result)?;
+        let target = self.written_offset();
+        if let Err(e) = self.mode.sync(self.writer.get_ref().inner.get_ref()) {
+            self.poisoned = true;
+            return Err(e.into());
+        }
+        self.durable_offset = target;
+        self.frames_since_sync = 0;
+        self.last_sync = self.clock.now();
+        Ok(target)
+    }
+
+    /// Syncs if an [`Interval`](SyncPolicy::Interval) deadline has passed,
+    /// for callers that drive the writer from a timer between writes.
+    pub fn sync_if_due(&mut self) -> Result<bool> {
+        if self.durable_offset < self.written_offset() && self.is_due() {
+            self.sync()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// End offset of everything written, durable or not.
+    pub fn written_offset(&self) -> u64 {
+        self.writer.get_ref().offset
+    }
+
+    /// End offset of the durable prefix of the file. Never moves backwards.
+    pub fn durable_offset(&self) -> u64 {
+        self.durable_offset
+    }
+
+    /// Syncs and returns the file.
+    pub fn finish(mut self) -> Result<File> {
+        self.sync()?;
+        self.writer
+            .into_inner()
+            .inner
+            .into_inner()
+            .map_err(|e| e.into_error().into())
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.poisoned {
+            return Err(poisoned());
+        }
+        Ok(())
+    }
+
+    fn poison_on_err<T>(&mut self, before: u64, result: Result<T>) -> Result<T> {
+        if let Err(e) = &result {
+            self.poisoned |= may_have_torn(e, before, self.written_offset());
+        }
+        result
+    }
+
+    fn is_due(&self) -> bool {
+        match self.policy {
+            SyncPolicy::EveryFrame => true,
+            SyncPolicy::EveryFrames(n) => self.frames_since_sync >= n,
+            SyncPolicy::Interval(interval) => {
+                self.clock.now().saturating_sub(self.last_sync) >= interval
+            }
+            SyncPolicy::Manual => false,
+        }
+    }
+
+    fn wrote(&mut self) -> Result<()> {
+        self.frames_since_sync += 1;
+        if self.is_due() {
+            self.sync()?;
+        }
+        Ok(())
+    }
+}
+
+struct CommitState<F: Framer> {
+    writer: StreamWriter<'static, FileSink, F>,
+    durable_offset: u64,
+    syncing: bool,
+    poisoned: bool,
+    syncs: u64,
+}
+
+struct CommitShared<F: Framer> {
+    state: Mutex<CommitState<F>>,
+    synced: Condvar,
+    // A second handle to the file, synced without holding the lock so that
+    // producers can keep appending while a sync is in flight.
+    file: File,
+    mode: SyncMode,
+}
+
+fn poison_writer<F: Framer>(
+    poison: PoisonError<MutexGuard<'_, CommitState<F>>>,
+) -> MutexGuard<'_, CommitState<F>> {
+    let mut state = poison.into_inner();
+    state.poisoned = true;
+    state
+}
+
+/// A journal writer shared by concurrent producers, with group commit.
+///
+/// [`commit`](Self::commit) appends one frame and returns once it is
+/// durable. The first producer to find no sync in flight becomes the leader:
+/// it flushes, releases the lock and syncs. Producers arriving meanwhile
+/// append their frames and wait; the next leader's single sync covers all of
+/// them. Frames land in the file in the order their `commit` calls took the
+/// lock.
+///
+/// ```rust
+/// use flatstream::{DefaultFramer, GroupCommitWriter, Result};
+/// use std::fs::File;
+///
+/// # fn main() -> Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// let journal = GroupCommitWriter::new(File::create(dir.path().join("j.bin"))?, DefaultFramer)?;
+/// let producers: Vec<_> = (0..4)
+///     .map(|i| {
+///         let journal = journal.clone();
+///         std::thread::spawn(move || journal.commit(format!("event {i}").as_bytes()))
+///     })
+///     .collect();
+/// for producer in producers {
+///     let end = producer.join().unwrap()?;
+///     assert!(end <= journal.durable_offset());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct GroupCommitWriter<F: Framer> {
+    shared: Arc<CommitShared<F>>,
+}
+
+impl<F: Framer> Clone for GroupCommitWriter<F> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<F: Framer> GroupCommitWriter<F> {
+    /// Wraps `file`, appending after its current end, syncing with
+    /// `sync_data`.
+    pub fn new(file: File, framer: F) -> Result<Self> {
+        Self::with_sync_mode(file, framer, SyncMode::default())
+    }
+
+    /// Like [`new`](Self::new), with an explicit [`SyncMode`].
+    pub fn with_sync_mode(file: File, framer: F, mode: SyncMode) -> Result<Self> {
+        let sync_handle = file.try_clone()?;
+        let sink = FileSink::new(file)?;
+        let state = CommitState {
+            durable_offset: sink.offset,
+            writer: StreamWriter::new(sink, framer),
+            syncing: false,
+            poisoned: false,
+            syncs: 0,
+        };
+        Ok(Self {
+            shared: Arc::new(CommitShared {
+                state: Mutex::new(state),
+                synced: Condvar::new(),
+                file: sync_handle,
+                mode,
+            }),
+        })
+    }
+
+    /// Appends one frame and waits until it is durable. Returns the frame's
+    /// end offset.
+    pub fn commit(&self, payload: &[u8]) -> Result<u64> {
+        let mut state = self.lock();
+        if state.poisoned {
+            return Err(poisoned());
+        }
+        let before = state.writer.get_ref().offset;
+        if let Err(e) = state.writer.write_payload(payload) {
+            state.poisoned = may_have_torn(&e, before, state.writer.get_ref().offset);
+            return Err(e);
+        }
+        let end = state.writer.get_ref().offset;
+        self.wait_durable(state, end)
+    }
+
+    /// Like [`commit`](Self::commit), for a finished builder.
+    pub fn commit_finished<A: flatbuffers::Allocator>(
+        &self,
+        builder: &mut flatbuffers::FlatBufferBuilder<A>,
+    ) -> Result<u64> {
+        self.commit(builder.finished_data())
+    }
+
+    /// Makes everything appended so far durable. Returns the durable offset.
+    pub fn sync(&self) -> Result<u64> {
+        let state = self.lock();
+        let end = state.writer.get_ref().offset;
+        self.wait_durable(state, end)
+    }
+
+    /// End offset of the durable prefix of the file.
+    pub fn durable_offset(&self) -> u64 {
+        self.lock().durable_offset
+    }
+
+    /// End offset of everything appended, durable or not.
+    pub fn written_offset(&self) -> u64 {
+        self.lock().writer.get_ref().offset
+    }
+
+    /// Number of syncs performed so far.
+    pub fn sync_count(&self) -> u64 {
+        self.lock().syncs
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CommitState<F>> {
+        // A producer that panicked mid-append may have left a partial frame
+        // in the buffer, like a failed write.
+        self.shared.state.lock().unwrap_or_else(poison_writer)
+    }
+
+    fn wait_durable<'s>(
+        &'s self,
+        mut state: MutexGuard<'s, CommitState<F>>,
+        end: u64,
+    ) -> Result<u64> {
+        loop {
+            if state.poisoned {
+                return Err(poisoned());
+            }
+            if state.durable_offset >= end {
+                return Ok(state.durable_offset);
+            }
+            if state.syncing {
+                state = self.shared.synced.wait(state).unwrap_or_else(poison_writer);
+                continue;
+            }
+
+            // Lead the next sync, covering everything appended so far.
+            let flushed = state.writer.flush();
+            let target = state.writer.get_ref().offset;
+            state.syncing = true;
+            drop(state);
+            let result = flushed.and_then(|()| {
+                self.shared
+                    .mode
+                    .sync(&self.shared.file)
+                    .map_err(Error::from)
+            });
+            state = self.lock();
+            state.syncing = false;
+            state.syncs += 1;
+            match &result {
+                Ok(()) => state.durable_offset = state.durable_offset.max(target),
+                Err(_) => state.poisoned = true,
+            }
+            self.shared.synced.notify_all();
+            result?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[derive(Clone, Default)]
+    struct TestClock(Arc<AtomicU64>); // elapsed milliseconds
+
+    impl TestClock {
+        fn advance_ms(&self, ms: u64) {
+            self.0.fetch_add(ms, Ordering::Relaxed);
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    fn frame_count(path: &std::path::Path) -> usize {
+        let mut reader = StreamReader::new(File::open(path).unwrap(), DefaultDeframer::new());
+        let mut n = 0;
+        reader
+            .process_all(|_| {
+                n += 1;
+                Ok(())
+            })
+            .unwrap();
+        n
+    }
+
+    #[test]
+    fn every_frames_policy_advances_durable_offset_in_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = File::create(dir.path().join("j.bin")).unwrap();
+        let mut writer =
+            DurableWriter::new(file, DefaultFramer, SyncPolicy::EveryFrames(3)).unwrap();
+        for i in 1..=7u64 {
+            writer.write_payload(b"1234").unwrap();
+            assert_eq!(writer.written_offset(), 8 * i);
+            assert_eq!(writer.durable_offset(), 8 * (i / 3 * 3));
+        }
+        let file = writer.finish().unwrap();
+        assert_eq!(file.metadata().unwrap().len(), 56);
+    }
+
+    #[test]
+    fn every_frame_and_manual_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        let mut writer = DurableWriter::new(
+            File::create(&path).unwrap(),
+            DefaultFramer,
+            SyncPolicy::EveryFrame,
+        )
+        .unwrap()
+        .with_sync_mode(SyncMode::All);
+        writer.write_payload(b"ab").unwrap();
+        assert_eq!(writer.durable_offset(), 6);
+        drop(writer);
+
+        // Reopening continues after the existing bytes, which count as durable.
+        let file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        let mut writer = DurableWriter::new(file, DefaultFramer, SyncPolicy::Manual).unwrap();
+        assert_eq!(writer.durable_offset(), 6);
+        writer.write_payload(b"cd").unwrap();
+        assert_eq!(writer.durable_offset(), 6);
+        assert_eq!(writer.sync().unwrap(), 12);
+        assert_eq!(frame_count(&path), 2);
+    }
+
+    #[test]
+    fn interval_policy_uses_the_injected_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        let clock = TestClock::default();
+        let mut writer = DurableWriter::new(
+            File::create(dir.path().join("j.bin")).unwrap(),
+            DefaultFramer,
+            SyncPolicy::Interval(Duration::from_millis(10)),
+        )
+        .unwrap()
+        .with_clock(clock.clone());
+        writer.write_payload(b"a").unwrap();
+        assert_eq!(writer.durable_offset(), 0);
+        clock.advance_ms(10);
+        assert!(writer.sync_if_due().unwrap());
+        assert_eq!(writer.durable_offset(), 5);
+        assert!(!writer.sync_if_due().unwrap());
+        writer.write_payload(b"b").unwrap();
+        clock.advance_ms(10);
+        writer.write_payload(b"c").unwrap();
+        assert_eq!(writer.durable_offset(), 15);
+    }
+
+    #[test]
+    fn group_commit_makes_every_committed_frame_durable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        let journal = GroupCommitWriter::new(File::create(&path).unwrap(), DefaultFramer).unwrap();
+        let producers: Vec<_> = (0..8)
+            .map(|_| {
+                let journal = journal.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        let end = journal.commit(b"payload").unwrap();
+                        assert!(journal.durable_offset() >= end);
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(journal.durable_offset(), 200 * 11);
+        assert_eq!(journal.written_offset(), journal.durable_offset());
+        assert!((1..=200).contains(&journal.sync_count()));
+        assert_eq!(frame_count(&path), 200);
+        assert_eq!(journal.sync().unwrap(), 200 * 11);
+    }
+
+    #[test]
+    fn rejected_payload_leaves_both_writers_usable() {
+        use crate::framing::FramerExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        let file = File::create(&path).unwrap();
+        let framer = DefaultFramer.bounded(16);
+        let mut writer = DurableWriter::new(file, framer, SyncPolicy::EveryFrame).unwrap();
+        assert!(writer.write_payload(&[0; 64]).is_err());
+        writer.write_payload(b"fits").unwrap();
+        assert_eq!(writer.durable_offset(), 8);
+        drop(writer);
+
+        let file = File::options().append(true).open(&path).unwrap();
+        let journal = GroupCommitWriter::new(file, DefaultFramer.bounded(16)).unwrap();
+        assert!(journal.commit(&[0; 64]).is_err());
+        assert_eq!(journal.commit(b"fits").unwrap(), 16);
+        assert_eq!(frame_count(&path), 2);
+    }
+
+    #[test]
+    fn failed_write_poisons_both_writers() {
+        // A read-only handle fails every write that reaches it; a frame
+        // larger than the buffer goes straight through.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        std::fs::write(&path, b"").unwrap();
+        let large = vec![0u8; 64 * 1024];
+        let read_only = || File::open(&path).unwrap();
+
+        let mut writer =
+            DurableWriter::new(read_only(), DefaultFramer, SyncPolicy::Manual).unwrap();
+        assert!(writer.write_payload(&large).is_err());
+        let err = writer.write_payload(b"small").unwrap_err();
+        assert!(err.to_string().contains("earlier write"), "{err}");
+        assert!(writer.sync().is_err());
+        assert_eq!(writer.durable_offset(), 0);
+
+        let journal = GroupCommitWriter::new(read_only(), DefaultFramer).unwrap();
+        assert!(journal.commit(&large).is_err());
+        let err = journal.commit(b"small").unwrap_err();
+        assert!(err.to_string().contains("earlier write"), "{err}");
+        assert_eq!(journal.durable_offset(), 0);
+    }
+}
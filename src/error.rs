@@ -22,6 +22,14 @@ pub enum ErrorKind {
     #[error("Checksum mismatch: expected {expected}, got {calculated}")]
     ChecksumMismatch { expected: u64, calculated: u64 },
 
+    /// Mismatch of a digest wider than 8 bytes (see [`crate::digest`]), with
+    /// the on-wire and calculated digest bytes.
+    #[error("Digest mismatch: expected {}, got {}", Hex(expected), Hex(calculated))]
+    DigestMismatch {
+        expected: Box<[u8]>,
+        calculated: Box<[u8]>,
+    },
+
     /// Invalid frame error for malformed frames (e.g., oversized length, policy limits).
     ///
     /// Optional context fields help diagnose issues quickly; they are rendered
@@ -62,6 +70,60 @@ pub enum ErrorKind {
     /// Unexpected end of file while reading stream data.
     #[error("Unexpected end of file while reading stream")]
     UnexpectedEof,
+
+    /// A keyed frame whose tag does not verify: tampered with, or written
+    /// under a different key (see the `auth` module).
+    #[error("Authentication failed: frame tag does not verify")]
+    AuthenticationFailed,
+
+    /// A keyed frame protected by a key id the reader holds no key for.
+    #[error("Unknown key id {key_id}: no key registered for this frame")]
+    UnknownKeyId { key_id: u32 },
+
+    /// An authentic frame that does not continue the stream being read: it
+    /// belongs to another stream, is replayed or reordered, or follows
+    /// missing frames. Both positions are `(stream id, sequence number)`.
+    #[error("Sequence violation: {}", SequenceContext(found, expected))]
+    SequenceViolation {
+        /// Position the frame carries.
+        found: (u64, u64),
+        /// Position the reader expected next.
+        expected: (u64, u64),
+    },
+}
+
+/// Renders a `SequenceViolation` as the kind of break it is.
+struct SequenceContext<'a>(&'a (u64, u64), &'a (u64, u64));
+
+impl fmt::Display for SequenceContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (&(stream, sequence), &(expected_stream, expected)) = (self.0, self.1);
+        if stream != expected_stream {
+            write!(
+                f,
+                "frame belongs to stream {stream:#018x}, not {expected_stream:#018x}"
+            )
+        } else if sequence < expected {
+            write!(
+                f,
+                "replayed or reordered frame: sequence {sequence} where {expected} was expected"
+            )
+        } else {
+            write!(
+                f,
+                "frames missing: sequence {sequence} where {expected} was expected"
+            )
+        }
+    }
+}
+
+/// Renders digest bytes as lowercase hex.
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
 }
 
 /// Renders `InvalidFrame`'s optional context as ` (declared_len=…, …)` — on
@@ -149,6 +211,17 @@ impl Error {
         .into()
     }
 
+    /// Create a new `DigestMismatch` error from the on-wire and calculated
+    /// digest bytes.
+    #[cold]
+    pub fn digest_mismatch(expected: &[u8], calculated: &[u8]) -> Self {
+        ErrorKind::DigestMismatch {
+            expected: expected.into(),
+            calculated: calculated.into(),
+        }
+        .into()
+    }
+
     /// Create a new `ValidationFailed` error for the named validator.
     ///
     /// The reason is `Cow`: static reasons stay borrowed and copy-free,
@@ -170,6 +243,25 @@ impl Error {
     pub fn unexpected_eof() -> Self {
         ErrorKind::UnexpectedEof.into()
     }
+
+    /// Create a new `AuthenticationFailed` error.
+    #[cold]
+    pub fn authentication_failed() -> Self {
+        ErrorKind::AuthenticationFailed.into()
+    }
+
+    /// Create a new `UnknownKeyId` error for the frame's key id.
+    #[cold]
+    pub fn unknown_key_id(key_id: u32) -> Self {
+        ErrorKind::UnknownKeyId { key_id }.into()
+    }
+
+    /// Create a new `SequenceViolation` error from the frame's and the
+    /// expected `(stream id, sequence number)`.
+    #[cold]
+    pub fn sequence_violation(found: (u64, u64), expected: (u64, u64)) -> Self {
+        ErrorKind::SequenceViolation { found, expected }.into()
+    }
 }
 
 impl From<ErrorKind> for Error {
@@ -229,6 +321,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn digest_mismatch_renders_hex() {
+        let err = Error::digest_mismatch(&[0xab, 0x01], &[0x00, 0xff]);
+        assert_eq!(err.to_string(), "Digest mismatch: expected ab01, got 00ff");
+    }
+
+    #[test]
+    fn sequence_violation_renders_the_kind_of_break() {
+        let render = |found, expected| Error::sequence_violation(found, expected).to_string();
+        assert_eq!(
+            render((1, 0), (2, 0)),
+            "Sequence violation: frame belongs to stream 0x0000000000000001, not 0x0000000000000002"
+        );
+        assert_eq!(
+            render((1, 3), (1, 5)),
+            "Sequence violation: replayed or reordered frame: sequence 3 where 5 was expected"
+        );
+        assert_eq!(
+            render((1, 7), (1, 5)),
+            "Sequence violation: frames missing: sequence 7 where 5 was expected"
+        );
+    }
+
     #[test]
     fn source_chain_preserved() {
         // `Error` is transparent over `ErrorKind`, whose #[from] fields are
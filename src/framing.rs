@@ -1,9 +1,11 @@
 //! Defines the framing and deframing strategies for the byte stream.
 
 use crate::checksum::Checksum;
+use crate::digest::{Digest, MAX_DIGEST_LEN};
 use crate::error::{Error, Result};
 use crate::validation::Validator;
 use std::io::{Read, Write};
+use std::ops::Range;
 
 /// Default maximum accepted payload length for the core deframers: the
 /// FlatBuffers maximum buffer size (2 GiB), so every valid FlatBuffer reads
@@ -40,6 +42,24 @@ pub const MAX_WIRE_FRAME_LEN: usize = u32::MAX as usize;
 /// Implementations are small strategy objects composed into `StreamWriter`.
 pub trait Framer {
     fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()>;
+
+    /// Writes out any frames the framer is holding back.
+    ///
+    /// Framers that write every frame immediately (all built-in ones except
+    /// the block framer) have nothing to do here. Writers call this before
+    /// flushing, syncing or closing their underlying writer, and adapters
+    /// forward it to the framer they wrap.
+    fn flush_pending<W: Write>(&self, _writer: &mut W) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the framer may hold payloads back for
+    /// [`flush_pending`](Self::flush_pending) rather than writing each one as
+    /// its own frame. A [`FrameIndex`](crate::FrameIndex) cannot be recorded
+    /// through such a framer. Adapters forward it like `flush_pending`.
+    fn holds_back_payloads(&self) -> bool {
+        false
+    }
 }
 
 /// The default framing strategy: `[4-byte length | payload]`
@@ -66,28 +86,24 @@ impl Framer for DefaultFramer {
 }
 
 /// A framing strategy that includes a checksum:
-/// `[4-byte length | C::SIZE-byte checksum | payload]` — the checksum field's
-/// width is the algorithm's associated `SIZE` (8 for XXH3-64, 4 for CRC-32,
-/// 2 for CRC-16), not a fixed 8 bytes.
+/// `[4-byte length | C::LEN-byte checksum | payload]` — the checksum field's
+/// width is the algorithm's associated [`Digest::LEN`] (8 for XXH3-64, 4 for
+/// CRC-32, 2 for CRC-16, up to 32 for the wide digests in
+/// [`crate::digest`]), not a fixed 8 bytes.
 ///
 /// When to use: Integrity validation at read-time and/or independent message corruption detection.
-pub struct ChecksumFramer<C: Checksum> {
+pub struct ChecksumFramer<C: Digest> {
     checksum_alg: C,
 }
 
-impl<C: Checksum> ChecksumFramer<C> {
+impl<C: Digest> ChecksumFramer<C> {
     pub fn new(checksum_alg: C) -> Self {
-        const {
-            assert!(
-                C::SIZE <= 8,
-                "checksum wider than the u64 the trait works in"
-            )
-        };
+        const { assert!(C::LEN <= MAX_DIGEST_LEN, "digest wider than MAX_DIGEST_LEN") };
         Self { checksum_alg }
     }
 }
 
-impl<C: Checksum> Framer for ChecksumFramer<C> {
+impl<C: Digest> Framer for ChecksumFramer<C> {
     fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
         // Enforce 32-bit length header contract to avoid truncation on cast
         if payload.len() > u32::MAX as usize {
@@ -99,13 +115,12 @@ impl<C: Checksum> Framer for ChecksumFramer<C> {
             ));
         }
         let payload_len = payload.len() as u32;
-        let checksum = self.checksum_alg.calculate(payload);
 
         // Assemble the full header ([4-byte length | checksum bytes]) in a
-        // 12-byte stack scratch and issue a single write_all — halves the call
+        // stack scratch and issue a single write_all — halves the call
         // count on this path versus writing length and checksum separately.
         // The bytes on the wire are identical (wire-format corpus tests).
-        // `C::SIZE` is an associated const, so the header length and the
+        // `C::LEN` is an associated const, so the header length and the
         // serialization width constant-fold by construction.
         //
         // On "copying" here: only header *metadata* is materialized — integers
@@ -113,14 +128,14 @@ impl<C: Checksum> Framer for ChecksumFramer<C> {
         // `to_le_bytes()` produced the same stack bytes before its own
         // write_all. At these fixed sizes `copy_from_slice` compiles to plain
         // register stores (no memcpy call). Payload bytes are never copied;
-        // the zero-copy guarantee concerns the payload, not the 4–12 header
+        // the zero-copy guarantee concerns the payload, not the 4–36 header
         // bytes.
-        let mut header = [0u8; 12];
+        let mut header = [0u8; 4 + MAX_DIGEST_LEN];
         header[..4].copy_from_slice(&payload_len.to_le_bytes());
-        let checksum_field: &mut [u8; 8] = (&mut header[4..12]).try_into().unwrap();
-        self.checksum_alg.write_bytes(checksum, checksum_field);
+        self.checksum_alg
+            .digest_into(payload, &mut header[4..4 + C::LEN]);
 
-        writer.write_all(&header[..4 + C::SIZE])?;
+        writer.write_all(&header[..4 + C::LEN])?;
         writer.write_all(payload)?;
         Ok(())
     }
@@ -142,7 +157,7 @@ impl<C: Checksum> Framer for ChecksumFramer<C> {
 /// `read` call costs a real memcpy per frame — measured at +100% on the
 /// tight read loops.
 #[inline(always)]
-fn read_header<R: Read>(reader: &mut R, header: &mut [u8]) -> Result<Option<()>> {
+pub(crate) fn read_header<R: Read>(reader: &mut R, header: &mut [u8]) -> Result<Option<()>> {
     match reader.read_exact(&mut header[..1]) {
         Ok(()) => {}
         Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
@@ -160,7 +175,7 @@ fn read_header<R: Read>(reader: &mut R, header: &mut [u8]) -> Result<Option<()>>
 /// Rejects a declared payload length that exceeds the configured bound —
 /// before any allocation is sized from it.
 #[inline(always)]
-fn check_frame_len(payload_len: usize, max: usize) -> Result<()> {
+pub(crate) fn check_frame_len(payload_len: usize, max: usize) -> Result<()> {
     if payload_len > max {
         return Err(Error::invalid_frame_with(
             "frame length exceeds configured limit",
@@ -176,7 +191,11 @@ fn check_frame_len(payload_len: usize, max: usize) -> Result<()> {
 /// zero-initializing) it only when the high-water mark rises. A partial
 /// payload is `ErrorKind::UnexpectedEof`; other I/O errors propagate intact.
 #[inline(always)]
-fn read_payload<R: Read>(reader: &mut R, buffer: &mut Vec<u8>, payload_len: usize) -> Result<()> {
+pub(crate) fn read_payload<R: Read>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    payload_len: usize,
+) -> Result<()> {
     if payload_len > buffer.len() {
         buffer.resize(payload_len, 0);
     }
@@ -288,19 +307,14 @@ impl Deframer for DefaultDeframer {
 /// maximum ([`DEFAULT_MAX_FRAME_LEN`], 2 GiB) by default, tightened for
 /// untrusted input with [`with_max_frame_len`](Self::with_max_frame_len).
 #[derive(Clone, Copy)]
-pub struct ChecksumDeframer<C: Checksum> {
+pub struct ChecksumDeframer<C: Digest> {
     checksum_alg: C,
     max_frame_len: usize,
 }
 
-impl<C: Checksum> ChecksumDeframer<C> {
+impl<C: Digest> ChecksumDeframer<C> {
     pub fn new(checksum_alg: C) -> Self {
-        const {
-            assert!(
-                C::SIZE <= 8,
-                "checksum wider than the u64 the trait works in"
-            )
-        };
+        const { assert!(C::LEN <= MAX_DIGEST_LEN, "digest wider than MAX_DIGEST_LEN") };
         Self {
             checksum_alg,
             max_frame_len: DEFAULT_MAX_FRAME_LEN,
@@ -314,7 +328,7 @@ impl<C: Checksum> ChecksumDeframer<C> {
     }
 }
 
-impl<C: Checksum> Deframer for ChecksumDeframer<C> {
+impl<C: Digest> Deframer for ChecksumDeframer<C> {
     fn read_and_deframe<R: Read>(
         &self,
         reader: &mut R,
@@ -324,19 +338,19 @@ impl<C: Checksum> Deframer for ChecksumDeframer<C> {
         // ChecksumFramer's single-write_all assembly). Safe only because
         // `read_header` distinguishes a clean frame boundary (zero bytes)
         // from a torn header — a plain `read_exact` over the merged header
-        // could not tell those apart (spec §6). `C::SIZE` keeps the header
+        // could not tell those apart (spec §6). `C::LEN` keeps the header
         // length statically known, so the reads compile to plain loads.
-        let mut header = [0u8; 12];
-        match read_header(reader, &mut header[..4 + C::SIZE])? {
+        let mut header = [0u8; 4 + MAX_DIGEST_LEN];
+        match read_header(reader, &mut header[..4 + C::LEN])? {
             Some(()) => {}
             None => return Ok(None),
         }
         let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
         check_frame_len(payload_len, self.max_frame_len)?;
-        let expected = self.checksum_alg.read_bytes(&header[4..4 + C::SIZE]);
 
         read_payload(reader, buffer, payload_len)?;
-        self.checksum_alg.verify(expected, &buffer[..payload_len])?;
+        self.checksum_alg
+            .verify_digest(&header[4..4 + C::LEN], &buffer[..payload_len])?;
         Ok(Some(payload_len))
     }
 
@@ -351,14 +365,169 @@ impl<C: Checksum> Deframer for ChecksumDeframer<C> {
         // The length header is already consumed, so EOF here is inside the
         // frame: a torn checksum field maps to UnexpectedEof, every other
         // I/O error propagates intact (recovery logic needs the kind).
-        let mut checksum_bytes = [0u8; 8];
+        let mut checksum_bytes = [0u8; MAX_DIGEST_LEN];
         reader
-            .read_exact(&mut checksum_bytes[..C::SIZE])
+            .read_exact(&mut checksum_bytes[..C::LEN])
             .map_err(|e| match e.kind() {
                 std::io::ErrorKind::UnexpectedEof => Error::unexpected_eof(),
                 _ => e.into(),
             })?;
-        let expected = self.checksum_alg.read_bytes(&checksum_bytes);
+
+        read_payload(reader, buffer, payload_len)?;
+        self.checksum_alg
+            .verify_digest(&checksum_bytes, &buffer[..payload_len])?;
+        Ok(Some(payload_len))
+    }
+}
+
+/// A checksummed framing strategy whose integrity check also covers the
+/// length header:
+/// `[4-byte length | C::SIZE-byte payload checksum | C::SIZE-byte header checksum | payload]`.
+///
+/// The header checksum is `C` computed over the `4 + C::SIZE` header bytes
+/// before it (the length and the payload checksum). [`ChecksumFramer`]'s
+/// checksum covers the payload only, so a bit flip in its length prefix
+/// surfaces as a confusing `InvalidFrame` or a wasted allocation; here it is
+/// a `ChecksumMismatch` raised before the payload buffer is sized.
+///
+/// When to use: streams where corruption of the header is as likely as
+/// corruption of the payload (long-lived archives, lossy media). Costs one
+/// extra `C::SIZE` field per frame. The choice is per stream: read it with a
+/// matching [`HeaderChecksumDeframer<C>`].
+pub struct HeaderChecksumFramer<C: Checksum> {
+    checksum_alg: C,
+}
+
+impl<C: Checksum> HeaderChecksumFramer<C> {
+    pub fn new(checksum_alg: C) -> Self {
+        const {
+            assert!(
+                C::SIZE > 0 && C::SIZE <= 8,
+                "a header checksum needs a checksum 1 to 8 bytes wide"
+            )
+        };
+        Self { checksum_alg }
+    }
+}
+
+impl<C: Checksum> Framer for HeaderChecksumFramer<C> {
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        if payload.len() > u32::MAX as usize {
+            return Err(Error::invalid_frame_with(
+                "payload length exceeds 32-bit header limit",
+                Some(payload.len()),
+                None,
+                Some(u32::MAX as usize),
+            ));
+        }
+        let mut header = [0u8; 20];
+        let mut field = [0u8; 8];
+        header[..4].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        let payload_checksum = self.checksum_alg.calculate(payload);
+        header[4..4 + C::SIZE]
+            .copy_from_slice(self.checksum_alg.write_bytes(payload_checksum, &mut field));
+        let header_checksum = self.checksum_alg.calculate(&header[..4 + C::SIZE]);
+        header[4 + C::SIZE..4 + 2 * C::SIZE]
+            .copy_from_slice(self.checksum_alg.write_bytes(header_checksum, &mut field));
+
+        writer.write_all(&header[..4 + 2 * C::SIZE])?;
+        writer.write_all(payload)?;
+        Ok(())
+    }
+}
+
+/// Reads streams written with a matching [`HeaderChecksumFramer<C>`].
+///
+/// The header checksum is verified before the declared length is trusted:
+/// a corrupted length is `ChecksumMismatch`, and only a verified length is
+/// bounded and used to size the payload buffer. Applies the same length
+/// policy as [`ChecksumDeframer`].
+#[derive(Clone, Copy)]
+pub struct HeaderChecksumDeframer<C: Checksum> {
+    checksum_alg: C,
+    max_frame_len: usize,
+}
+
+impl<C: Checksum> HeaderChecksumDeframer<C> {
+    pub fn new(checksum_alg: C) -> Self {
+        const {
+            assert!(
+                C::SIZE > 0 && C::SIZE <= 8,
+                "a header checksum needs a checksum 1 to 8 bytes wide"
+            )
+        };
+        Self {
+            checksum_alg,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Sets the maximum accepted payload length (enforced before allocation).
+    pub fn with_max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+
+    /// Verifies a complete `4 + 2 * C::SIZE`-byte header, then bounds its
+    /// length. Returns the payload length and the expected payload checksum.
+    #[inline(always)]
+    fn check_header(&self, header: &[u8]) -> Result<(usize, u64)> {
+        let covered = &header[..4 + C::SIZE];
+        let expected = self.checksum_alg.read_bytes(&header[4 + C::SIZE..]);
+        self.checksum_alg.verify(expected, covered)?;
+        let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        check_frame_len(payload_len, self.max_frame_len)?;
+        Ok((payload_len, self.checksum_alg.read_bytes(&header[4..])))
+    }
+}
+
+impl<C: Checksum> Deframer for HeaderChecksumDeframer<C> {
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        let mut header = [0u8; 20];
+        let header = &mut header[..4 + 2 * C::SIZE];
+        match read_header(reader, header)? {
+            Some(()) => {}
+            None => return Ok(None),
+        }
+        let (payload_len, expected) = self.check_header(header)?;
+
+        read_payload(reader, buffer, payload_len)?;
+        self.checksum_alg.verify(expected, &buffer[..payload_len])?;
+        Ok(Some(payload_len))
+    }
+
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        // The header checksum covers the length bytes, so rebuild them from
+        // the parsed value before trusting it.
+        let len_bytes = u32::try_from(payload_len)
+            .map_err(|_| {
+                Error::invalid_frame_with(
+                    "payload length exceeds 32-bit header limit",
+                    Some(payload_len),
+                    None,
+                    Some(u32::MAX as usize),
+                )
+            })?
+            .to_le_bytes();
+        let mut header = [0u8; 20];
+        let header = &mut header[..4 + 2 * C::SIZE];
+        header[..4].copy_from_slice(&len_bytes);
+        reader
+            .read_exact(&mut header[4..])
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::UnexpectedEof => Error::unexpected_eof(),
+                _ => e.into(),
+            })?;
+        let (payload_len, expected) = self.check_header(header)?;
 
         read_payload(reader, buffer, payload_len)?;
         self.checksum_alg.verify(expected, &buffer[..payload_len])?;
@@ -392,6 +561,14 @@ impl<F: Framer> Framer for BoundedFramer<F> {
         }
         self.inner.frame_and_write(writer, payload)
     }
+
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        self.inner.holds_back_payloads()
+    }
 }
 
 //--- Validation Adapters ---
@@ -417,6 +594,15 @@ impl<F: Framer, V: Validator> Framer for ValidatingFramer<F, V> {
         self.validator.validate(payload)?;
         self.inner.frame_and_write(writer, payload)
     }
+
+    #[inline]
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        self.inner.holds_back_payloads()
+    }
 }
 
 /// A composable adapter that adds validation to any `Deframer`.
@@ -487,6 +673,14 @@ impl<F: Framer, C: Fn(&[u8])> Framer for ObserverFramer<F, C> {
         (self.callback)(payload);
         self.inner.frame_and_write(writer, payload)
     }
+
+    fn flush_pending<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.inner.flush_pending(writer)
+    }
+
+    fn holds_back_payloads(&self) -> bool {
+        self.inner.holds_back_payloads()
+    }
 }
 
 /// An adapter that allows observing payloads on the read path without copying or mutating.
@@ -534,6 +728,126 @@ impl<D: Deframer, C: Fn(&[u8])> Deframer for ObserverDeframer<D, C> {
     }
 }
 
+//--- Slice Deframing ---
+
+/// A frame located inside an in-memory byte slice by
+/// [`SliceDeframer::deframe_slice`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceFrame {
+    /// Where the payload sits within the input slice.
+    pub payload: Range<usize>,
+    /// Total bytes the frame occupies (length header, checksum field and
+    /// payload), measured from the start of the input.
+    pub len: usize,
+}
+
+/// Deframing over a byte slice that is already in memory.
+///
+/// The slice counterpart of [`Deframer`]: the same wire format, length bounds,
+/// checksum verification and validation, but the frame is *located* in the
+/// input instead of being read into a buffer, so the payload is never copied
+/// and can be borrowed for as long as the input lives.
+///
+/// Returns `Ok(None)` when `input` does not start with a complete frame
+/// (including when it is empty); what that means is the caller's call — at
+/// the end of a whole stream a non-empty remainder is a torn frame, for a push
+/// decoder it means "need more bytes". Header fields are checked as soon as
+/// they are present: an oversized declared length is rejected before its
+/// payload has arrived, never after waiting for it.
+pub trait SliceDeframer {
+    /// Locates the frame at the start of `input`; see the trait docs for the
+    /// meaning of `Ok(None)`.
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>>;
+}
+
+/// Returns the frame spanning `header_len` header bytes plus `payload_len`
+/// payload bytes, or `None` while `input` is too short to hold it. The caller
+/// has already checked that `input` holds the header.
+#[inline(always)]
+fn complete_frame(input: &[u8], header_len: usize, payload_len: usize) -> Option<SliceFrame> {
+    // Subtract rather than add: `header_len + payload_len` could overflow a
+    // 32-bit usize at the wire ceiling.
+    if input.len() - header_len < payload_len {
+        return None;
+    }
+    Some(SliceFrame {
+        payload: header_len..header_len + payload_len,
+        len: header_len + payload_len,
+    })
+}
+
+impl SliceDeframer for DefaultDeframer {
+    #[inline]
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        let Some(len_bytes) = input.first_chunk::<4>() else {
+            return Ok(None);
+        };
+        let payload_len = u32::from_le_bytes(*len_bytes) as usize;
+        check_frame_len(payload_len, self.max_frame_len)?;
+        Ok(complete_frame(input, 4, payload_len))
+    }
+}
+
+impl<C: Digest> SliceDeframer for ChecksumDeframer<C> {
+    #[inline]
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        let header_len = 4 + C::LEN;
+        let Some(header) = input.get(..header_len) else {
+            return Ok(None);
+        };
+        let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        check_frame_len(payload_len, self.max_frame_len)?;
+        let Some(frame) = complete_frame(input, header_len, payload_len) else {
+            return Ok(None);
+        };
+        self.checksum_alg
+            .verify_digest(&input[4..header_len], &input[frame.payload.clone()])?;
+        Ok(Some(frame))
+    }
+}
+
+impl<C: Checksum> SliceDeframer for HeaderChecksumDeframer<C> {
+    #[inline]
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        let header_len = 4 + 2 * C::SIZE;
+        let Some(header) = input.get(..header_len) else {
+            return Ok(None);
+        };
+        let (payload_len, expected) = self.check_header(header)?;
+        let Some(frame) = complete_frame(input, header_len, payload_len) else {
+            return Ok(None);
+        };
+        self.checksum_alg
+            .verify(expected, &input[frame.payload.clone()])?;
+        Ok(Some(frame))
+    }
+}
+
+impl<D: Deframer + SliceDeframer, V: Validator> SliceDeframer for ValidatingDeframer<D, V> {
+    #[inline]
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        match self.inner.deframe_slice(input)? {
+            Some(frame) => {
+                self.validator.validate(&input[frame.payload.clone()])?;
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<D: Deframer + SliceDeframer, C: Fn(&[u8])> SliceDeframer for ObserverDeframer<D, C> {
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        match self.inner.deframe_slice(input)? {
+            Some(frame) => {
+                (self.callback)(&input[frame.payload.clone()]);
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
 //--- Fluent Extension Traits ---
 
 /// Extension methods for framers to enable fluent composition without importing adapter types.
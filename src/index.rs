@@ -0,0 +1,519 @@
+//! Sidecar frame index: jump to the Nth frame of a stream without scanning.
+//!
+//! The wire format has no random access — frame `n` starts wherever the
+//! `n - 1` frames before it end. A [`FrameIndex`] records, for every frame,
+//! the byte offset of its length header and its payload length (the frame
+//! number is the entry's position), and persists them in a compact sidecar
+//! file next to the stream. A `StreamReader` over a `Read + Seek` source then
+//! seeks straight to a frame with
+//! [`seek_to_frame`](crate::StreamReader::seek_to_frame).
+//!
+//! An index is built either while writing
+//! ([`StreamWriter::with_index`](crate::StreamWriter::with_index)) or after
+//! the fact by scanning an existing stream ([`FrameIndex::scan_file`]).
+//!
+//! # Sidecar format
+//!
+//! All integers are little-endian:
+//!
+//! ```text
+//! [4 magic "FSIX"] [2 version = 1] [2 reserved = 0] [8 frame count] [8 end offset]
+//! frame count × [8 frame offset] [4 payload length]
+//! ```
+//!
+//! The end offset is one past the last indexed frame: where the next frame
+//! will be written. Entries are fixed-size (12 bytes), so frame `n`'s entry
+//! sits at a computable position. The sidecar carries no checksum of its own;
+//! [`read_from`](FrameIndex::read_from) rejects an index whose entries are
+//! out of order or overlap, and a frame reached through a stale index still
+//! passes through the deframer, which checks its length bound and checksum.
+
+use crate::error::{Error, Result};
+use crate::framing::{Deframer, Framer};
+use crate::recover::CountingReader;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Magic bytes opening every sidecar index file.
+pub const INDEX_MAGIC: [u8; 4] = *b"FSIX";
+
+/// The sidecar format version written by this crate.
+pub const INDEX_VERSION: u16 = 1;
+
+/// The extension [`FrameIndex::sidecar_path`] appends to a stream's path.
+pub const INDEX_EXTENSION: &str = "fsidx";
+
+const HEADER_LEN: usize = 24;
+const ENTRY_LEN: usize = 12;
+
+/// Entries allocated before any is read; larger indexes grow as they are
+/// read, so a corrupt frame count cannot force a huge allocation.
+const PREALLOCATED_ENTRIES: u64 = 1 << 16;
+
+/// The smallest frame the wire format can hold: a bare length header.
+const MIN_FRAME_LEN: u64 = 4;
+
+/// One indexed frame. Its frame number is its position in the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Byte offset of the frame's length header within the stream.
+    pub offset: u64,
+    /// Length of the frame's payload in bytes.
+    pub payload_len: u32,
+}
+
+/// The offsets of every frame in a stream, in order.
+///
+/// ```rust
+/// use flatstream::{DefaultDeframer, DefaultFramer, FrameIndex, Result, StreamReader, StreamWriter};
+/// use std::io::Cursor;
+///
+/// # fn main() -> Result<()> {
+/// let mut capture = Vec::new();
+/// let mut writer = StreamWriter::new(Cursor::new(&mut capture), DefaultFramer)
+///     .with_index(FrameIndex::new());
+/// for i in 0..100 {
+///     writer.write(&format!("event {i}"))?;
+/// }
+/// let index = writer.take_index().unwrap();
+/// assert_eq!(index.frame_count(), 100);
+///
+/// // Persist next to the capture with `index.save(FrameIndex::sidecar_path("capture.bin"))`.
+/// let mut sidecar = Vec::new();
+/// index.write_to(&mut sidecar)?;
+///
+/// let mut reader = StreamReader::new(Cursor::new(&capture), DefaultDeframer::new())
+///     .with_index(FrameIndex::read_from(&sidecar[..])?);
+/// reader.seek_to_frame(42)?;
+/// let root = flatbuffers::root::<&str>(reader.read_message()?.unwrap())?;
+/// assert_eq!(root, "event 42");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameIndex {
+    entries: Vec<IndexEntry>,
+    end_offset: u64,
+}
+
+impl FrameIndex {
+    /// Creates an empty index for a stream that starts at offset 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty index for a stream whose first frame starts at
+    /// `offset`, e.g. after a file header.
+    pub fn starting_at(offset: u64) -> Self {
+        Self {
+            entries: Vec::new(),
+            end_offset: offset,
+        }
+    }
+
+    /// Indexes a stream from the reader's **current position**, with offsets
+    /// relative to that position. To index a file from its beginning with
+    /// absolute offsets, use [`scan_file`](Self::scan_file).
+    ///
+    /// The scan reads every frame through `deframer`, so checksums and
+    /// validators apply, and stops at the first error: a torn tail is
+    /// `UnexpectedEof`. Repair a crashed journal with
+    /// [`recover_file`](crate::recover_file) before indexing it. Like
+    /// recovery, scanning needs a deframer that consumes exactly one frame
+    /// per call and never reads ahead.
+    pub fn scan<R: Read, D: Deframer>(reader: R, deframer: D) -> Result<Self> {
+        let mut reader = CountingReader::new(reader);
+        let mut buffer = Vec::new();
+        let mut index = Self::new();
+        while let Some(n) = deframer.read_and_deframe(&mut reader, &mut buffer)? {
+            index.record(n, reader.count)?;
+        }
+        Ok(index)
+    }
+
+    /// Indexes a seekable stream (typically a journal file) from its
+    /// **beginning**, with absolute offsets. The cursor is left at
+    /// [`end_offset`](Self::end_offset), ready for an indexing writer to
+    /// resume appending.
+    pub fn scan_file<R: Read + Seek, D: Deframer>(reader: &mut R, deframer: D) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let index = Self::scan(&mut *reader, deframer)?;
+        reader.seek(SeekFrom::Start(index.end_offset))?;
+        Ok(index)
+    }
+
+    /// Appends a frame of `payload_len` payload bytes that ends at byte
+    /// `frame_end`; it starts at the previous end offset.
+    pub(crate) fn record(&mut self, payload_len: usize, frame_end: u64) -> Result<()> {
+        let payload_len = u32::try_from(payload_len).map_err(|_| {
+            Error::invalid_frame_with(
+                "payload length exceeds 32-bit header limit",
+                Some(payload_len),
+                None,
+                Some(u32::MAX as usize),
+            )
+        })?;
+        self.entries.push(IndexEntry {
+            offset: self.end_offset,
+            payload_len,
+        });
+        self.end_offset = frame_end;
+        Ok(())
+    }
+
+    /// Number of indexed frames.
+    pub fn frame_count(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Whether no frame is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry for frame `n` (zero-based), if indexed.
+    pub fn get(&self, n: u64) -> Option<IndexEntry> {
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| self.entries.get(n))
+            .copied()
+    }
+
+    /// All entries, in frame order.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Byte offset one past the last indexed frame.
+    pub fn end_offset(&self) -> u64 {
+        self.end_offset
+    }
+
+    /// Serializes the index in the sidecar format.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(&INDEX_MAGIC);
+        header[4..6].copy_from_slice(&INDEX_VERSION.to_le_bytes());
+        header[8..16].copy_from_slice(&self.frame_count().to_le_bytes());
+        header[16..24].copy_from_slice(&self.end_offset.to_le_bytes());
+        writer.write_all(&header)?;
+        for entry in &self.entries {
+            let mut record = [0u8; ENTRY_LEN];
+            record[..8].copy_from_slice(&entry.offset.to_le_bytes());
+            record[8..].copy_from_slice(&entry.payload_len.to_le_bytes());
+            writer.write_all(&record)?;
+        }
+        Ok(())
+    }
+
+    /// Parses an index in the sidecar format, rejecting a wrong magic or
+    /// version, a length that disagrees with the frame count, and entries that
+    /// are out of order, overlap, or extend past the end offset — all as
+    /// `ErrorKind::InvalidFrame`.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut header = [0u8; HEADER_LEN];
+        let n = read_up_to(&mut reader, &mut header)?;
+        if n < HEADER_LEN {
+            return Err(Error::invalid_frame_with(
+                "frame index shorter than its header",
+                None,
+                Some(n),
+                Some(HEADER_LEN),
+            ));
+        }
+        if header[..4] != INDEX_MAGIC {
+            return Err(Error::invalid_frame("not a frame index (bad magic)"));
+        }
+        let version = u16::from_le_bytes(header[4..6].try_into().unwrap());
+        if version != INDEX_VERSION {
+            return Err(Error::invalid_frame_with(
+                "unsupported frame index version",
+                Some(version as usize),
+                None,
+                Some(INDEX_VERSION as usize),
+            ));
+        }
+        let count = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        let end_offset = u64::from_le_bytes(header[16..24].try_into().unwrap());
+        let count_mismatch = |found: Option<usize>| {
+            Error::invalid_frame_with(
+                "frame index length disagrees with its frame count",
+                usize::try_from(count).ok(),
+                found,
+                None,
+            )
+        };
+
+        // Entries are parsed as they are read, so the sidecar is never held
+        // in memory twice. The declared count only caps the up-front
+        // allocation; it is checked against the entries that actually exist.
+        let mut entries = Vec::with_capacity(count.min(PREALLOCATED_ENTRIES) as usize);
+        let mut next_free = 0u64;
+        let mut record = [0u8; ENTRY_LEN];
+        loop {
+            match read_up_to(&mut reader, &mut record)? {
+                0 => break,
+                ENTRY_LEN if (entries.len() as u64) < count => {}
+                ENTRY_LEN => return Err(count_mismatch(None)),
+                _ => return Err(count_mismatch(Some(entries.len()))),
+            }
+            let entry = IndexEntry {
+                offset: u64::from_le_bytes(record[..8].try_into().unwrap()),
+                payload_len: u32::from_le_bytes(record[8..].try_into().unwrap()),
+            };
+            if entry.offset < next_free {
+                return Err(Error::invalid_frame_with(
+                    "frame index entries overlap or are out of order",
+                    Some(entries.len()),
+                    None,
+                    None,
+                ));
+            }
+            next_free = entry
+                .offset
+                .saturating_add(MIN_FRAME_LEN + entry.payload_len as u64);
+            entries.push(entry);
+        }
+        if (entries.len() as u64) < count {
+            return Err(count_mismatch(Some(entries.len())));
+        }
+        if next_free > end_offset {
+            return Err(Error::invalid_frame(
+                "frame index entry extends past its end offset",
+            ));
+        }
+        Ok(Self {
+            entries,
+            end_offset,
+        })
+    }
+
+    /// Writes the index to the sidecar file at `path`, replacing it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reads the sidecar file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read_from(File::open(path)?)
+    }
+
+    /// The conventional sidecar path for a stream: the stream's path with
+    /// `.fsidx` appended (`capture.bin` → `capture.bin.fsidx`).
+    pub fn sidecar_path<P: AsRef<Path>>(stream_path: P) -> PathBuf {
+        let mut path = OsString::from(stream_path.as_ref());
+        path.push(".");
+        path.push(INDEX_EXTENSION);
+        path.into()
+    }
+}
+
+/// Fills as much of `buf` as `reader` has left, returning how much that was.
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// Tracks how many bytes a framer has written, so a frame's end offset is
+/// known without the writer having to understand the framer's layout.
+pub(crate) struct CountingWriter<'w, W> {
+    inner: &'w mut W,
+    pub(crate) count: u64,
+}
+
+impl<'w, W> CountingWriter<'w, W> {
+    pub(crate) fn new(inner: &'w mut W) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Frames `payload` through `framer` and records the frame in `index`.
+/// Outlined so the unindexed write path stays small enough to inline.
+///
+/// A framer that holds payloads back (the block framer) is rejected before
+/// anything is written: its frames are not one per payload, and the bytes
+/// `flush_pending` writes later would never be counted.
+#[inline(never)]
+pub(crate) fn frame_and_record<F: Framer, W: Write>(
+    framer: &F,
+    writer: &mut W,
+    index: &mut FrameIndex,
+    payload: &[u8],
+) -> Result<()> {
+    if framer.holds_back_payloads() {
+        return Err(Error::invalid_frame(
+            "cannot index frames through a framer that holds payloads back",
+        ));
+    }
+    let mut counter = CountingWriter::new(writer);
+    framer.frame_and_write(&mut counter, payload)?;
+    let frame_end = index.end_offset + counter.count;
+    index.record(payload.len(), frame_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+    use crate::writer::StreamWriter;
+    use std::io::Cursor;
+
+    #[cfg(feature = "crc32")]
+    use crate::{ChecksumDeframer, ChecksumFramer, Crc32};
+
+    fn indexed_capture<F: Framer>(framer: F, payloads: &[&[u8]]) -> (Vec<u8>, FrameIndex) {
+        let mut wire = Vec::new();
+        let mut writer =
+            StreamWriter::new(Cursor::new(&mut wire), framer).with_index(FrameIndex::new());
+        for p in payloads {
+            let mut builder = flatbuffers::FlatBufferBuilder::new();
+            let v = builder.create_vector(p);
+            builder.finish(v, None);
+            writer.write_finished(&mut builder).unwrap();
+        }
+        let index = writer.take_index().unwrap();
+        drop(writer);
+        (wire, index)
+    }
+
+    #[test]
+    fn writer_index_matches_a_scan() {
+        let (wire, written) = indexed_capture(DefaultFramer, &[b"a", b"", b"ccc-ccc"]);
+        let scanned = FrameIndex::scan(&wire[..], DefaultDeframer::new()).unwrap();
+        assert_eq!(written, scanned);
+        assert_eq!(written.frame_count(), 3);
+        assert_eq!(written.end_offset(), wire.len() as u64);
+        assert_eq!(written.get(0).unwrap().offset, 0);
+        assert!(written.get(3).is_none());
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn checksummed_frames_seek_exactly() {
+        let payloads: [&[u8]; 4] = [b"zero", b"one", b"two", b"three"];
+        let (wire, index) = indexed_capture(ChecksumFramer::new(Crc32), &payloads);
+        let mut reader = StreamReader::new(Cursor::new(&wire), ChecksumDeframer::new(Crc32))
+            .with_index(index.clone());
+        assert_eq!(reader.frame_count(), Some(4));
+        for n in [3u64, 0, 2, 1] {
+            reader.seek_to_frame(n).unwrap();
+            let payload = reader.read_message().unwrap().unwrap();
+            let root = flatbuffers::root::<flatbuffers::Vector<u8>>(payload).unwrap();
+            assert_eq!(root.bytes(), payloads[n as usize]);
+            assert_eq!(payload.len(), index.get(n).unwrap().payload_len as usize);
+        }
+        reader.seek_to_frame(4).unwrap();
+        assert!(reader.read_message().unwrap().is_none());
+        let err = reader.seek_to_frame(5).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[test]
+    fn sidecar_roundtrip_through_a_file() {
+        let (wire, index) = indexed_capture(DefaultFramer, &[b"one", b"two"]);
+        let dir = tempfile::tempdir().unwrap();
+        let stream_path = dir.path().join("capture.bin");
+        std::fs::write(&stream_path, &wire).unwrap();
+        let sidecar = FrameIndex::sidecar_path(&stream_path);
+        assert_eq!(sidecar, dir.path().join("capture.bin.fsidx"));
+
+        index.save(&sidecar).unwrap();
+        assert_eq!(
+            std::fs::metadata(&sidecar).unwrap().len(),
+            (HEADER_LEN + 2 * ENTRY_LEN) as u64
+        );
+        assert_eq!(FrameIndex::load(&sidecar).unwrap(), index);
+
+        let mut file = File::open(&stream_path).unwrap();
+        let scanned = FrameIndex::scan_file(&mut file, DefaultDeframer::new()).unwrap();
+        assert_eq!(scanned, index);
+        assert_eq!(file.stream_position().unwrap(), index.end_offset());
+    }
+
+    #[test]
+    fn corrupt_sidecars_are_rejected() {
+        let (_, index) = indexed_capture(DefaultFramer, &[b"one", b"two"]);
+        let mut good = Vec::new();
+        index.write_to(&mut good).unwrap();
+
+        let mut bad_magic = good.clone();
+        bad_magic[0] = b'X';
+        let mut truncated = good.clone();
+        truncated.pop();
+        let mut out_of_order = good.clone();
+        out_of_order[HEADER_LEN + ENTRY_LEN..HEADER_LEN + ENTRY_LEN + 8]
+            .copy_from_slice(&1u64.to_le_bytes());
+        let mut past_end = good.clone();
+        past_end[16..24].copy_from_slice(&5u64.to_le_bytes());
+        let mut extra_entry = good.clone();
+        extra_entry.extend_from_slice(&good[good.len() - ENTRY_LEN..]);
+        let mut huge_count = good.clone();
+        huge_count[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        for (label, bytes) in [
+            ("magic", bad_magic),
+            ("length", truncated),
+            ("order", out_of_order),
+            ("end", past_end),
+            ("extra", extra_entry),
+            ("count", huge_count),
+            ("empty", Vec::new()),
+        ] {
+            let err = FrameIndex::read_from(&bytes[..]).unwrap_err();
+            assert!(
+                matches!(err.kind(), ErrorKind::InvalidFrame { .. }),
+                "{label}: {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn resumed_writer_extends_a_scanned_index() {
+        let (mut wire, _) = indexed_capture(DefaultFramer, &[b"before"]);
+        let mut file = Cursor::new(&mut wire);
+        let index = FrameIndex::scan_file(&mut file, DefaultDeframer::new()).unwrap();
+        let mut writer = StreamWriter::new(file, DefaultFramer).with_index(index);
+        writer.write(&"after").unwrap();
+        let index = writer.take_index().unwrap();
+        drop(writer);
+        assert_eq!(
+            index,
+            FrameIndex::scan(&wire[..], DefaultDeframer::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn seeking_without_an_index_is_an_error() {
+        let mut reader = StreamReader::new(Cursor::new(Vec::new()), DefaultDeframer::new());
+        assert_eq!(reader.frame_count(), None);
+        assert!(reader.seek_to_frame(0).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Open a journal file for appending, repairing a torn tail first.
+//!
+//! Reopening a journal after a crash is always the same sequence: scan it
+//! with [`recover_file`], truncate **only** if the scan ended in a
+//! [`RecoveryEnd::TornTail`], and resume appending at the last good offset.
+//! [`Journal::open`] performs exactly that sequence, so callers cannot get the
+//! one dangerous step — truncation — wrong.
+
+use crate::durable::{DurableWriter, SyncPolicy};
+use crate::error::Result;
+use crate::framing::{Deframer, Framer};
+use crate::recover::{recover_file, RecoveryEnd, RecoveryReport};
+use crate::writer::StreamWriter;
+use std::fs::{File, OpenOptions};
+use std::io::BufWriter;
+use std::path::Path;
+
+/// A journal file that has been scanned, repaired if torn, and positioned for
+/// appending.
+///
+/// ```rust
+/// use flatstream::{DefaultDeframer, DefaultFramer, Journal, RecoveryEnd, Result};
+///
+/// # fn main() -> Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// # let path = dir.path().join("journal.bin");
+/// // Simulate a crash mid-append: one intact frame, then a torn one.
+/// std::fs::write(&path, [&5u32.to_le_bytes()[..], b"hello", &9u32.to_le_bytes(), b"wor"].concat())?;
+///
+/// let journal = Journal::open(&path, DefaultDeframer::new(), DefaultFramer)?;
+/// assert_eq!(journal.report().end, RecoveryEnd::TornTail);
+/// assert_eq!(journal.truncated_bytes(), 7);
+///
+/// let mut writer = journal.into_writer();
+/// writer.write_payload(b"world")?;
+/// writer.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Journal<F: Framer> {
+    file: File,
+    framer: F,
+    report: RecoveryReport,
+    truncated_bytes: u64,
+}
+
+impl<F: Framer> Journal<F> {
+    /// Opens (or creates) the journal at `path` and makes it safe to append
+    /// to.
+    ///
+    /// The file is scanned with `deframer`, which must match the wire format
+    /// the journal was written with (same checksum, no validators). Then:
+    ///
+    /// - a clean end leaves the file untouched;
+    /// - a torn tail is truncated to `last_good_offset` with `File::set_len`
+    ///   and the new length is synced, so a second crash cannot resurrect the
+    ///   torn bytes behind newly appended frames;
+    /// - **any other stop reason** (`ChecksumMismatch`, `InvalidFrame`,
+    ///   `ValidationFailed`, an I/O error) is returned as that error, and the
+    ///   file is not modified. Corruption never authorizes truncation.
+    pub fn open<P: AsRef<Path>, D: Deframer>(path: P, deframer: D, framer: F) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let len = file.metadata()?.len();
+        let report = recover_file(&mut file, deframer)?;
+        let mut truncated_bytes = 0;
+        if report.end == RecoveryEnd::TornTail {
+            file.set_len(report.last_good_offset)?;
+            file.sync_data()?;
+            truncated_bytes = len - report.last_good_offset;
+        }
+        Ok(Self {
+            file,
+            framer,
+            report,
+            truncated_bytes,
+        })
+    }
+
+    /// The scan's report: intact frames, last good offset, and how it ended.
+    pub fn report(&self) -> &RecoveryReport {
+        &self.report
+    }
+
+    /// How many torn-tail bytes were removed (0 after a clean end).
+    pub fn truncated_bytes(&self) -> u64 {
+        self.truncated_bytes
+    }
+
+    /// Returns a buffered [`StreamWriter`] that appends after the last intact
+    /// frame.
+    pub fn into_writer<'a>(self) -> StreamWriter<'a, BufWriter<File>, F> {
+        StreamWriter::new(BufWriter::new(self.file), self.framer)
+    }
+
+    /// Returns a [`DurableWriter`] that appends after the last intact frame
+    /// and syncs according to `policy`.
+    pub fn into_durable_writer<'a>(self, policy: SyncPolicy) -> Result<DurableWriter<'a, F>> {
+        DurableWriter::new(self.file, self.framer, policy)
+    }
+
+    /// Returns the repaired file, positioned at `last_good_offset`, and the
+    /// framer.
+    pub fn into_parts(self) -> (File, F) {
+        (self.file, self.framer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+
+    fn frames(payloads: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for p in payloads {
+            DefaultFramer.frame_and_write(&mut out, p).unwrap();
+        }
+        out
+    }
+
+    fn read_all(path: &Path) -> Vec<Vec<u8>> {
+        let mut reader = StreamReader::new(File::open(path).unwrap(), DefaultDeframer::new());
+        let mut out = Vec::new();
+        reader
+            .process_all(|p| {
+                out.push(p.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn creates_a_missing_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("new.bin");
+        let journal = Journal::open(&path, DefaultDeframer::new(), DefaultFramer).unwrap();
+        assert_eq!(journal.report().end, RecoveryEnd::CleanEof);
+        assert_eq!(journal.report().frames, 0);
+        let mut writer = journal.into_writer();
+        writer.write_payload(b"first").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(read_all(&path), [b"first"]);
+    }
+
+    #[test]
+    fn clean_journal_is_untouched_and_appended_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        std::fs::write(&path, frames(&[b"a", b"b"])).unwrap();
+
+        let journal = Journal::open(&path, DefaultDeframer::new(), DefaultFramer).unwrap();
+        assert_eq!(journal.report().frames, 2);
+        assert_eq!(journal.truncated_bytes(), 0);
+        let mut writer = journal.into_durable_writer(SyncPolicy::EveryFrame).unwrap();
+        writer.write_payload(b"c").unwrap();
+        assert_eq!(writer.durable_offset(), 15);
+        assert_eq!(read_all(&path), [b"a", b"b", b"c"]);
+    }
+
+    #[test]
+    fn torn_tail_is_truncated_before_appending() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        let mut bytes = frames(&[b"intact"]);
+        bytes.extend_from_slice(&[200, 0, 0, 0, 1, 2, 3]);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let journal = Journal::open(&path, DefaultDeframer::new(), DefaultFramer).unwrap();
+        assert_eq!(journal.report().end, RecoveryEnd::TornTail);
+        assert_eq!(journal.report().last_good_offset, 10);
+        assert_eq!(journal.truncated_bytes(), 7);
+        let mut writer = journal.into_writer();
+        writer.write_payload(b"next").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(read_all(&path), [b"intact".to_vec(), b"next".to_vec()]);
+    }
+
+    #[test]
+    fn corruption_is_an_error_and_never_truncates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("j.bin");
+        let mut bytes = frames(&[b"intact"]);
+        bytes.extend_from_slice(&frames(&[&[0u8; 64][..]]));
+        std::fs::write(&path, &bytes).unwrap();
+
+        let deframer = DefaultDeframer::new().with_max_frame_len(16);
+        let err = match Journal::open(&path, deframer, DefaultFramer) {
+            Ok(_) => panic!("corrupt journal opened"),
+            Err(e) => e,
+        };
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    }
+}
@@ -12,11 +12,39 @@
 //! ## Key Features
 //!
 //! * **Composable Architecture**: Separate traits for serialization, framing, and checksums
-//! * **Flexible Framing**: Choose between simple length-prefixed or checksum-protected framing
+//! * **Flexible Framing**: Choose between simple length-prefixed or checksum-protected framing,
+//!   optionally with a header checksum that also covers the length (`HeaderChecksumFramer`)
+//! * **Wide Digests**: `ChecksumFramer` also carries 16- and 32-byte `Digest`s — XXH3-128,
+//!   SHA-256 (feature `sha256`) and BLAKE3 (feature `blake3`)
+//! * **Runtime Checksum Selection**: `ChecksumKind` parses an algorithm name from configuration
+//!   and builds a non-generic `ChecksumKindFramer`/`ChecksumKindDeframer`
+//! * **Self-Describing Streams**: An optional `StreamPreamble` lets `AutoDeframer` pick the
+//!   checksum and frame bound itself
 //! * **Borrowed Payload Access**: Direct `&[u8]` access through the Processor API
+//! * **In-Memory Streams**: `SliceReader` yields payloads borrowed from the input itself
+//! * **Sans-IO Decoding**: `FrameDecoder` accepts pushed byte chunks for event-loop integrations
+//! * **Async I/O**: tokio `AsyncStreamReader`/`AsyncStreamWriter` (feature `tokio`) and a
+//!   `FlatStreamCodec` for `tokio_util::codec::Framed` (feature `tokio_codec`)
+//! * **Compression**: `CompressingFramer`/`DecompressingDeframer` compress each frame with LZ4 or
+//!   zstd (features `lz4`, `zstd`), optionally against a trained `ZstdDictionary`, or batch small
+//!   frames into compressed blocks with `BlockFramer`/`BlockDeframer`
+//! * **Authenticated Frames**: `HmacFramer` tags and `AeadFramer` encrypts every frame under
+//!   rotatable keys, rejecting tampered, replayed or reordered frames (features `hmac`, `aead`)
+//! * **Runtime Pipelines**: Object-safe `DynFramer`/`DynDeframer`; any strategy boxes into a
+//!   `BoxFramer`/`BoxDeframer` that `StreamWriter`/`StreamReader` and the adapters accept
 //! * **Memory Efficient**: Reusable buffers and minimal allocations
 //! * **Type Safe**: Generic over I/O types and framing strategies
-//! * **Journal Recovery**: Strict torn-tail detection with exact truncation offsets
+//! * **Durability**: `DurableWriter` sync policies and `GroupCommitWriter` shared `fdatasync`
+//! * **Journal Recovery**: Strict torn-tail detection with exact truncation offsets, and
+//!   `Journal::open` to repair a torn tail and resume appending
+//! * **Integrity Audits**: `Auditor` checks every frame of a stream and reports each bad one
+//! * **Sync Markers**: `SyncFramer`/`SyncDeframer` let a reader resynchronize after corruption
+//! * **Salvage**: Explicitly destructive copy of every verifiable frame past mid-file corruption
+//! * **Follow Mode**: `TailReader` consumes a journal while it is still being written
+//! * **Segment Rotation**: `RollingStreamWriter` rolls journals by size, frame count or age,
+//!   and `SegmentedReader` replays the segments as one stream
+//! * **Frame Index**: Sidecar `FrameIndex` files for O(1) seeks to the Nth frame
+//! * **Sealed Containers**: Self-describing files with a footer index and summary
 //!
 //! ## Quick Start
 //!
@@ -107,33 +135,85 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
-// The default build is provably free of `unsafe`: the only unsafe block in the
-// crate is the opt-in `unsafe_typed` verification-skipping path (reader.rs).
-#![cfg_attr(not(feature = "unsafe_typed"), forbid(unsafe_code))]
+// The default build is provably free of `unsafe`: the only unsafe blocks in the
+// crate are the opt-in `unsafe_typed` verification-skipping path (reader.rs)
+// and the opt-in `mmap` file mapping (mmap.rs).
+#![cfg_attr(
+    not(any(feature = "unsafe_typed", feature = "mmap")),
+    forbid(unsafe_code)
+)]
 
+#[cfg(feature = "tokio")]
+pub mod async_io;
+pub mod audit;
+#[cfg(any(feature = "hmac", feature = "aead"))]
+pub mod auth;
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+pub mod block;
+pub mod boxed;
 pub mod checksum;
+pub mod checksum_kind;
+#[cfg(feature = "tokio_codec")]
+pub mod codec;
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+pub mod compression;
+pub mod container;
+pub mod decoder;
+pub mod digest;
+pub mod durable;
 pub mod error;
 pub mod framing;
+pub mod index;
+pub mod journal;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod policy;
+pub mod preamble;
 pub mod reader;
 pub mod recover;
+pub mod rolling;
+pub mod salvage;
+pub mod segmented;
+pub mod slice;
+pub mod sync_marker;
+pub mod tail;
 pub mod traits;
 pub mod validation;
 pub mod writer;
 
 // Re-export the main public API for user convenience.
+pub use audit::{AuditEnd, AuditReport, Auditor, ChecksumStatus, FrameAudit, ValidationStatus};
+pub use boxed::{
+    ArcDeframer, ArcFramer, BoxDeframer, BoxFramer, DeframerBoxExt, DynDeframer, DynFramer,
+    FramerBoxExt,
+};
 pub use checksum::NoChecksum;
+pub use checksum_kind::{ChecksumKind, ChecksumKindDeframer, ChecksumKindFramer};
+pub use container::{seal_journal, ContainerReader, ContainerSummary, ContainerWriter};
+pub use decoder::FrameDecoder;
+pub use digest::{Digest, MAX_DIGEST_LEN};
+pub use durable::{DurableWriter, GroupCommitWriter, SyncMode, SyncPolicy};
 pub use error::{Error, ErrorKind, Result};
 pub use framing::{
     BoundedFramer, DefaultDeframer, DefaultFramer, Deframer, DeframerExt, Framer, FramerExt,
-    ValidatingDeframer, ValidatingFramer, DEFAULT_MAX_FRAME_LEN, MAX_WIRE_FRAME_LEN,
+    SliceDeframer, SliceFrame, ValidatingDeframer, ValidatingFramer, DEFAULT_MAX_FRAME_LEN,
+    MAX_WIRE_FRAME_LEN,
 };
+pub use index::{FrameIndex, IndexEntry};
+pub use journal::Journal;
 pub use policy::{
     AdaptiveWatermarkPolicy, Clock, MemoryPolicy, MonotonicClock, NoOpPolicy, ReclamationInfo,
     ReclamationReason, SizeThresholdPolicy,
 };
+pub use preamble::{AutoDeframer, StreamPreamble};
 pub use reader::{Messages, StreamReader, TypedMessages};
 pub use recover::{recover, recover_file, RecoveryEnd, RecoveryReport};
+pub use rolling::{NumberedSegments, RollPolicy, RollingStreamWriter, SegmentInfo, SegmentNaming};
+pub use salvage::{salvage, salvage_file, SalvageReport};
+pub use segmented::{SegmentedMessages, SegmentedReader, TypedSegmentedMessages};
+pub use slice::{SliceMessages, SliceReader, TypedSliceMessages};
+pub use sync_marker::{SyncDeframer, SyncFramer, DEFAULT_SYNC_MARKER};
+pub use tail::TailReader;
 pub use traits::StreamDeserialize;
 pub use traits::StreamSerialize;
 pub use validation::{
@@ -143,11 +223,43 @@ pub use writer::StreamWriter;
 
 #[cfg(feature = "xxhash")]
 pub use checksum::XxHash64;
-#[cfg(any(feature = "xxhash", feature = "crc32", feature = "crc16"))]
+#[cfg(feature = "blake3")]
+pub use digest::Blake3;
+#[cfg(feature = "sha256")]
+pub use digest::Sha256;
+#[cfg(feature = "xxhash")]
+pub use digest::XxHash128;
+#[cfg(any(
+    feature = "xxhash",
+    feature = "crc32",
+    feature = "crc16",
+    feature = "crc32c",
+    feature = "sha256",
+    feature = "blake3"
+))]
 pub use framing::{ChecksumDeframer, ChecksumFramer};
+#[cfg(any(
+    feature = "xxhash",
+    feature = "crc32",
+    feature = "crc16",
+    feature = "crc32c"
+))]
+pub use framing::{HeaderChecksumDeframer, HeaderChecksumFramer};
 
 #[cfg(feature = "crc32")]
 pub use checksum::Crc32;
 
 #[cfg(feature = "crc16")]
 pub use checksum::Crc16;
+
+#[cfg(feature = "crc32c")]
+pub use checksum::Crc32c;
+
+#[cfg(feature = "mmap")]
+pub use mmap::MmapSource;
+
+#[cfg(feature = "tokio")]
+pub use async_io::{AsyncDeframingReader, AsyncStreamReader, AsyncStreamWriter};
+
+#[cfg(feature = "tokio_codec")]
+pub use codec::{FlatStreamCodec, DEFAULT_CODEC_MAX_FRAME_LEN};
@@ -0,0 +1,194 @@
+//! Memory-mapped journal access (feature `mmap`).
+//!
+//! [`MmapSource`] maps a journal file once and hands out
+//! [`SliceReader`]s over the mapping. Payloads borrow the mapping, not a
+//! reader buffer: they stay valid until the source is remapped or dropped,
+//! any number of readers (cursors) can walk the same mapping at once, and no
+//! frame is ever copied. Frame bounds, checksum verification and validators
+//! are exactly those of the deframer passed to [`reader`](MmapSource::reader).
+//!
+//! A file that is still being appended to is followed with
+//! [`remap`](MmapSource::remap): it takes `&mut self`, so the borrow checker
+//! guarantees no payload from the old mapping is alive when it is replaced.
+//! Record a reader's [`position`](SliceReader::position) before remapping and
+//! resume from it with [`reader_at`](MmapSource::reader_at).
+
+use crate::error::Result;
+use crate::framing::SliceDeframer;
+use crate::slice::SliceReader;
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+/// A read-only memory mapping of a journal file.
+///
+/// ```no_run
+/// use flatstream::{DefaultDeframer, MmapSource, Result};
+///
+/// # fn main() -> Result<()> {
+/// let source = MmapSource::open("capture.bin")?;
+/// let mut reader = source.reader(DefaultDeframer::new().with_max_frame_len(1 << 20));
+/// let mut largest: &[u8] = &[];
+/// reader.process_all(|payload| {
+///     if payload.len() > largest.len() {
+///         largest = payload; // borrowed from the mapping
+///     }
+///     Ok(())
+/// })?;
+/// println!("largest frame: {} bytes", largest.len());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # File mutation
+///
+/// A mapping observes the file's pages directly. Appending is fine — bytes
+/// past the mapped length are simply not visible until [`remap`](Self::remap)
+/// — but the mapped range must not be truncated or rewritten in place while
+/// the source is alive: on most platforms touching a truncated page raises
+/// `SIGBUS`. Run journal repair (`recover_file` + `set_len`) before mapping,
+/// never during.
+pub struct MmapSource {
+    file: File,
+    // `None` for an empty file: a zero-length mapping is not portable.
+    map: Option<Mmap>,
+}
+
+impl MmapSource {
+    /// Opens and maps the file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file(File::open(path)?)
+    }
+
+    /// Maps an already open file (readable handle required).
+    pub fn from_file(file: File) -> Result<Self> {
+        let map = map_file(&file)?;
+        Ok(Self { file, map })
+    }
+
+    /// The mapped bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_deref().unwrap_or(&[])
+    }
+
+    /// Length of the current mapping in bytes.
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    /// Whether the current mapping is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reader over the mapping, starting at its first byte.
+    pub fn reader<D: SliceDeframer>(&self, deframer: D) -> SliceReader<'_, D> {
+        SliceReader::new(self.as_bytes(), deframer)
+    }
+
+    /// Returns a reader over the mapping that starts at byte `position` —
+    /// typically a [`SliceReader::position`] saved before a
+    /// [`remap`](Self::remap).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is beyond the end of the mapping.
+    pub fn reader_at<D: SliceDeframer>(&self, deframer: D, position: usize) -> SliceReader<'_, D> {
+        SliceReader::with_position(self.as_bytes(), deframer, position)
+    }
+
+    /// Remaps the file if its length changed since the last mapping, so
+    /// frames appended by a writer become visible. Returns `true` when the
+    /// mapping was replaced.
+    ///
+    /// A torn frame at the end of the old mapping (a writer caught
+    /// mid-append) typically completes after a remap: resume reading from the
+    /// last good position rather than treating that `UnexpectedEof` as final.
+    pub fn remap(&mut self) -> Result<bool> {
+        if self.file.metadata()?.len() == self.len() as u64 {
+            return Ok(false);
+        }
+        self.map = map_file(&self.file)?;
+        Ok(true)
+    }
+
+    /// Returns a reference to the mapped file.
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+}
+
+fn map_file(file: &File) -> Result<Option<Mmap>> {
+    if file.metadata()?.len() == 0 {
+        return Ok(None);
+    }
+    // SAFETY: the mapping is read-only and its lifetime is tied to
+    // `MmapSource`, which hands out slices only through `&self`. Soundness
+    // additionally requires that no process truncates or rewrites the mapped
+    // range while it is alive; that is the documented contract of
+    // `MmapSource` (append-only journals satisfy it).
+    let map = unsafe { Mmap::map(file)? };
+    Ok(Some(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer, Framer};
+    use std::io::Write;
+
+    #[test]
+    fn payloads_borrow_the_mapping_across_readers() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for p in [&b"one"[..], b"two", b"three"] {
+            DefaultFramer.frame_and_write(&mut file, p).unwrap();
+        }
+        file.flush().unwrap();
+
+        let source = MmapSource::open(file.path()).unwrap();
+        let mut first = source.reader(DefaultDeframer::new());
+        let mut second = source.reader(DefaultDeframer::new());
+        let a = first.read_message().unwrap().unwrap();
+        let b = first.read_message().unwrap().unwrap();
+        // An independent cursor over the same mapping.
+        assert_eq!(second.read_message().unwrap().unwrap(), b"one");
+        assert_eq!((a, b), (&b"one"[..], &b"two"[..]));
+        let range = source.as_bytes().as_ptr_range();
+        assert!(range.contains(&a.as_ptr()) && range.contains(&b.as_ptr()));
+    }
+
+    #[test]
+    fn remap_follows_an_appending_writer() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let mut source = MmapSource::from_file(file.reopen().unwrap()).unwrap();
+        assert!(source.is_empty());
+        assert!(source
+            .reader(DefaultDeframer::new())
+            .read_message()
+            .unwrap()
+            .is_none());
+
+        DefaultFramer.frame_and_write(&mut file, b"first").unwrap();
+        // A writer caught mid-append: the next frame's header is torn.
+        file.write_all(&[6, 0]).unwrap();
+        file.flush().unwrap();
+        assert!(source.remap().unwrap());
+
+        let mut reader = source.reader(DefaultDeframer::new());
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"first");
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+        let resume = reader.position();
+
+        file.write_all(&[0, 0]).unwrap();
+        file.write_all(b"second").unwrap();
+        file.flush().unwrap();
+        assert!(source.remap().unwrap());
+        assert!(!source.remap().unwrap());
+
+        let mut reader = source.reader_at(DefaultDeframer::new(), resume);
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"second");
+        assert!(reader.read_message().unwrap().is_none());
+    }
+}
@@ -134,6 +134,14 @@ impl MemoryPolicy for NoOpPolicy {
 pub trait Clock: Send {
     /// Time elapsed since the clock's origin.
     fn now(&self) -> Duration;
+
+    /// Waits for `duration`. The default blocks the current thread; clocks
+    /// under test control override it to advance their own time instead, so
+    /// polling loops (such as [`TailReader`](crate::TailReader)) run without
+    /// real sleeps.
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
 }
 
 /// The production [`Clock`]: monotonic time from a stored [`Instant`] origin.
@@ -0,0 +1,382 @@
+//! An optional self-describing preamble, and a deframer that configures
+//! itself from it.
+//!
+//! The frame format does not say which checksum it was written with: the
+//! algorithm is agreed out-of-band, and a file read with the wrong deframer
+//! fails with `ChecksumMismatch` or, worse, silently mis-frames. A stream may
+//! therefore open with a 12-byte [`StreamPreamble`]:
+//!
+//! ```text
+//! magic "FSTR" | version u16 | checksum algorithm id u8 | checksum width u8 | max frame len u32
+//! ```
+//!
+//! All integers are little-endian; algorithm ids are
+//! [`Checksum::ALGORITHM_ID`]. Frames follow in the ordinary format for that
+//! checksum. [`AutoDeframer::read_preamble`] consumes the preamble and picks
+//! `DefaultDeframer` or the matching `ChecksumDeframer` itself.
+//!
+//! The preamble is opt-in and written once by the producer; streams without
+//! one are unchanged and must still be read with an explicit deframer.
+
+use crate::checksum::{Checksum, NoChecksum, CUSTOM_ALGORITHM_ID};
+use crate::checksum_kind::{ChecksumKind, ChecksumKindDeframer};
+use crate::error::{Error, Result};
+use crate::framing::{Deframer, SliceDeframer, SliceFrame, DEFAULT_MAX_FRAME_LEN};
+use std::io::{Read, Write};
+
+#[cfg(feature = "crc16")]
+use crate::checksum::Crc16;
+#[cfg(feature = "crc32")]
+use crate::checksum::Crc32;
+#[cfg(feature = "crc32c")]
+use crate::checksum::Crc32c;
+#[cfg(feature = "xxhash")]
+use crate::checksum::XxHash64;
+
+/// Magic bytes opening a stream preamble.
+pub const PREAMBLE_MAGIC: [u8; 4] = *b"FSTR";
+
+/// The preamble version written by this crate.
+pub const PREAMBLE_VERSION: u16 = 1;
+
+/// Size of the preamble; the first frame starts at this offset.
+pub const PREAMBLE_LEN: usize = 12;
+
+/// The self-describing header of a stream. See the
+/// [module documentation](self) for the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamPreamble {
+    /// Preamble format version.
+    pub version: u16,
+    /// [`Checksum::ALGORITHM_ID`] of the frames' checksum.
+    pub algorithm_id: u8,
+    /// Width of the frames' checksum field in bytes.
+    pub checksum_width: u8,
+    /// Largest payload length the producer writes.
+    pub max_frame_len: u32,
+}
+
+impl StreamPreamble {
+    /// The preamble for frames checksummed with `C`, declaring
+    /// [`DEFAULT_MAX_FRAME_LEN`] as the largest payload.
+    pub fn for_checksum<C: Checksum>(_checksum: &C) -> Self {
+        Self {
+            version: PREAMBLE_VERSION,
+            algorithm_id: C::ALGORITHM_ID,
+            checksum_width: C::SIZE as u8,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN as u32,
+        }
+    }
+
+    /// Declares `max` as the largest payload the producer writes. Readers
+    /// configured from the preamble reject longer frames.
+    pub fn with_max_frame_len(mut self, max: u32) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+
+    /// The preamble's wire bytes.
+    pub fn to_bytes(&self) -> [u8; PREAMBLE_LEN] {
+        let mut bytes = [0u8; PREAMBLE_LEN];
+        bytes[..4].copy_from_slice(&PREAMBLE_MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6] = self.algorithm_id;
+        bytes[7] = self.checksum_width;
+        bytes[8..12].copy_from_slice(&self.max_frame_len.to_le_bytes());
+        bytes
+    }
+
+    /// Parses a preamble, rejecting a wrong magic or an unknown version with
+    /// `InvalidFrame`.
+    pub fn from_bytes(bytes: &[u8; PREAMBLE_LEN]) -> Result<Self> {
+        if bytes[..4] != PREAMBLE_MAGIC {
+            return Err(Error::invalid_frame(
+                "stream does not start with a flatstream preamble",
+            ));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != PREAMBLE_VERSION {
+            return Err(Error::invalid_frame_with(
+                "unsupported stream preamble version",
+                Some(version as usize),
+                None,
+                Some(PREAMBLE_VERSION as usize),
+            ));
+        }
+        Ok(Self {
+            version,
+            algorithm_id: bytes[6],
+            checksum_width: bytes[7],
+            max_frame_len: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+        })
+    }
+
+    /// Writes the preamble; call once, before the first frame.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Reads and parses a preamble. A stream shorter than the preamble is
+    /// `UnexpectedEof`.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; PREAMBLE_LEN];
+        reader.read_exact(&mut bytes).map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::unexpected_eof(),
+            _ => e.into(),
+        })?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// A deframer configured from a stream's [`StreamPreamble`].
+///
+/// Dispatches to `DefaultDeframer` or `ChecksumDeframer<Crc16 | Crc32 |
+/// XxHash64 | Crc32c>` according to the preamble's algorithm id, with the preamble's
+/// declared maximum frame length (capped at [`DEFAULT_MAX_FRAME_LEN`] unless
+/// raised explicitly with [`with_max_frame_len`](Self::with_max_frame_len)).
+///
+/// ```rust
+/// use flatstream::{AutoDeframer, DefaultFramer, NoChecksum, Result, StreamPreamble, StreamWriter};
+///
+/// # fn main() -> Result<()> {
+/// let mut stream = Vec::new();
+/// StreamPreamble::for_checksum(&NoChecksum).write_to(&mut stream)?;
+/// let mut writer = StreamWriter::new(&mut stream, DefaultFramer);
+/// writer.write_payload(b"hello")?;
+///
+/// let mut input = &stream[..];
+/// let deframer = AutoDeframer::read_preamble(&mut input)?;
+/// assert_eq!(deframer.algorithm_name(), "none");
+/// let mut reader = flatstream::StreamReader::new(input, deframer);
+/// assert_eq!(reader.read_message()?, Some(&b"hello"[..]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct AutoDeframer {
+    preamble: StreamPreamble,
+    inner: ChecksumKindDeframer,
+    algorithm_name: &'static str,
+}
+
+impl AutoDeframer {
+    /// Configures a deframer for the stream `preamble` describes.
+    ///
+    /// Fails with `InvalidFrame` when the algorithm is custom or unknown,
+    /// when its width does not match the algorithm, or when its feature is
+    /// not compiled in.
+    pub fn from_preamble(preamble: StreamPreamble) -> Result<Self> {
+        let max = (preamble.max_frame_len as usize).min(DEFAULT_MAX_FRAME_LEN);
+        let (kind, algorithm_name) = match preamble.algorithm_id {
+            id if id == NoChecksum::ALGORITHM_ID => {
+                check_width::<NoChecksum>(&preamble)?;
+                (ChecksumKind::None, "none")
+            }
+            #[cfg(feature = "crc16")]
+            id if id == Crc16::ALGORITHM_ID => {
+                check_width::<Crc16>(&preamble)?;
+                (ChecksumKind::Crc16, "CRC-16")
+            }
+            #[cfg(feature = "crc32")]
+            id if id == Crc32::ALGORITHM_ID => {
+                check_width::<Crc32>(&preamble)?;
+                (ChecksumKind::Crc32, "CRC-32")
+            }
+            #[cfg(feature = "xxhash")]
+            id if id == XxHash64::ALGORITHM_ID => {
+                check_width::<XxHash64>(&preamble)?;
+                (ChecksumKind::XxHash64, "XXH3-64")
+            }
+            #[cfg(feature = "crc32c")]
+            id if id == Crc32c::ALGORITHM_ID => {
+                check_width::<Crc32c>(&preamble)?;
+                (ChecksumKind::Crc32c, "CRC-32C")
+            }
+            // Built-in ids whose feature is compiled out.
+            1 => return Err(feature_disabled("CRC-16", "crc16")),
+            2 => return Err(feature_disabled("CRC-32", "crc32")),
+            3 => return Err(feature_disabled("XXH3-64", "xxhash")),
+            4 => return Err(feature_disabled("CRC-32C", "crc32c")),
+            CUSTOM_ALGORITHM_ID => return Err(Error::invalid_frame(
+                "stream uses a custom checksum; read it with a ChecksumDeframer of that algorithm",
+            )),
+            id => {
+                return Err(Error::invalid_frame(format!(
+                    "unknown checksum algorithm id {id} in stream preamble"
+                )))
+            }
+        };
+        Ok(Self {
+            preamble,
+            inner: ChecksumKindDeframer::new(kind).with_max_frame_len(max),
+            algorithm_name,
+        })
+    }
+
+    /// Reads the preamble from `reader` and configures a deframer for the
+    /// frames that follow it. `reader` is left at the first frame.
+    pub fn read_preamble<R: Read>(reader: &mut R) -> Result<Self> {
+        Self::from_preamble(StreamPreamble::read_from(reader)?)
+    }
+
+    /// Overrides the maximum accepted payload length taken from the
+    /// preamble (enforced before allocation).
+    pub fn with_max_frame_len(mut self, max: usize) -> Self {
+        self.inner = self.inner.with_max_frame_len(max);
+        self
+    }
+
+    /// The preamble this deframer was configured from.
+    pub fn preamble(&self) -> &StreamPreamble {
+        &self.preamble
+    }
+
+    /// The detected checksum algorithm.
+    pub fn checksum_kind(&self) -> ChecksumKind {
+        self.inner.kind()
+    }
+
+    /// Human-readable name of the detected checksum algorithm.
+    pub fn algorithm_name(&self) -> &'static str {
+        self.algorithm_name
+    }
+}
+
+impl Deframer for AutoDeframer {
+    #[inline]
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        self.inner.read_and_deframe(reader, buffer)
+    }
+
+    #[inline]
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        self.inner.read_after_length(reader, buffer, payload_len)
+    }
+}
+
+impl SliceDeframer for AutoDeframer {
+    #[inline]
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        self.inner.deframe_slice(input)
+    }
+}
+
+fn check_width<C: Checksum>(preamble: &StreamPreamble) -> Result<()> {
+    if preamble.checksum_width as usize != C::SIZE {
+        return Err(Error::invalid_frame_with(
+            "stream preamble checksum width does not match its algorithm",
+            Some(preamble.checksum_width as usize),
+            None,
+            Some(C::SIZE),
+        ));
+    }
+    Ok(())
+}
+
+#[cold]
+fn feature_disabled(algorithm: &str, feature: &str) -> Error {
+    Error::invalid_frame(format!(
+        "stream uses {algorithm}, but the `{feature}` feature is not enabled"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultFramer, Framer};
+    use crate::reader::StreamReader;
+
+    fn invalid(result: Result<AutoDeframer>) -> bool {
+        matches!(
+            result.map(|_| ()).unwrap_err().kind(),
+            ErrorKind::InvalidFrame { .. }
+        )
+    }
+
+    #[test]
+    fn preamble_round_trips_and_configures_the_plain_deframer() {
+        let preamble = StreamPreamble::for_checksum(&NoChecksum).with_max_frame_len(16);
+        let bytes = preamble.to_bytes();
+        assert_eq!(&bytes[..4], b"FSTR");
+        assert_eq!(StreamPreamble::from_bytes(&bytes).unwrap(), preamble);
+
+        let mut stream = bytes.to_vec();
+        DefaultFramer
+            .frame_and_write(&mut stream, b"short")
+            .unwrap();
+        DefaultFramer
+            .frame_and_write(&mut stream, &[0u8; 17])
+            .unwrap();
+        let mut input = &stream[..];
+        let deframer = AutoDeframer::read_preamble(&mut input).unwrap();
+        let mut reader = StreamReader::new(input, deframer);
+        assert_eq!(reader.read_message().unwrap(), Some(&b"short"[..]));
+        // The declared maximum is enforced.
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[test]
+    fn rejects_bad_magic_version_width_and_custom_algorithms() {
+        let good = StreamPreamble::for_checksum(&NoChecksum).to_bytes();
+
+        let mut bad_magic = good;
+        bad_magic[0] = b'X';
+        assert!(invalid(AutoDeframer::read_preamble(&mut &bad_magic[..])));
+
+        let mut bad_version = good;
+        bad_version[4] = 9;
+        assert!(invalid(AutoDeframer::read_preamble(&mut &bad_version[..])));
+
+        let mut bad_width = good;
+        bad_width[7] = 4;
+        assert!(invalid(AutoDeframer::read_preamble(&mut &bad_width[..])));
+
+        let mut custom = good;
+        custom[6] = CUSTOM_ALGORITHM_ID;
+        assert!(invalid(AutoDeframer::read_preamble(&mut &custom[..])));
+
+        let err = AutoDeframer::read_preamble(&mut &good[..5])
+            .map(|_| ())
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+    }
+
+    #[cfg(all(feature = "xxhash", feature = "crc32", feature = "crc16"))]
+    #[test]
+    fn detects_every_built_in_checksum() {
+        use crate::framing::ChecksumFramer;
+
+        fn round_trip<C: Checksum + Copy>(checksum: C, name: &str) {
+            let mut stream = Vec::new();
+            StreamPreamble::for_checksum(&checksum)
+                .write_to(&mut stream)
+                .unwrap();
+            ChecksumFramer::new(checksum)
+                .frame_and_write(&mut stream, b"payload")
+                .unwrap();
+            let mut input = &stream[..];
+            let deframer = AutoDeframer::read_preamble(&mut input).unwrap();
+            assert_eq!(deframer.algorithm_name(), name);
+            let frame = deframer.deframe_slice(input).unwrap().unwrap();
+            assert_eq!(&input[frame.payload], b"payload");
+            assert_eq!(frame.len, input.len());
+        }
+        round_trip(Crc16::new(), "CRC-16");
+        round_trip(Crc32::new(), "CRC-32");
+        round_trip(XxHash64::new(), "XXH3-64");
+        #[cfg(feature = "crc32c")]
+        round_trip(Crc32c::new(), "CRC-32C");
+    }
+}
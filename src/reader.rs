@@ -1,10 +1,11 @@
 //! A generic, composable reader for `flatstream`.
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::framing::Deframer;
+use crate::index::FrameIndex;
 use crate::policy::{MemoryPolicy, ReclamationInfo};
 use crate::traits::StreamDeserialize;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::marker::PhantomData;
 
 /// A reader for streaming messages from a `flatstream`.
@@ -79,6 +80,13 @@ use std::marker::PhantomData;
 /// to the policy's baseline capacity (`MemoryPolicy::baseline_capacity`,
 /// default 16 KiB). The shrink is deferred to the start of the next read, so a
 /// payload already returned is never invalidated.
+///
+/// ## Random Access
+///
+/// Over a `Read + Seek` source, installing a [`FrameIndex`] with
+/// [`with_index`](Self::with_index) enables
+/// [`seek_to_frame`](Self::seek_to_frame) and
+/// [`frame_count`](Self::frame_count) without scanning the stream.
 pub struct StreamReader<R: Read, D: Deframer> {
     reader: R,
     deframer: D,
@@ -88,14 +96,45 @@ pub struct StreamReader<R: Read, D: Deframer> {
     // Optional capacity-aware policy; one predictable branch per read when absent.
     policy: Option<PolicySlot>,
     pending_shrink: bool,
+    index: Option<FrameIndex>,
 }
 
 /// Installed-policy state: the policy plus its baseline (cached from
 /// `MemoryPolicy::baseline_capacity()` at installation so the steady-state gate
 /// is a plain integer compare).
-struct PolicySlot {
+pub(crate) struct PolicySlot {
     policy: Box<dyn MemoryPolicy>,
-    baseline_capacity: usize,
+    pub(crate) baseline_capacity: usize,
+}
+
+impl PolicySlot {
+    pub(crate) fn new<P: MemoryPolicy + 'static>(policy: P) -> Self {
+        Self {
+            baseline_capacity: policy.baseline_capacity(),
+            policy: Box::new(policy),
+        }
+    }
+
+    /// Consults the policy for a read buffer of `capacity` bytes that just
+    /// yielded `last_message_size` bytes, notifying it of the reclaim when it
+    /// fires. The caller performs the shrink (to `baseline_capacity`).
+    pub(crate) fn should_shrink(&mut self, last_message_size: usize, capacity: usize) -> bool {
+        // At or below the policy's baseline there is nothing to reclaim —
+        // skip the policy so its state cannot churn.
+        if capacity <= self.baseline_capacity {
+            return false;
+        }
+        let Some(reason) = self.policy.should_reset(last_message_size, capacity) else {
+            return false;
+        };
+        self.policy.on_reclaim(&ReclamationInfo {
+            reason,
+            last_message_size,
+            capacity_before: capacity,
+            capacity_after: self.baseline_capacity,
+        });
+        true
+    }
 }
 
 impl<R: Read, D: Deframer> StreamReader<R, D> {
@@ -107,6 +146,7 @@ impl<R: Read, D: Deframer> StreamReader<R, D> {
             buffer: Vec::new(),
             policy: None,
             pending_shrink: false,
+            index: None,
         }
     }
 
@@ -118,6 +158,7 @@ impl<R: Read, D: Deframer> StreamReader<R, D> {
             buffer: Vec::with_capacity(capacity),
             policy: None,
             pending_shrink: false,
+            index: None,
         }
     }
 
@@ -132,10 +173,7 @@ impl<R: Read, D: Deframer> StreamReader<R, D> {
     /// capacity exceeds that baseline — at or below it there is nothing to
     /// reclaim.
     pub fn with_memory_policy<P: MemoryPolicy + 'static>(mut self, policy: P) -> Self {
-        self.policy = Some(PolicySlot {
-            baseline_capacity: policy.baseline_capacity(),
-            policy: Box::new(policy),
-        });
+        self.policy = Some(PolicySlot::new(policy));
         self
     }
 
@@ -186,21 +224,10 @@ impl<R: Read, D: Deframer> StreamReader<R, D> {
         let Some(slot) = self.policy.as_mut() else {
             return;
         };
-        let capacity = self.buffer.capacity();
-        // At or below the policy's baseline there is nothing to reclaim —
-        // skip the policy so its state cannot churn.
-        if capacity > slot.baseline_capacity {
-            if let Some(reason) = slot.policy.should_reset(last_message_size, capacity) {
-                // Schedule the shrink for the start of the *next* read so the
-                // payload about to be returned is never invalidated.
-                self.pending_shrink = true;
-                slot.policy.on_reclaim(&ReclamationInfo {
-                    reason,
-                    last_message_size,
-                    capacity_before: capacity,
-                    capacity_after: slot.baseline_capacity,
-                });
-            }
+        // Schedule the shrink for the start of the *next* read so the payload
+        // about to be returned is never invalidated.
+        if slot.should_shrink(last_message_size, self.buffer.capacity()) {
+            self.pending_shrink = true;
         }
     }
 
@@ -376,6 +403,54 @@ impl<R: Read, D: Deframer> StreamReader<R, D> {
     }
 }
 
+impl<R: Read + Seek, D: Deframer> StreamReader<R, D> {
+    /// Installs a frame index for this stream, typically loaded from its
+    /// sidecar with [`FrameIndex::load`]. Index offsets are absolute
+    /// positions in the source.
+    pub fn with_index(mut self, index: FrameIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Returns the installed frame index, if any.
+    pub fn index(&self) -> Option<&FrameIndex> {
+        self.index.as_ref()
+    }
+
+    /// Number of frames in the installed index, or `None` without one.
+    pub fn frame_count(&self) -> Option<u64> {
+        self.index.as_ref().map(FrameIndex::frame_count)
+    }
+
+    /// Positions the reader so the next read returns frame `n` (zero-based).
+    /// Seeking to `n == frame_count()` positions at the end of the indexed
+    /// frames.
+    ///
+    /// Returns `ErrorKind::InvalidFrame` when no index is installed or `n` is
+    /// beyond it. The frame read after the seek goes through the deframer as
+    /// usual, so a stale index surfaces as a checksum or framing error rather
+    /// than a silently wrong payload wherever the format can detect it.
+    pub fn seek_to_frame(&mut self, n: u64) -> Result<()> {
+        let Some(index) = self.index.as_ref() else {
+            return Err(Error::invalid_frame("no frame index installed"));
+        };
+        let offset = match index.get(n) {
+            Some(entry) => entry.offset,
+            None if n == index.frame_count() => index.end_offset(),
+            None => {
+                return Err(Error::invalid_frame_with(
+                    "frame number beyond the index",
+                    usize::try_from(n).ok(),
+                    None,
+                    usize::try_from(index.frame_count()).ok(),
+                ))
+            }
+        };
+        self.reader.seek(SeekFrom::Start(offset))?;
+        Ok(())
+    }
+}
+
 /// An iterator-like object for manual message processing.
 ///
 /// This struct provides the "expert path" for users who need more control over
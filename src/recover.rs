@@ -22,15 +22,15 @@
 //! All of those return `Err` with the stop reason intact; the caller — not
 //! this function — decides what a corrupted or misread file deserves. Run
 //! recovery with the deframer that matches the wire format (plain, matching
-//! checksum, no validators). A prefix-salvage API that truncates *through*
-//! corruption would be a separate, explicitly destructive operation; it does
-//! not exist today by design.
+//! checksum, no validators). Recovering frames *past* corruption is a
+//! separate, explicitly destructive operation that never touches the input:
+//! see [`salvage`](mod@crate::salvage).
 //!
 //! Scope: this contract is designed for **append-only journals whose
 //! expected failure mode is a crash during the final write**. It is not a
 //! general repair tool for arbitrarily damaged files, and it must not be
 //! pointed at sealed container files (a valid container footer is handled by
-//! the container layer, never by scanning).
+//! [`ContainerReader`](crate::ContainerReader), never by scanning).
 
 use crate::error::{ErrorKind, Result};
 use crate::framing::Deframer;
@@ -64,9 +64,15 @@ pub struct RecoveryReport {
 /// deframer must consume exactly one frame and never read ahead; the built-in
 /// deframers and their adapters satisfy that contract. The count after a
 /// successful frame is therefore precisely that frame's end offset.
-struct CountingReader<R> {
+pub(crate) struct CountingReader<R> {
     inner: R,
-    count: u64,
+    pub(crate) count: u64,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
 }
 
 impl<R: Read> Read for CountingReader<R> {
@@ -104,10 +110,7 @@ impl<R: Read> Read for CountingReader<R> {
 /// deframers and their adapters satisfy this; a custom `Deframer` that
 /// buffers or reads speculatively would misreport `last_good_offset`.
 pub fn recover<R: Read, D: Deframer>(reader: R, deframer: D) -> Result<RecoveryReport> {
-    let mut reader = CountingReader {
-        inner: reader,
-        count: 0,
-    };
+    let mut reader = CountingReader::new(reader);
     let mut buffer = Vec::new();
     let mut report = RecoveryReport {
         frames: 0,
@@ -150,6 +153,10 @@ pub fn recover<R: Read, D: Deframer>(reader: R, deframer: D) -> Result<RecoveryR
 /// If scanning or the final seek returns `Err`, the cursor position is
 /// unspecified and the caller must not append through that handle.
 ///
+/// For journal files on disk, [`Journal::open`](crate::Journal::open)
+/// performs this whole sequence — scan, truncate only on a torn tail,
+/// resume — and should be preferred over repeating it by hand.
+///
 /// ```
 /// use flatstream::{recover_file, DefaultDeframer, DefaultFramer, Framer, RecoveryEnd, Result};
 /// use std::io::{Cursor, Seek};
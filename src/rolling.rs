@@ -0,0 +1,542 @@
+//! Segment rotation: split one long-running journal into numbered segment
+//! files.
+//!
+//! A [`RollingStreamWriter`] writes frames exactly like a
+//! [`StreamWriter`] but closes the current segment and starts the next one
+//! once a [`RollPolicy`] threshold is crossed — bytes written, frames
+//! written, or segment age measured on a [`Clock`]. Segment files are named
+//! by a [`SegmentNaming`] scheme from monotonically increasing ids, and an
+//! optional hook runs for every segment as it is closed, so it can be
+//! shipped, indexed or sealed.
+//!
+//! Every segment is a complete, independent stream: frames never straddle a
+//! roll, and each segment starts at a frame boundary.
+
+use crate::error::Result;
+use crate::framing::Framer;
+use crate::policy::{Clock, MonotonicClock};
+use crate::traits::StreamSerialize;
+use crate::writer::StreamWriter;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Maps segment ids to file paths and back.
+///
+/// Ids must map to distinct paths inside [`directory`](Self::directory), and
+/// [`segment_id`](Self::segment_id) must invert
+/// [`segment_path`](Self::segment_path) and reject every other file name, so
+/// that [`list_segments`](Self::list_segments) sees exactly this scheme's
+/// segments.
+pub trait SegmentNaming {
+    /// The directory holding the segments.
+    fn directory(&self) -> &Path;
+
+    /// The path of segment `id`.
+    fn segment_path(&self, id: u64) -> PathBuf;
+
+    /// The id of the segment at `path`, or `None` if `path` is not one of
+    /// this scheme's segments.
+    fn segment_id(&self, path: &Path) -> Option<u64>;
+
+    /// Lists the segments present in the directory, ordered by id.
+    fn list_segments(&self) -> Result<Vec<(u64, PathBuf)>> {
+        let mut segments = Vec::new();
+        for entry in std::fs::read_dir(self.directory())? {
+            let path = entry?.path();
+            if let Some(id) = self.segment_id(&path) {
+                segments.push((id, path));
+            }
+        }
+        segments.sort_unstable_by_key(|(id, _)| *id);
+        Ok(segments)
+    }
+}
+
+/// The default naming scheme: `<dir>/<prefix>-<id>.<extension>`, with the id
+/// zero-padded to 20 digits so that lexical and numeric order agree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberedSegments {
+    directory: PathBuf,
+    prefix: String,
+    extension: String,
+}
+
+impl NumberedSegments {
+    /// Segments named `<prefix>-<id>.bin` in `directory`.
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(directory: P, prefix: S) -> Self {
+        Self {
+            directory: directory.into(),
+            prefix: prefix.into(),
+            extension: "bin".to_string(),
+        }
+    }
+
+    /// Replaces the file extension (without the dot; default `bin`).
+    pub fn with_extension<S: Into<String>>(mut self, extension: S) -> Self {
+        self.extension = extension.into();
+        self
+    }
+}
+
+impl SegmentNaming for NumberedSegments {
+    fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn segment_path(&self, id: u64) -> PathBuf {
+        self.directory
+            .join(format!("{}-{:020}.{}", self.prefix, id, self.extension))
+    }
+
+    fn segment_id(&self, path: &Path) -> Option<u64> {
+        let name = path.file_name()?.to_str()?;
+        let digits = name
+            .strip_prefix(self.prefix.as_str())?
+            .strip_prefix('-')?
+            .strip_suffix(self.extension.as_str())?
+            .strip_suffix('.')?;
+        if digits.len() != 20 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+}
+
+/// When to close the current segment. Unset thresholds never trigger; with
+/// none set, everything goes to one segment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RollPolicy {
+    max_bytes: Option<u64>,
+    max_frames: Option<u64>,
+    max_age: Option<Duration>,
+}
+
+impl RollPolicy {
+    /// A policy with no thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Roll once a segment holds at least `bytes` bytes (frame overhead
+    /// included). The frame that crosses the threshold stays in the segment.
+    pub fn with_max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Roll once a segment holds `frames` frames.
+    pub fn with_max_frames(mut self, frames: u64) -> Self {
+        self.max_frames = Some(frames);
+        self
+    }
+
+    /// Roll once a segment was opened `age` ago.
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+}
+
+/// A closed segment, as handed to the roll hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentInfo {
+    /// The segment's id.
+    pub id: u64,
+    /// The segment's file.
+    pub path: PathBuf,
+    /// Frames written to the segment.
+    pub frames: u64,
+    /// Bytes written to the segment.
+    pub bytes: u64,
+}
+
+/// The sink behind a rolling writer's `StreamWriter`: the current segment
+/// file, swapped out on every roll.
+struct SegmentSink {
+    file: Option<BufWriter<File>>,
+    bytes: u64,
+}
+
+impl Write for SegmentSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let file = self
+            .file
+            .as_mut()
+            .expect("rolling writer opens a segment before writing");
+        let n = file.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+struct OpenSegment {
+    id: u64,
+    path: PathBuf,
+    frames: u64,
+    opened_at: Duration,
+}
+
+type RollHook<'a> = Box<dyn FnMut(&SegmentInfo) -> Result<()> + Send + 'a>;
+
+/// A journal writer that rolls over to a new segment file by size, frame
+/// count or age.
+///
+/// Segments are opened lazily by the first write after a roll, so no empty
+/// segment is ever created. Thresholds are checked before each write, which
+/// means an age-based roll happens at the next write after the deadline;
+/// call [`roll_if_due`](Self::roll_if_due) from a timer to close idle
+/// segments on time.
+///
+/// ```rust
+/// use flatstream::{DefaultFramer, NumberedSegments, Result, RollPolicy, RollingStreamWriter};
+///
+/// # fn main() -> Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// let naming = NumberedSegments::new(dir.path(), "telemetry");
+/// let policy = RollPolicy::new().with_max_bytes(64 << 20);
+/// let mut writer = RollingStreamWriter::new(naming, DefaultFramer, policy)?
+///     .on_roll(|segment| {
+///         println!("closed {} ({} frames)", segment.path.display(), segment.frames);
+///         Ok(())
+///     });
+/// writer.write(&"event")?;
+/// writer.finish()?; // closes the last segment and runs the hook for it
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Dropping the writer flushes the current segment but does not run the
+/// hook for it; call [`finish`](Self::finish) for an orderly shutdown.
+pub struct RollingStreamWriter<'a, N: SegmentNaming, F: Framer, C: Clock = MonotonicClock> {
+    writer: StreamWriter<'a, SegmentSink, F>,
+    naming: N,
+    policy: RollPolicy,
+    clock: C,
+    current: Option<OpenSegment>,
+    next_id: u64,
+    on_roll: Option<RollHook<'a>>,
+}
+
+impl<'a, N: SegmentNaming, F: Framer> RollingStreamWriter<'a, N, F> {
+    /// Creates a writer for the segments named by `naming`.
+    ///
+    /// The directory is created if missing. Ids continue after the highest
+    /// existing segment, so a restarted process never overwrites one.
+    pub fn new(naming: N, framer: F, policy: RollPolicy) -> Result<Self> {
+        std::fs::create_dir_all(naming.directory())?;
+        let next_id = naming.list_segments()?.last().map_or(0, |(id, _)| id + 1);
+        let sink = SegmentSink {
+            file: None,
+            bytes: 0,
+        };
+        Ok(Self {
+            writer: StreamWriter::new(sink, framer),
+            naming,
+            policy,
+            clock: MonotonicClock::new(),
+            current: None,
+            next_id,
+            on_roll: None,
+        })
+    }
+}
+
+impl<'a, N: SegmentNaming, F: Framer, C: Clock> RollingStreamWriter<'a, N, F, C> {
+    /// Replaces the clock segment ages are measured on.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> RollingStreamWriter<'a, N, F, C2> {
+        RollingStreamWriter {
+            writer: self.writer,
+            naming: self.naming,
+            policy: self.policy,
+            clock,
+            current: self.current,
+            next_id: self.next_id,
+            on_roll: self.on_roll,
+        }
+    }
+
+    /// Runs `hook` for every segment once it is closed and flushed. An error
+    /// from the hook is returned from the write (or `roll`/`finish`) that
+    /// closed the segment; the segment stays closed.
+    pub fn on_roll<H>(mut self, hook: H) -> Self
+    where
+        H: FnMut(&SegmentInfo) -> Result<()> + Send + 'a,
+    {
+        self.on_roll = Some(Box::new(hook));
+        self
+    }
+
+    /// Serializes and writes one item (see [`StreamWriter::write`]).
+    pub fn write<T: StreamSerialize>(&mut self, item: &T) -> Result<()> {
+        self.prepare()?;
+        self.writer.write(item)?;
+        self.wrote();
+        Ok(())
+    }
+
+    /// Writes a finished builder's payload (see
+    /// [`StreamWriter::write_finished`]).
+    pub fn write_finished<A: flatbuffers::Allocator>(
+        &mut self,
+        builder: &mut flatbuffers::FlatBufferBuilder<A>,
+    ) -> Result<()> {
+        self.prepare()?;
+        self.writer.write_finished(builder)?;
+        self.wrote();
+        Ok(())
+    }
+
+    /// Writes already-serialized payload bytes (see
+    /// [`StreamWriter::write_payload`]).
+    pub fn write_payload(&mut self, payload: &[u8]) -> Result<()> {
+        self.prepare()?;
+        self.writer.write_payload(payload)?;
+        self.wrote();
+        Ok(())
+    }
+
+    /// Flushes the current segment.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+
+    /// Closes the current segment if a threshold has been crossed. Returns
+    /// whether it rolled.
+    pub fn roll_if_due(&mut self) -> Result<bool> {
+        if self.is_due() {
+            self.roll()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Closes the current segment now, if one is open; the next write starts
+    /// a new one.
+    pub fn roll(&mut self) -> Result<()> {
+        let Some(segment) = self.current.take() else {
+            return Ok(());
+        };
+        self.writer.flush()?;
+        let sink = self.writer.get_mut();
+        let bytes = sink.bytes;
+        sink.file = None;
+        let info = SegmentInfo {
+            id: segment.id,
+            path: segment.path,
+            frames: segment.frames,
+            bytes,
+        };
+        match self.on_roll.as_mut() {
+            Some(hook) => hook(&info),
+            None => Ok(()),
+        }
+    }
+
+    /// Closes the last segment, running the hook for it.
+    pub fn finish(mut self) -> Result<()> {
+        self.roll()
+    }
+
+    /// The id of the open segment, if any.
+    pub fn segment_id(&self) -> Option<u64> {
+        self.current.as_ref().map(|segment| segment.id)
+    }
+
+    /// The id the next segment will get.
+    pub fn next_segment_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Returns the naming scheme.
+    pub fn naming(&self) -> &N {
+        &self.naming
+    }
+
+    fn is_due(&self) -> bool {
+        let Some(segment) = &self.current else {
+            return false;
+        };
+        let policy = &self.policy;
+        policy.max_frames.is_some_and(|max| segment.frames >= max)
+            || policy
+                .max_bytes
+                .is_some_and(|max| self.writer.get_ref().bytes >= max)
+            || policy
+                .max_age
+                .is_some_and(|max| self.clock.now().saturating_sub(segment.opened_at) >= max)
+    }
+
+    /// Rolls if due, then makes sure a segment is open.
+    fn prepare(&mut self) -> Result<()> {
+        self.roll_if_due()?;
+        if self.current.is_none() {
+            let id = self.next_id;
+            let path = self.naming.segment_path(id);
+            let file = File::options().write(true).create_new(true).open(&path)?;
+            let sink = self.writer.get_mut();
+            sink.file = Some(BufWriter::new(file));
+            sink.bytes = 0;
+            self.next_id += 1;
+            self.current = Some(OpenSegment {
+                id,
+                path,
+                frames: 0,
+                opened_at: self.clock.now(),
+            });
+        }
+        Ok(())
+    }
+
+    fn wrote(&mut self) {
+        if let Some(segment) = self.current.as_mut() {
+            segment.frames += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{DefaultDeframer, DefaultFramer};
+    use crate::reader::StreamReader;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct TestClock(Arc<AtomicU64>); // elapsed milliseconds
+
+    impl TestClock {
+        fn advance_ms(&self, ms: u64) {
+            self.0.fetch_add(ms, Ordering::Relaxed);
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    fn payloads(path: &Path) -> Vec<Vec<u8>> {
+        let mut reader = StreamReader::new(File::open(path).unwrap(), DefaultDeframer::new());
+        let mut out = Vec::new();
+        reader
+            .process_all(|p| {
+                out.push(p.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn rolls_by_frame_count_and_runs_the_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let seen = closed.clone();
+        let mut writer = RollingStreamWriter::new(
+            naming.clone(),
+            DefaultFramer,
+            RollPolicy::new().with_max_frames(2),
+        )
+        .unwrap()
+        .on_roll(move |segment| {
+            seen.lock().unwrap().push(segment.clone());
+            Ok(())
+        });
+        for i in 0..5u8 {
+            writer.write_payload(&[i; 3]).unwrap();
+        }
+        assert_eq!(writer.segment_id(), Some(2));
+        writer.finish().unwrap();
+
+        let closed = closed.lock().unwrap();
+        assert_eq!(closed.iter().map(|s| s.id).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(
+            closed.iter().map(|s| s.frames).collect::<Vec<_>>(),
+            [2, 2, 1]
+        );
+        assert_eq!(closed[0].bytes, 14);
+        assert_eq!(payloads(&closed[1].path), [vec![2; 3], vec![3; 3]]);
+        assert_eq!(naming.list_segments().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn rolls_by_bytes_after_the_crossing_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        let mut writer = RollingStreamWriter::new(
+            naming.clone(),
+            DefaultFramer,
+            RollPolicy::new().with_max_bytes(10),
+        )
+        .unwrap();
+        writer.write_payload(b"12345678").unwrap(); // 12 bytes: over, but kept
+        writer.write_payload(b"x").unwrap();
+        writer.finish().unwrap();
+
+        let segments = naming.list_segments().unwrap();
+        assert_eq!(payloads(&segments[0].1), [b"12345678".to_vec()]);
+        assert_eq!(payloads(&segments[1].1), [b"x".to_vec()]);
+    }
+
+    #[test]
+    fn rolls_by_age_on_the_injected_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        let clock = TestClock::default();
+        let mut writer = RollingStreamWriter::new(
+            naming.clone(),
+            DefaultFramer,
+            RollPolicy::new().with_max_age(Duration::from_secs(60)),
+        )
+        .unwrap()
+        .with_clock(clock.clone());
+        writer.write_payload(b"a").unwrap();
+        clock.advance_ms(59_999);
+        writer.write_payload(b"b").unwrap();
+        assert!(!writer.roll_if_due().unwrap());
+        clock.advance_ms(1);
+        assert!(writer.roll_if_due().unwrap());
+        assert_eq!(writer.segment_id(), None);
+        writer.write_payload(b"c").unwrap();
+        writer.finish().unwrap();
+
+        let segments = naming.list_segments().unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(payloads(&segments[1].1), [b"c".to_vec()]);
+    }
+
+    #[test]
+    fn ids_continue_after_existing_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        std::fs::write(naming.segment_path(7), b"").unwrap();
+        std::fs::write(dir.path().join("journal-7.bin"), b"").unwrap();
+        std::fs::write(dir.path().join("other-00000000000000000009.bin"), b"").unwrap();
+
+        let mut writer =
+            RollingStreamWriter::new(naming.clone(), DefaultFramer, RollPolicy::new()).unwrap();
+        assert_eq!(writer.next_segment_id(), 8);
+        writer.write_payload(b"p").unwrap();
+        writer.finish().unwrap();
+
+        let ids: Vec<u64> = naming
+            .list_segments()
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, [7, 8]);
+    }
+}
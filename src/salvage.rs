@@ -0,0 +1,263 @@
+//! Salvage: copy every verifiable frame out of a damaged stream, skipping
+//! past corruption.
+//!
+//! This is the explicitly **destructive** counterpart to
+//! [`recover`](fn@crate::recover). Recovery stops at the first frame that is
+//! not a torn tail and refuses to decide what corruption deserves. Salvage
+//! decides: it drops every byte it cannot verify and keeps going, so the
+//! output holds fewer frames than were written, and nothing in the output
+//! says where the holes were — only the [`SalvageReport`] does. Use it for
+//! forensic copies after a disk error, never as routine journal repair.
+//!
+//! At a frame that fails its length bound or checksum (or cannot complete
+//! because a corrupted length points past the end), salvage scans forward
+//! one byte at a time for the next offset where a whole frame verifies —
+//! length within the deframer's bound, payload present, checksum matching —
+//! and resumes there. Resynchronizing is only trustworthy because the
+//! checksum vouches for each candidate, so salvage requires a checksummed
+//! format; the wider the checksum, the less likely a false resync on random
+//! bytes. Tighten the deframer's `with_max_frame_len` to the largest frame
+//! the writer produces: it rejects most candidates before any checksum is
+//! computed.
+//!
+//! An all-zero header verifies as an empty frame under CRC-32 and CRC-16, so
+//! zero-filled damage — the most common kind — would otherwise come out as a
+//! run of bogus empty frames. Salvage therefore never resynchronizes on an
+//! all-zero frame and drops runs of them; a lone empty frame in an intact
+//! stretch is kept.
+//!
+//! The input is never modified. Surviving frames are copied verbatim, so the
+//! output is a clean stream in the input's own format.
+
+use crate::checksum::Checksum;
+use crate::error::{Error, Result};
+use crate::framing::{ChecksumDeframer, SliceDeframer, SliceFrame};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Outcome of [`salvage`] or [`salvage_file`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SalvageReport {
+    /// Frames copied to the output.
+    pub frames: u64,
+    /// Bytes copied to the output.
+    pub bytes_written: u64,
+    /// Input byte ranges that were dropped, in order. A range reaching the
+    /// end of the input covers a torn tail or trailing garbage.
+    pub skipped: Vec<Range<u64>>,
+}
+
+impl SalvageReport {
+    /// Whether nothing was skipped: the input was an intact stream.
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty()
+    }
+
+    /// Total number of input bytes dropped.
+    pub fn skipped_bytes(&self) -> u64 {
+        self.skipped.iter().map(|r| r.end - r.start).sum()
+    }
+}
+
+/// Copies every verifiable frame of `input` to `output`, resynchronizing
+/// past corruption. See the [module documentation](self).
+///
+/// Fails with `InvalidFrame` for a zero-width checksum such as `NoChecksum`,
+/// which cannot verify a resynchronization candidate.
+pub fn salvage<C: Checksum, W: Write>(
+    input: &[u8],
+    deframer: ChecksumDeframer<C>,
+    mut output: W,
+) -> Result<SalvageReport> {
+    if C::SIZE == 0 {
+        return Err(Error::invalid_frame(
+            "salvage needs a checksummed format to verify resynchronization",
+        ));
+    }
+    let mut report = SalvageReport::default();
+    let mut pos = 0;
+    while pos < input.len() {
+        let (start, frame) = match next_frame(&deframer, input, pos) {
+            Some(found) => found,
+            None => {
+                report.skipped.push(pos as u64..input.len() as u64);
+                break;
+            }
+        };
+        if start > pos {
+            report.skipped.push(pos as u64..start as u64);
+        }
+        let end = start + frame.len;
+        output.write_all(&input[start..end])?;
+        report.frames += 1;
+        report.bytes_written += frame.len as u64;
+        pos = end;
+    }
+    output.flush()?;
+    Ok(report)
+}
+
+/// Salvages the file at `input` into a new file at `output`, which must not
+/// exist yet, and syncs it. The input is read into memory in full.
+pub fn salvage_file<C, P, Q>(
+    input: P,
+    output: Q,
+    deframer: ChecksumDeframer<C>,
+) -> Result<SalvageReport>
+where
+    C: Checksum,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let bytes = std::fs::read(input)?;
+    let file = File::options().write(true).create_new(true).open(output)?;
+    let mut writer = BufWriter::new(file);
+    let report = salvage(&bytes, deframer, &mut writer)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(report)
+}
+
+/// The first offset at or after `pos` where a whole frame verifies.
+fn next_frame<D: SliceDeframer>(
+    deframer: &D,
+    input: &[u8],
+    pos: usize,
+) -> Option<(usize, SliceFrame)> {
+    (pos..input.len()).find_map(|start| match deframer.deframe_slice(&input[start..]) {
+        Ok(Some(frame)) if !is_zero_fill(input, start, frame.len, pos) => Some((start, frame)),
+        _ => None,
+    })
+}
+
+/// Whether a verified candidate is really zero fill. CRC-32 and CRC-16 of an
+/// empty payload are 0, so every all-zero header "verifies" as an empty
+/// frame. One only counts where the stream is already in sync and it is not
+/// followed by another: never as a resynchronization point, and never as
+/// part of a run.
+fn is_zero_fill(input: &[u8], start: usize, len: usize, pos: usize) -> bool {
+    let zeros = |at: usize| {
+        input
+            .get(at..at + len)
+            .is_some_and(|bytes| bytes.iter().all(|&b| b == 0))
+    };
+    zeros(start) && (start > pos || zeros(start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::NoChecksum;
+    use crate::error::ErrorKind;
+
+    #[cfg(feature = "crc32")]
+    use crate::{framing::ChecksumFramer, framing::Framer, Crc32};
+
+    #[cfg(feature = "crc32")]
+    fn wire(payloads: &[&[u8]]) -> (Vec<u8>, Vec<usize>) {
+        let framer = ChecksumFramer::new(Crc32::new());
+        let mut out = Vec::new();
+        let mut starts = Vec::new();
+        for p in payloads {
+            starts.push(out.len());
+            framer.frame_and_write(&mut out, p).unwrap();
+        }
+        (out, starts)
+    }
+
+    #[test]
+    fn zero_width_checksums_are_rejected() {
+        let err = salvage(b"", ChecksumDeframer::new(NoChecksum), Vec::new()).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn intact_stream_is_copied_verbatim() {
+        let (bytes, _) = wire(&[b"one", b"two", b""]);
+        let mut out = Vec::new();
+        let report = salvage(&bytes, ChecksumDeframer::new(Crc32::new()), &mut out).unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.frames, 3);
+        assert_eq!(out, bytes);
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn skips_a_corrupt_payload_and_a_corrupt_length() {
+        let (mut bytes, starts) = wire(&[b"keep a", b"bit rot", b"keep b", b"bad len", b"keep c"]);
+        bytes[starts[1] + 9] ^= 0x40; // payload byte: checksum mismatch
+        bytes[starts[3] + 3] = 0x7F; // length header: points far past the end
+
+        let deframer = ChecksumDeframer::new(Crc32::new()).with_max_frame_len(1024);
+        let mut out = Vec::new();
+        let report = salvage(&bytes, deframer, &mut out).unwrap();
+        assert_eq!(report.frames, 3);
+        assert_eq!(
+            report.skipped,
+            [
+                starts[1] as u64..starts[2] as u64,
+                starts[3] as u64..starts[4] as u64
+            ]
+        );
+
+        let (expected, _) = wire(&[b"keep a", b"keep b", b"keep c"]);
+        assert_eq!(out, expected);
+        assert_eq!(report.bytes_written, expected.len() as u64);
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn zero_filled_region_is_skipped_not_read_as_empty_frames() {
+        let (first, _) = wire(&[b"before"]);
+        let (last, _) = wire(&[b"after"]);
+        for bytes in [
+            [&first[..], &[0; 64], &last[..]].concat(),
+            // Misaligned, so the scan has to find the frame after it.
+            [&first[..], &[0; 64], &[0xA5; 3], &last[..]].concat(),
+        ] {
+            let mut out = Vec::new();
+            let report = salvage(&bytes, ChecksumDeframer::new(Crc32::new()), &mut out).unwrap();
+            assert_eq!(report.frames, 2);
+            let next = (bytes.len() - last.len()) as u64;
+            assert_eq!(report.skipped.len(), 1);
+            assert_eq!(report.skipped[0], first.len() as u64..next);
+            assert_eq!(out, [&first[..], &last[..]].concat());
+        }
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn torn_tail_is_a_trailing_skipped_range() {
+        let (bytes, starts) = wire(&[b"first", b"second"]);
+        let torn = &bytes[..bytes.len() - 2];
+        let report = salvage(torn, ChecksumDeframer::new(Crc32::new()), Vec::new()).unwrap();
+        assert_eq!(report.frames, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0], starts[1] as u64..torn.len() as u64);
+        assert_eq!(report.skipped_bytes(), (torn.len() - starts[1]) as u64);
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn salvage_file_writes_a_new_file_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut bytes, starts) = wire(&[b"a", b"b"]);
+        bytes[starts[0] + 8] ^= 1;
+        let input = dir.path().join("capture.bin");
+        let output = dir.path().join("capture.salvaged.bin");
+        std::fs::write(&input, &bytes).unwrap();
+
+        let report = salvage_file(&input, &output, ChecksumDeframer::new(Crc32::new())).unwrap();
+        assert_eq!(report.frames, 1);
+        assert_eq!(std::fs::read(&input).unwrap(), bytes);
+        assert_eq!(std::fs::read(&output).unwrap(), wire(&[b"b"]).0);
+
+        let err = salvage_file(&input, &output, ChecksumDeframer::new(Crc32::new())).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Io(_)));
+    }
+}
@@ -0,0 +1,338 @@
+//! Replay a directory of rotated journal segments as one stream.
+//!
+//! [`RollingStreamWriter`](crate::RollingStreamWriter) splits a journal into
+//! segments; a [`SegmentedReader`] puts them back together. It lists the
+//! segments with the same [`SegmentNaming`] scheme, orders them by id, and
+//! reads them back to back through one deframer, so frames come out exactly
+//! as they were written.
+//!
+//! Segment boundaries follow the [`recover`](fn@crate::recover) contract:
+//!
+//! - Only the **final** segment may end in a torn frame — the crash-mid-append
+//!   signature. Reading then stops with `Ok(None)` and
+//!   [`end`](SegmentedReader::end) reports [`RecoveryEnd::TornTail`], with
+//!   [`offset`](SegmentedReader::offset) as the safe truncation point.
+//! - A torn frame in any **earlier** segment cannot be a crash (the writer
+//!   went on to open a later segment), so it is an `InvalidFrame` error.
+//! - Checksum, length and validation failures are errors in every segment.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::framing::Deframer;
+use crate::recover::{CountingReader, RecoveryEnd};
+use crate::rolling::SegmentNaming;
+use crate::traits::StreamDeserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Reads a sequence of segment files as one stream.
+///
+/// ```rust
+/// use flatstream::{
+///     DefaultDeframer, DefaultFramer, NumberedSegments, RecoveryEnd, Result, RollPolicy,
+///     RollingStreamWriter, SegmentedReader,
+/// };
+///
+/// # fn main() -> Result<()> {
+/// # let dir = tempfile::tempdir()?;
+/// let naming = NumberedSegments::new(dir.path(), "journal");
+/// let policy = RollPolicy::new().with_max_frames(2);
+/// let mut writer = RollingStreamWriter::new(naming.clone(), DefaultFramer, policy)?;
+/// for event in ["a", "b", "c", "d", "e"] {
+///     writer.write_payload(event.as_bytes())?;
+/// }
+/// writer.finish()?;
+///
+/// let mut reader = SegmentedReader::open(&naming, DefaultDeframer::new())?;
+/// let mut replayed = Vec::new();
+/// reader.process_all(|payload| {
+///     replayed.push(payload.to_vec());
+///     Ok(())
+/// })?;
+/// assert_eq!(replayed.len(), 5);
+/// assert_eq!(reader.end(), Some(RecoveryEnd::CleanEof));
+/// # Ok(())
+/// # }
+/// ```
+pub struct SegmentedReader<D: Deframer> {
+    segments: Vec<(u64, PathBuf)>,
+    // Index into `segments` of the segment being read (or next to open).
+    position: usize,
+    current: Option<CountingReader<BufReader<File>>>,
+    deframer: D,
+    buffer: Vec<u8>,
+    end: Option<RecoveryEnd>,
+}
+
+impl<D: Deframer> SegmentedReader<D> {
+    /// Lists the segments named by `naming` and prepares to read them in id
+    /// order. Segments are opened one at a time, as reading reaches them.
+    pub fn open<N: SegmentNaming>(naming: &N, deframer: D) -> Result<Self> {
+        Ok(Self::from_segments(naming.list_segments()?, deframer))
+    }
+
+    /// Reads the given `(id, path)` segments in the order given.
+    pub fn from_segments(segments: Vec<(u64, PathBuf)>, deframer: D) -> Self {
+        Self {
+            segments,
+            position: 0,
+            current: None,
+            deframer,
+            buffer: Vec::new(),
+            end: None,
+        }
+    }
+
+    /// Reads the next message, moving on to the next segment at the end of
+    /// each one. Returns `Ok(None)` once the last segment is exhausted (or
+    /// ends in a torn frame; see [`end`](Self::end)).
+    #[inline]
+    pub fn read_message(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.read_len()?.map(|n| &self.buffer[..n]))
+    }
+
+    fn read_len(&mut self) -> Result<Option<usize>> {
+        while self.end.is_none() {
+            let Some(reader) = self.current.as_mut() else {
+                self.open_current()?;
+                continue;
+            };
+            let frame_start = reader.count;
+            match self.deframer.read_and_deframe(reader, &mut self.buffer) {
+                Ok(Some(n)) => return Ok(Some(n)),
+                Ok(None) => self.advance(),
+                Err(e) if matches!(e.kind(), ErrorKind::UnexpectedEof) => {
+                    if self.position + 1 < self.segments.len() {
+                        return Err(Error::invalid_frame(format!(
+                            "segment {} ends inside a frame at offset {} but is not the last segment",
+                            self.segments[self.position].0, frame_start
+                        )));
+                    }
+                    // Leave the counter at the last frame boundary.
+                    reader.count = frame_start;
+                    self.end = Some(RecoveryEnd::TornTail);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Opens `segments[position]`, or records a clean end past the last one.
+    fn open_current(&mut self) -> Result<()> {
+        match self.segments.get(self.position) {
+            Some((_, path)) => {
+                let file = File::open(path)?;
+                self.current = Some(CountingReader::new(BufReader::new(file)));
+            }
+            None => self.end = Some(RecoveryEnd::CleanEof),
+        }
+        Ok(())
+    }
+
+    fn advance(&mut self) {
+        self.current = None;
+        self.position += 1;
+    }
+
+    /// Processes all messages across all segments using a closure.
+    pub fn process_all<F>(&mut self, mut processor: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        while let Some(payload) = self.read_message()? {
+            processor(payload)?;
+        }
+        Ok(())
+    }
+
+    /// Processes all messages, deserializing each into a typed root.
+    pub fn process_typed<T, F>(&mut self, mut processor: F) -> Result<()>
+    where
+        for<'p> T: StreamDeserialize<'p>,
+        for<'p> F: FnMut(<T as StreamDeserialize<'p>>::Root) -> Result<()>,
+    {
+        self.process_all(|payload| {
+            let root = <T as StreamDeserialize<'_>>::from_payload(payload)?;
+            processor(root)
+        })
+    }
+
+    /// Returns an iterator-like object for manual message processing.
+    pub fn messages(&mut self) -> SegmentedMessages<'_, D> {
+        SegmentedMessages { reader: self }
+    }
+
+    /// Returns a typed iterator-like object for manual message processing.
+    pub fn typed_messages<T>(&mut self) -> TypedSegmentedMessages<'_, D, T>
+    where
+        for<'p> T: StreamDeserialize<'p>,
+    {
+        TypedSegmentedMessages {
+            reader: self,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// How reading ended: `None` while segments remain,
+    /// [`RecoveryEnd::CleanEof`] after the last segment ended on a frame
+    /// boundary, [`RecoveryEnd::TornTail`] if it ends in a torn frame.
+    pub fn end(&self) -> Option<RecoveryEnd> {
+        self.end
+    }
+
+    /// The `(id, path)` of the segment being read, if any. After a torn tail
+    /// this is the segment to repair.
+    pub fn current_segment(&self) -> Option<(u64, &Path)> {
+        self.segments
+            .get(self.position)
+            .map(|(id, path)| (*id, path.as_path()))
+    }
+
+    /// Byte offset of the next frame within the current segment. After a
+    /// torn tail this is the last good offset, where the segment can safely
+    /// be truncated.
+    pub fn offset(&self) -> u64 {
+        self.current.as_ref().map_or(0, |reader| reader.count)
+    }
+
+    /// The segments this reader covers, in reading order.
+    pub fn segments(&self) -> &[(u64, PathBuf)] {
+        &self.segments
+    }
+
+    /// Returns a reference to the deframer strategy.
+    pub fn deframer(&self) -> &D {
+        &self.deframer
+    }
+}
+
+/// Iterator-like access to the messages of a [`SegmentedReader`].
+pub struct SegmentedMessages<'a, D: Deframer> {
+    reader: &'a mut SegmentedReader<D>,
+}
+
+impl<D: Deframer> SegmentedMessages<'_, D> {
+    /// Returns the next message across segment boundaries.
+    #[inline]
+    pub fn next_message(&mut self) -> Result<Option<&[u8]>> {
+        self.reader.read_message()
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn next(&mut self) -> Result<Option<&[u8]>> {
+        self.next_message()
+    }
+}
+
+/// Typed iterator-like access to the messages of a [`SegmentedReader`].
+pub struct TypedSegmentedMessages<'a, D: Deframer, T>
+where
+    for<'p> T: StreamDeserialize<'p>,
+{
+    reader: &'a mut SegmentedReader<D>,
+    _phantom: PhantomData<T>,
+}
+
+impl<D: Deframer, T> TypedSegmentedMessages<'_, D, T>
+where
+    for<'p> T: StreamDeserialize<'p>,
+{
+    /// Returns the next typed root across segment boundaries.
+    #[inline]
+    pub fn next_typed<'p>(&'p mut self) -> Result<Option<<T as StreamDeserialize<'p>>::Root>> {
+        match self.reader.read_message()? {
+            Some(payload) => Ok(Some(<T as StreamDeserialize<'p>>::from_payload(payload)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn next<'p>(&'p mut self) -> Result<Option<<T as StreamDeserialize<'p>>::Root>> {
+        self.next_typed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{DefaultDeframer, DefaultFramer, Framer};
+    use crate::rolling::NumberedSegments;
+
+    fn write_segment(naming: &NumberedSegments, id: u64, payloads: &[&[u8]]) {
+        let mut bytes = Vec::new();
+        for p in payloads {
+            DefaultFramer.frame_and_write(&mut bytes, p).unwrap();
+        }
+        std::fs::write(naming.segment_path(id), bytes).unwrap();
+    }
+
+    fn replay(reader: &mut SegmentedReader<DefaultDeframer>) -> Result<Vec<Vec<u8>>> {
+        let mut out = Vec::new();
+        reader.process_all(|p| {
+            out.push(p.to_vec());
+            Ok(())
+        })?;
+        Ok(out)
+    }
+
+    #[test]
+    fn replays_segments_in_id_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        write_segment(&naming, 10, &[b"c"]);
+        write_segment(&naming, 2, &[b"a", b"b"]);
+        write_segment(&naming, 11, &[]);
+        write_segment(&naming, 12, &[b"d"]);
+
+        let mut reader = SegmentedReader::open(&naming, DefaultDeframer::new()).unwrap();
+        assert_eq!(replay(&mut reader).unwrap(), [b"a", b"b", b"c", b"d"]);
+        assert_eq!(reader.end(), Some(RecoveryEnd::CleanEof));
+        assert!(reader.read_message().unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_directory_is_a_clean_empty_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        let mut reader = SegmentedReader::open(&naming, DefaultDeframer::new()).unwrap();
+        assert!(reader.messages().next().unwrap().is_none());
+        assert_eq!(reader.end(), Some(RecoveryEnd::CleanEof));
+    }
+
+    #[test]
+    fn torn_final_segment_reports_torn_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        write_segment(&naming, 0, &[b"a"]);
+        write_segment(&naming, 1, &[b"b", b"torn"]);
+        let path = naming.segment_path(1);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+
+        let mut reader = SegmentedReader::open(&naming, DefaultDeframer::new()).unwrap();
+        assert_eq!(replay(&mut reader).unwrap(), [b"a", b"b"]);
+        assert_eq!(reader.end(), Some(RecoveryEnd::TornTail));
+        assert_eq!(reader.current_segment(), Some((1, path.as_path())));
+        assert_eq!(reader.offset(), 5);
+    }
+
+    #[test]
+    fn torn_earlier_segment_is_a_hard_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let naming = NumberedSegments::new(dir.path(), "journal");
+        write_segment(&naming, 0, &[b"a", b"torn"]);
+        write_segment(&naming, 1, &[b"b"]);
+        let path = naming.segment_path(0);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+
+        let mut reader = SegmentedReader::open(&naming, DefaultDeframer::new()).unwrap();
+        let err = replay(&mut reader).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        assert_eq!(reader.end(), None);
+    }
+}
@@ -0,0 +1,306 @@
+//! A reader over a stream that is already in memory.
+
+use crate::error::{Error, Result};
+use crate::framing::SliceDeframer;
+use crate::traits::StreamDeserialize;
+
+/// A reader that walks an in-memory byte slice frame by frame.
+///
+/// The borrowed-slice counterpart of [`StreamReader`](crate::StreamReader):
+/// the same wire format, length bounds, checksum verification and validation
+/// (through [`SliceDeframer`], implemented by `DefaultDeframer`,
+/// `ChecksumDeframer` and the validating/observer adapters), but nothing is
+/// copied. Each payload is a subslice of the input and carries the input's
+/// lifetime `'a`, so payloads — and typed roots built on them — can be held
+/// across iterations, collected, or sent to other borrowers of the input.
+///
+/// ```rust
+/// use flatstream::{DefaultDeframer, DefaultFramer, Framer, Result, SliceReader};
+///
+/// # fn main() -> Result<()> {
+/// let mut capture = Vec::new();
+/// DefaultFramer.frame_and_write(&mut capture, b"first")?;
+/// DefaultFramer.frame_and_write(&mut capture, b"second")?;
+///
+/// let mut reader = SliceReader::new(&capture, DefaultDeframer::new());
+/// let first = reader.read_message()?.unwrap();
+/// let second = reader.read_message()?.unwrap();
+/// // Both payloads are still valid: they borrow `capture`, not the reader.
+/// assert_eq!((first, second), (&b"first"[..], &b"second"[..]));
+/// assert!(reader.read_message()?.is_none());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## End of input
+///
+/// An exhausted input is a clean end of stream (`Ok(None)`); a non-empty
+/// remainder that does not hold a complete frame is a torn frame and reports
+/// `ErrorKind::UnexpectedEof`, exactly as the `Read`-based path does (spec
+/// §6). A failed read leaves [`position`](Self::position) at the start of the
+/// offending frame — one past the last intact frame.
+pub struct SliceReader<'a, D: SliceDeframer> {
+    data: &'a [u8],
+    position: usize,
+    deframer: D,
+}
+
+impl<'a, D: SliceDeframer> SliceReader<'a, D> {
+    /// Creates a reader over `data`, starting at its first byte.
+    pub fn new(data: &'a [u8], deframer: D) -> Self {
+        Self {
+            data,
+            position: 0,
+            deframer,
+        }
+    }
+
+    /// Creates a reader over `data` that starts at byte `position`, e.g. a
+    /// frame boundary recorded by an earlier reader.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is greater than `data.len()`.
+    pub fn with_position(data: &'a [u8], deframer: D, position: usize) -> Self {
+        assert!(position <= data.len(), "position beyond end of input");
+        Self {
+            data,
+            position,
+            deframer,
+        }
+    }
+
+    /// Reads the next message. Returns `Ok(Some(payload))` on success and
+    /// `Ok(None)` at the end of the input.
+    ///
+    /// The payload borrows the input, not the reader: it stays valid after
+    /// further reads.
+    #[inline]
+    pub fn read_message(&mut self) -> Result<Option<&'a [u8]>> {
+        let rest = &self.data[self.position..];
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        match self.deframer.deframe_slice(rest)? {
+            Some(frame) => {
+                self.position += frame.len;
+                Ok(Some(&rest[frame.payload]))
+            }
+            None => Err(Error::unexpected_eof()),
+        }
+    }
+
+    /// Processes all remaining messages using a closure.
+    ///
+    /// The closure receives payloads with the input's lifetime, so it may
+    /// keep them. Return `Ok(())` to continue or an error to stop.
+    pub fn process_all<F>(&mut self, mut processor: F) -> Result<()>
+    where
+        F: FnMut(&'a [u8]) -> Result<()>,
+    {
+        while let Some(payload) = self.read_message()? {
+            processor(payload)?;
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator-like object for manual message processing.
+    ///
+    /// Unlike [`StreamReader::messages`](crate::StreamReader::messages), each
+    /// payload borrows the input and remains valid across iterations.
+    pub fn messages(&mut self) -> SliceMessages<'_, 'a, D> {
+        SliceMessages { reader: self }
+    }
+
+    /// Processes all remaining messages, deserializing each into a typed
+    /// FlatBuffer root that borrows the input.
+    pub fn process_typed<T, F>(&mut self, mut processor: F) -> Result<()>
+    where
+        T: StreamDeserialize<'a>,
+        F: FnMut(T::Root) -> Result<()>,
+    {
+        self.process_all(|payload| processor(T::from_payload(payload)?))
+    }
+
+    /// Returns a typed iterator-like object yielding verified roots that
+    /// borrow the input.
+    pub fn typed_messages<T>(&mut self) -> TypedSliceMessages<'_, 'a, D, T>
+    where
+        T: StreamDeserialize<'a>,
+    {
+        TypedSliceMessages {
+            reader: self,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Byte offset of the next frame within the input. After a clean run this
+    /// equals the input length; after an error it is the start of the frame
+    /// that failed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The bytes not yet consumed.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    /// Returns the whole input slice.
+    pub fn get_ref(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns a reference to the deframer strategy.
+    pub fn deframer(&self) -> &D {
+        &self.deframer
+    }
+}
+
+/// An iterator-like object over a [`SliceReader`] whose payloads borrow the
+/// input rather than the reader.
+pub struct SliceMessages<'r, 'a, D: SliceDeframer> {
+    reader: &'r mut SliceReader<'a, D>,
+}
+
+impl<'a, D: SliceDeframer> SliceMessages<'_, 'a, D> {
+    /// Returns the next message in the input.
+    ///
+    /// # Returns
+    /// * `Ok(Some(payload))` - A message was successfully read
+    /// * `Ok(None)` - End of input reached
+    /// * `Err(e)` - An error occurred during reading
+    #[inline]
+    pub fn next_message(&mut self) -> Result<Option<&'a [u8]>> {
+        self.reader.read_message()
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn next(&mut self) -> Result<Option<&'a [u8]>> {
+        self.next_message()
+    }
+}
+
+/// Typed iterator-like object over a [`SliceReader`] yielding verified roots
+/// that borrow the input.
+pub struct TypedSliceMessages<'r, 'a, D: SliceDeframer, T>
+where
+    T: StreamDeserialize<'a>,
+{
+    reader: &'r mut SliceReader<'a, D>,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<'a, D: SliceDeframer, T> TypedSliceMessages<'_, 'a, D, T>
+where
+    T: StreamDeserialize<'a>,
+{
+    /// Returns the next typed root in the input.
+    #[inline]
+    pub fn next_typed(&mut self) -> Result<Option<T::Root>> {
+        match self.reader.read_message()? {
+            Some(payload) => Ok(Some(T::from_payload(payload)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    #[inline]
+    pub fn next(&mut self) -> Result<Option<T::Root>> {
+        self.next_typed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::framing::{DefaultDeframer, DefaultFramer, DeframerExt, Framer};
+    use crate::validation::SizeValidator;
+
+    #[cfg(feature = "xxhash")]
+    use crate::{ChecksumDeframer, ChecksumFramer, XxHash64};
+
+    fn frames<F: Framer>(framer: &F, payloads: &[&[u8]]) -> Vec<u8> {
+        let mut wire = Vec::new();
+        for p in payloads {
+            framer.frame_and_write(&mut wire, p).unwrap();
+        }
+        wire
+    }
+
+    #[test]
+    fn payloads_borrow_the_input_and_outlive_the_next_read() {
+        let wire = frames(&DefaultFramer, &[b"one", b"two", b"three"]);
+        let mut reader = SliceReader::new(&wire, DefaultDeframer::new());
+        let mut held = Vec::new();
+        reader
+            .process_all(|payload| {
+                held.push(payload);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(held, [&b"one"[..], b"two", b"three"]);
+        // Zero-copy: every payload points into the input.
+        let range = wire.as_ptr_range();
+        assert!(held.iter().all(|p| range.contains(&p.as_ptr())));
+        assert_eq!(reader.position(), wire.len());
+    }
+
+    #[test]
+    fn messages_iterator_matches_process_all() {
+        let wire = frames(&DefaultFramer, &[b"a", b"", b"ccc"]);
+        let mut reader = SliceReader::new(&wire, DefaultDeframer::new());
+        let mut messages = reader.messages();
+        let first = messages.next().unwrap().unwrap();
+        let second = messages.next().unwrap().unwrap();
+        let third = messages.next().unwrap().unwrap();
+        assert!(messages.next().unwrap().is_none());
+        assert_eq!((first, second, third), (&b"a"[..], &b""[..], &b"ccc"[..]));
+    }
+
+    #[test]
+    fn torn_tail_is_unexpected_eof_at_last_boundary() {
+        let mut wire = frames(&DefaultFramer, &[b"intact"]);
+        let boundary = wire.len();
+        wire.extend_from_slice(&[9, 0, 0, 0, b'x']); // declares 9, holds 1
+        let mut reader = SliceReader::new(&wire, DefaultDeframer::new());
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"intact");
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+        assert_eq!(reader.position(), boundary);
+    }
+
+    #[test]
+    fn bounds_and_validators_apply() {
+        let wire = frames(&DefaultFramer, &[b"0123456789"]);
+        let mut reader = SliceReader::new(&wire, DefaultDeframer::new().with_max_frame_len(4));
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+
+        let deframer = DefaultDeframer::new().with_validator(SizeValidator::new(0, 4));
+        let mut reader = SliceReader::new(&wire, deframer);
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ValidationFailed { .. }));
+    }
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn checksummed_slice_matches_stream_reader() {
+        let framer = ChecksumFramer::new(XxHash64::new());
+        let mut wire = frames(&framer, &[b"alpha", b"beta"]);
+
+        let mut reader = SliceReader::new(&wire, ChecksumDeframer::new(XxHash64::new()));
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"alpha");
+        assert_eq!(reader.read_message().unwrap().unwrap(), b"beta");
+        assert!(reader.read_message().unwrap().is_none());
+
+        let last = wire.len() - 1;
+        wire[last] ^= 0x01;
+        let mut reader = SliceReader::new(&wire, ChecksumDeframer::new(XxHash64::new()));
+        reader.read_message().unwrap();
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }));
+    }
+}
@@ -0,0 +1,454 @@
+//! Sync-marker framing: a fixed magic word in front of every frame, so a
+//! reader can re-anchor after corruption.
+//!
+//! In the plain formats the only thing locating frame *n + 1* is frame *n*'s
+//! length header: one flipped bit there and nothing after it can be found
+//! again. [`SyncFramer`] writes
+//!
+//! ```text
+//! [4-byte sync marker | 4-byte LE length | C::SIZE-byte checksum | payload]
+//! ```
+//!
+//! and [`SyncDeframer`] reads it. By default the deframer is strict — a
+//! missing marker is an `InvalidFrame` error, like any other malformed
+//! header — so torn-tail recovery works unchanged. With
+//! [`with_resync`](SyncDeframer::with_resync) it becomes tolerant: after a
+//! missing marker, an out-of-bounds length or a checksum mismatch it scans
+//! forward to the next marker that starts a verifiable frame, reports the
+//! skipped bytes to a hook, and carries on. That is the mode for lossy links;
+//! never combine it with [`recover`](fn@crate::recover), whose job is to
+//! *stop* at corruption.
+//!
+//! A marker can occur by chance inside a payload, so a resynchronized frame
+//! is only as trustworthy as its checksum: use a checksummed format and a
+//! tight [`with_max_frame_len`](SyncDeframer::with_max_frame_len). Over a
+//! [`Read`] stream, a corrupted length that is still within the bound
+//! consumes its declared payload before the mismatch shows, so the frames
+//! inside those bytes are lost with it; the [`SliceDeframer`] path rescans
+//! from the byte after the bad marker and loses nothing it can verify.
+
+use crate::checksum::Checksum;
+use crate::error::{Error, ErrorKind, Result};
+use crate::framing::{
+    check_frame_len, read_header, read_payload, Deframer, Framer, SliceDeframer, SliceFrame,
+    DEFAULT_MAX_FRAME_LEN,
+};
+use std::io::{Read, Write};
+
+/// The marker written in front of every frame unless
+/// [`with_marker`](SyncFramer::with_marker) chooses another. Its bytes are
+/// unlikely in a little-endian length header (the high byte is `0xC3`) and
+/// in FlatBuffers offsets.
+pub const DEFAULT_SYNC_MARKER: [u8; 4] = [0xF5, 0x1A, 0x7E, 0xC3];
+
+/// Writes each frame as `[sync marker | length | checksum | payload]`.
+///
+/// When to use: streams crossing lossy links or media where a reader must be
+/// able to skip a damaged frame and keep reading. Costs 4 bytes per frame
+/// over [`ChecksumFramer`](crate::framing::ChecksumFramer).
+pub struct SyncFramer<C: Checksum> {
+    checksum_alg: C,
+    marker: [u8; 4],
+}
+
+impl<C: Checksum> SyncFramer<C> {
+    pub fn new(checksum_alg: C) -> Self {
+        const {
+            assert!(
+                C::SIZE <= 8,
+                "checksum wider than the u64 the trait works in"
+            )
+        };
+        Self {
+            checksum_alg,
+            marker: DEFAULT_SYNC_MARKER,
+        }
+    }
+
+    /// Uses `marker` instead of [`DEFAULT_SYNC_MARKER`]; the deframer must be
+    /// configured with the same marker.
+    pub fn with_marker(mut self, marker: [u8; 4]) -> Self {
+        self.marker = marker;
+        self
+    }
+}
+
+impl<C: Checksum> Framer for SyncFramer<C> {
+    fn frame_and_write<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<()> {
+        if payload.len() > u32::MAX as usize {
+            return Err(Error::invalid_frame_with(
+                "payload length exceeds 32-bit header limit",
+                Some(payload.len()),
+                None,
+                Some(u32::MAX as usize),
+            ));
+        }
+        let checksum = self.checksum_alg.calculate(payload);
+        let mut header = [0u8; 16];
+        header[..4].copy_from_slice(&self.marker);
+        header[4..8].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        let checksum_field: &mut [u8; 8] = (&mut header[8..16]).try_into().unwrap();
+        self.checksum_alg.write_bytes(checksum, checksum_field);
+
+        writer.write_all(&header[..8 + C::SIZE])?;
+        writer.write_all(payload)?;
+        Ok(())
+    }
+}
+
+/// Reads streams written by a [`SyncFramer`] with the same checksum and
+/// marker, optionally resynchronizing past corruption.
+///
+/// ```rust
+/// use flatstream::{Framer, NoChecksum, Result, StreamReader, SyncDeframer, SyncFramer};
+///
+/// # fn main() -> Result<()> {
+/// let framer = SyncFramer::new(NoChecksum);
+/// let mut stream = Vec::new();
+/// framer.frame_and_write(&mut stream, b"before")?;
+/// stream.extend_from_slice(b"line noise");
+/// framer.frame_and_write(&mut stream, b"after")?;
+///
+/// let deframer = SyncDeframer::new(NoChecksum)
+///     .with_max_frame_len(1024)
+///     .with_resync(|skipped, cause| eprintln!("skipped {skipped} bytes: {cause}"));
+/// let mut reader = StreamReader::new(&stream[..], deframer);
+/// assert_eq!(reader.read_message()?, Some(&b"before"[..]));
+/// assert_eq!(reader.read_message()?, Some(&b"after"[..]));
+/// assert_eq!(reader.read_message()?, None);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct SyncDeframer<C: Checksum, H = fn(u64, &Error)> {
+    checksum_alg: C,
+    marker: [u8; 4],
+    max_frame_len: usize,
+    on_resync: Option<H>,
+}
+
+impl<C: Checksum> SyncDeframer<C> {
+    /// A strict deframer: every frame must start with the marker.
+    pub fn new(checksum_alg: C) -> Self {
+        const {
+            assert!(
+                C::SIZE <= 8,
+                "checksum wider than the u64 the trait works in"
+            )
+        };
+        Self {
+            checksum_alg,
+            marker: DEFAULT_SYNC_MARKER,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            on_resync: None,
+        }
+    }
+}
+
+impl<C: Checksum, H: Fn(u64, &Error)> SyncDeframer<C, H> {
+    /// Expects `marker` instead of [`DEFAULT_SYNC_MARKER`].
+    pub fn with_marker(mut self, marker: [u8; 4]) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    /// Sets the maximum accepted payload length (enforced before allocation).
+    pub fn with_max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+
+    /// Switches to tolerant reading: a missing marker, an out-of-bounds
+    /// length or a checksum mismatch no longer fails the read; the deframer
+    /// scans forward to the next verifiable frame instead. Before yielding
+    /// that frame (or a clean EOF) it calls `hook` with the number of bytes
+    /// skipped and the first error that caused the skip.
+    ///
+    /// A torn frame at the end of the stream is still `UnexpectedEof`, and
+    /// I/O errors still propagate.
+    pub fn with_resync<H2: Fn(u64, &Error)>(self, hook: H2) -> SyncDeframer<C, H2> {
+        SyncDeframer {
+            checksum_alg: self.checksum_alg,
+            marker: self.marker,
+            max_frame_len: self.max_frame_len,
+            on_resync: Some(hook),
+        }
+    }
+
+    /// Reads `[length | checksum | payload]` after a marker, adding the bytes
+    /// taken from `reader` to `consumed` as it goes.
+    fn read_body<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        consumed: &mut u64,
+    ) -> Result<usize> {
+        let mut header = [0u8; 12];
+        read_in_frame(reader, &mut header[..4 + C::SIZE])?;
+        *consumed += (4 + C::SIZE) as u64;
+        let payload_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        check_frame_len(payload_len, self.max_frame_len)?;
+        let expected = self.checksum_alg.read_bytes(&header[4..4 + C::SIZE]);
+
+        read_payload(reader, buffer, payload_len)?;
+        *consumed += payload_len as u64;
+        self.checksum_alg.verify(expected, &buffer[..payload_len])?;
+        Ok(payload_len)
+    }
+
+    /// Locates a frame whose marker sits at `input[start..]`; the returned
+    /// frame's `len` is measured from the start of `input`.
+    fn frame_at(&self, input: &[u8], start: usize) -> Result<Option<SliceFrame>> {
+        let rest = &input[start..];
+        let Some(marker) = rest.first_chunk::<4>() else {
+            return Ok(None);
+        };
+        if *marker != self.marker {
+            return Err(missing_marker());
+        }
+        let header_len = 8 + C::SIZE;
+        let Some(header) = rest.get(..header_len) else {
+            return Ok(None);
+        };
+        let payload_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        check_frame_len(payload_len, self.max_frame_len)?;
+        if rest.len() - header_len < payload_len {
+            return Ok(None);
+        }
+        let payload = start + header_len..start + header_len + payload_len;
+        let expected = self.checksum_alg.read_bytes(&header[8..]);
+        self.checksum_alg
+            .verify(expected, &input[payload.clone()])?;
+        Ok(Some(SliceFrame {
+            len: payload.end,
+            payload,
+        }))
+    }
+}
+
+impl<C: Checksum, H: Fn(u64, &Error)> Deframer for SyncDeframer<C, H> {
+    fn read_and_deframe<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<usize>> {
+        let mut window = [0u8; 4];
+        if read_header(reader, &mut window)?.is_none() {
+            return Ok(None);
+        }
+        let Some(on_resync) = &self.on_resync else {
+            if window != self.marker {
+                return Err(missing_marker());
+            }
+            return self.read_body(reader, buffer, &mut 0).map(Some);
+        };
+
+        // Slide a 4-byte window over the stream until it holds a marker that
+        // starts a verifiable frame. `cause` keeps the first failure.
+        let mut skipped = 0u64;
+        let mut cause = None;
+        loop {
+            if window != self.marker {
+                cause.get_or_insert_with(missing_marker);
+                skipped += 1;
+                window.copy_within(1.., 0);
+                read_in_frame(reader, &mut window[3..])?;
+                continue;
+            }
+            let mut consumed = 4;
+            match self.read_body(reader, buffer, &mut consumed) {
+                Ok(len) => {
+                    if let Some(cause) = &cause {
+                        on_resync(skipped, cause);
+                    }
+                    return Ok(Some(len));
+                }
+                Err(e) if is_corruption(&e) => {
+                    skipped += consumed;
+                    cause.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+            // The bad frame was consumed whole; what follows may be a clean
+            // end of stream.
+            if read_header(reader, &mut window)?.is_none() {
+                if let Some(cause) = &cause {
+                    on_resync(skipped, cause);
+                }
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Continues a strict read whose marker and length were consumed by the
+    /// caller; never resynchronizes.
+    fn read_after_length<R: Read>(
+        &self,
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+        payload_len: usize,
+    ) -> Result<Option<usize>> {
+        check_frame_len(payload_len, self.max_frame_len)?;
+        let mut checksum_bytes = [0u8; 8];
+        read_in_frame(reader, &mut checksum_bytes[..C::SIZE])?;
+        let expected = self.checksum_alg.read_bytes(&checksum_bytes);
+        read_payload(reader, buffer, payload_len)?;
+        self.checksum_alg.verify(expected, &buffer[..payload_len])?;
+        Ok(Some(payload_len))
+    }
+}
+
+impl<C: Checksum, H: Fn(u64, &Error)> SliceDeframer for SyncDeframer<C, H> {
+    fn deframe_slice(&self, input: &[u8]) -> Result<Option<SliceFrame>> {
+        let Some(on_resync) = &self.on_resync else {
+            return self.frame_at(input, 0);
+        };
+        let mut cause = None;
+        let mut start = 0;
+        loop {
+            match self.frame_at(input, start) {
+                Ok(Some(frame)) => {
+                    if let Some(cause) = &cause {
+                        on_resync(start as u64, cause);
+                    }
+                    return Ok(Some(frame));
+                }
+                Ok(None) => return Ok(None),
+                Err(e) if is_corruption(&e) => {
+                    cause.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+            // Rescan from the byte after the rejected candidate, so a frame
+            // hidden inside a bogus payload is still found.
+            match input[start + 1..].windows(4).position(|w| w == self.marker) {
+                Some(p) => start += 1 + p,
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Fills `buf` from inside a frame, where any EOF is a torn frame.
+fn read_in_frame<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::unexpected_eof(),
+        _ => e.into(),
+    })
+}
+
+fn missing_marker() -> Error {
+    Error::invalid_frame("sync marker missing at frame start")
+}
+
+/// Failures a resynchronizing reader skips past; EOF and I/O errors are not.
+fn is_corruption(e: &Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::InvalidFrame { .. } | ErrorKind::ChecksumMismatch { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::NoChecksum;
+    use crate::reader::StreamReader;
+    use crate::slice::SliceReader;
+    use std::cell::RefCell;
+
+    fn framed(framer: &impl Framer, payloads: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for p in payloads {
+            framer.frame_and_write(&mut out, p).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn strict_mode_round_trips_and_rejects_a_missing_marker() {
+        let stream = framed(&SyncFramer::new(NoChecksum), &[b"one", b"", b"three"]);
+        assert_eq!(&stream[..4], &DEFAULT_SYNC_MARKER);
+        let mut reader = StreamReader::new(&stream[..], SyncDeframer::new(NoChecksum));
+        assert_eq!(reader.read_message().unwrap(), Some(&b"one"[..]));
+        assert_eq!(reader.read_message().unwrap(), Some(&b""[..]));
+        assert_eq!(reader.read_message().unwrap(), Some(&b"three"[..]));
+        assert_eq!(reader.read_message().unwrap(), None);
+
+        let mut damaged = stream.clone();
+        damaged[0] ^= 0xFF;
+        let mut reader = StreamReader::new(&damaged[..], SyncDeframer::new(NoChecksum));
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+        let err = SliceReader::new(&damaged, SyncDeframer::new(NoChecksum))
+            .read_message()
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+
+        let torn = &stream[..stream.len() - 1];
+        let mut reader = StreamReader::new(torn, SyncDeframer::new(NoChecksum));
+        reader.read_message().unwrap();
+        reader.read_message().unwrap();
+        let err = reader.read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn resync_skips_garbage_and_an_oversized_length() {
+        let framer = SyncFramer::new(NoChecksum);
+        let mut stream = framed(&framer, &[b"a"]);
+        stream.extend_from_slice(b"noise");
+        stream.extend_from_slice(&framed(&framer, &[b"b"]));
+        let bad_start = stream.len();
+        stream.extend_from_slice(&framed(&framer, &[&[7u8; 40][..]]));
+        stream[bad_start + 5] = 0x10; // length 4096 + 40: over the bound
+        let bad_len = (stream.len() - bad_start) as u64;
+        stream.extend_from_slice(&framed(&framer, &[b"c"]));
+
+        let skips = RefCell::new(Vec::new());
+        let deframer = SyncDeframer::new(NoChecksum)
+            .with_max_frame_len(64)
+            .with_resync(|n, _: &Error| skips.borrow_mut().push(n));
+
+        let mut payloads = Vec::new();
+        StreamReader::new(&stream[..], deframer)
+            .process_all(|p| {
+                payloads.push(p.to_vec());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(payloads, [b"a", b"b", b"c"]);
+        assert_eq!(*skips.borrow(), [5, bad_len]);
+
+        skips.borrow_mut().clear();
+        let mut reader = SliceReader::new(&stream, deframer);
+        let mut slices = Vec::new();
+        while let Some(p) = reader.read_message().unwrap() {
+            slices.push(p);
+        }
+        assert_eq!(slices, [b"a", b"b", b"c"]);
+        assert_eq!(*skips.borrow(), [5, bad_len]);
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn resync_skips_a_checksum_mismatch_up_to_a_clean_end() {
+        use crate::Crc32;
+
+        let stream = framed(&SyncFramer::new(Crc32::new()), &[b"good", b"flipped"]);
+        let mut damaged = stream.clone();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 0x01;
+
+        let causes = RefCell::new(Vec::new());
+        let deframer = SyncDeframer::new(Crc32::new())
+            .with_resync(|n, e: &Error| causes.borrow_mut().push((n, e.to_string())));
+        let mut reader = StreamReader::new(&damaged[..], deframer);
+        assert_eq!(reader.read_message().unwrap(), Some(&b"good"[..]));
+        assert_eq!(reader.read_message().unwrap(), None);
+        let causes = causes.borrow();
+        assert_eq!(causes.len(), 1);
+        assert_eq!(causes[0].0, 4 + 4 + 4 + 7);
+        assert!(causes[0].1.to_lowercase().contains("checksum"));
+    }
+}
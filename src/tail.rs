@@ -0,0 +1,327 @@
+//! Follow mode: read a journal while another process is still appending to
+//! it.
+//!
+//! A [`StreamReader`](crate::StreamReader) treats the end of its source as
+//! the end of the stream, and a frame cut short by EOF as `UnexpectedEof`.
+//! For a file that is still growing both are merely "not yet": a
+//! [`TailReader`] rewinds to the start of the incomplete frame, waits one poll
+//! interval on its [`Clock`], and tries again.
+//!
+//! Only EOF is treated that way. A checksum mismatch, an oversized length or
+//! a validator failure in bytes that *are* on disk is still an error — the
+//! writer appends, it never rewrites, so waiting cannot fix them.
+//!
+//! The reader also notices when the file it follows is replaced:
+//!
+//! - **Truncation** (the file shrinks below the read position, e.g. a
+//!   copy-and-truncate rotation): reading restarts at offset 0.
+//! - **Rotation** (the path now names a different file, e.g. rename-and-
+//!   recreate): once no complete frame is left in the old file, the new file
+//!   is opened and read from offset 0. Frames appended to the old file after
+//!   the switch are not seen. Rotation is detected by file identity, which is
+//!   only available on Unix; elsewhere only truncation is detected.
+//!
+//! Both bump [`TailReader::generation`], so consumers that aggregate state
+//! can tell a fresh file from a continuation.
+
+use crate::error::{ErrorKind, Result};
+use crate::framing::Deframer;
+use crate::policy::{Clock, MonotonicClock};
+use crate::recover::CountingReader;
+use std::fs::{File, Metadata};
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Default wait between polls of a file with no complete frame.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Follows a journal file as it is written.
+///
+/// ```rust,no_run
+/// use flatstream::{DefaultDeframer, Result, TailReader};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<()> {
+/// let mut tail = TailReader::open("telemetry.bin", DefaultDeframer::new())?
+///     .with_poll_interval(Duration::from_millis(50));
+/// // Without an idle timeout, each call waits as long as it takes.
+/// while let Some(payload) = tail.read_message()? {
+///     println!("{} byte frame at generation {}", payload.len(), tail.generation());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct TailReader<D: Deframer, C: Clock = MonotonicClock> {
+    path: PathBuf,
+    file: BufReader<File>,
+    deframer: D,
+    clock: C,
+    buffer: Vec<u8>,
+    // Offset of the next frame's length header in the current file.
+    offset: u64,
+    generation: u64,
+    poll_interval: Duration,
+    idle_timeout: Option<Duration>,
+}
+
+impl<D: Deframer> TailReader<D> {
+    /// Opens `path` and follows it from its first frame, polling on the
+    /// [`MonotonicClock`].
+    pub fn open<P: AsRef<Path>>(path: P, deframer: D) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = BufReader::new(File::open(&path)?);
+        Ok(Self {
+            path,
+            file,
+            deframer,
+            clock: MonotonicClock::new(),
+            buffer: Vec::new(),
+            offset: 0,
+            generation: 0,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            idle_timeout: None,
+        })
+    }
+}
+
+impl<D: Deframer, C: Clock> TailReader<D, C> {
+    /// Replaces the clock used for poll intervals and the idle timeout.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> TailReader<D, C2> {
+        TailReader {
+            path: self.path,
+            file: self.file,
+            deframer: self.deframer,
+            clock,
+            buffer: self.buffer,
+            offset: self.offset,
+            generation: self.generation,
+            poll_interval: self.poll_interval,
+            idle_timeout: self.idle_timeout,
+        }
+    }
+
+    /// Sets the wait between polls (default [`DEFAULT_POLL_INTERVAL`]).
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Makes [`read_message`](Self::read_message) return `Ok(None)` after
+    /// waiting `timeout` without a new frame. Without one it waits forever.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Reads the next frame, waiting for it to be written.
+    ///
+    /// Returns `Ok(None)` only when the idle timeout elapses first. The
+    /// payload borrows the reader's buffer and is valid until the next read.
+    pub fn read_message(&mut self) -> Result<Option<&[u8]>> {
+        let started = self.clock.now();
+        loop {
+            if let Some(len) = self.poll()? {
+                return Ok(Some(&self.buffer[..len]));
+            }
+            if let Some(timeout) = self.idle_timeout {
+                if self.clock.now().saturating_sub(started) >= timeout {
+                    return Ok(None);
+                }
+            }
+            self.clock.sleep(self.poll_interval);
+        }
+    }
+
+    /// Reads the next frame if it is already complete, without waiting.
+    /// `Ok(None)` means "not yet".
+    pub fn try_read_message(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.poll()?.map(|len| &self.buffer[..len]))
+    }
+
+    /// Offset of the next frame in the file currently followed.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// How many times the followed file was truncated or rotated.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The followed path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns a reference to the deframer strategy.
+    pub fn deframer(&self) -> &D {
+        &self.deframer
+    }
+
+    /// One attempt at the next frame; on "not yet", rewinds to the frame
+    /// start and checks whether the file was truncated or replaced.
+    fn poll(&mut self) -> Result<Option<usize>> {
+        let mut counted = CountingReader::new(&mut self.file);
+        match self
+            .deframer
+            .read_and_deframe(&mut counted, &mut self.buffer)
+        {
+            Ok(Some(len)) => {
+                self.offset += counted.count;
+                return Ok(Some(len));
+            }
+            Ok(None) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::UnexpectedEof) => {}
+            Err(e) => return Err(e),
+        }
+
+        let current = self.file.get_ref().metadata()?;
+        if current.len() < self.offset {
+            self.offset = 0;
+            self.generation += 1;
+        } else if let Ok(named) = std::fs::metadata(&self.path) {
+            // A missing path is a rotation in progress: keep the old file.
+            if file_id(&named).is_some() && file_id(&named) != file_id(&current) {
+                self.file = BufReader::new(File::open(&self.path)?);
+                self.offset = 0;
+                self.generation += 1;
+            }
+        }
+        self.file.seek(SeekFrom::Start(self.offset))?;
+        Ok(None)
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::{DefaultDeframer, DefaultFramer, Framer};
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// Advances its own time on `sleep`, so idle timeouts elapse instantly.
+    #[derive(Clone, Default)]
+    struct TestClock(Arc<AtomicU64>); // elapsed milliseconds
+
+    impl Clock for TestClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.0.load(Ordering::Relaxed))
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.0
+                .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        DefaultFramer.frame_and_write(&mut out, payload).unwrap();
+        out
+    }
+
+    fn append(path: &Path, bytes: &[u8]) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn torn_tail_is_not_yet_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.bin");
+        let second = frame(b"second");
+        std::fs::write(&path, [frame(b"first"), second[..6].to_vec()].concat()).unwrap();
+
+        let mut tail = TailReader::open(&path, DefaultDeframer::new()).unwrap();
+        assert_eq!(tail.try_read_message().unwrap().unwrap(), b"first");
+        assert!(tail.try_read_message().unwrap().is_none());
+        assert!(tail.try_read_message().unwrap().is_none());
+        assert_eq!(tail.offset(), 9);
+
+        append(&path, &second[6..]);
+        assert_eq!(tail.try_read_message().unwrap().unwrap(), b"second");
+        assert_eq!(tail.offset(), 9 + second.len() as u64);
+        assert_eq!(tail.generation(), 0);
+    }
+
+    #[test]
+    fn idle_timeout_elapses_on_the_injected_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.bin");
+        std::fs::write(&path, frame(b"only")).unwrap();
+
+        let clock = TestClock::default();
+        let mut tail = TailReader::open(&path, DefaultDeframer::new())
+            .unwrap()
+            .with_clock(clock.clone())
+            .with_poll_interval(Duration::from_millis(100))
+            .with_idle_timeout(Duration::from_secs(1));
+        assert_eq!(tail.read_message().unwrap().unwrap(), b"only");
+        assert_eq!(clock.now(), Duration::ZERO);
+        assert!(tail.read_message().unwrap().is_none());
+        assert_eq!(clock.now(), Duration::from_secs(1));
+
+        append(&path, &frame(b"later"));
+        assert_eq!(tail.read_message().unwrap().unwrap(), b"later");
+    }
+
+    #[test]
+    fn corruption_on_disk_is_still_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.bin");
+        std::fs::write(&path, 1024u32.to_le_bytes()).unwrap();
+
+        let deframer = DefaultDeframer::new().with_max_frame_len(16);
+        let mut tail = TailReader::open(&path, deframer).unwrap();
+        let err = tail.try_read_message().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidFrame { .. }));
+    }
+
+    #[test]
+    fn truncation_restarts_from_the_beginning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.bin");
+        std::fs::write(&path, [frame(b"old one"), frame(b"old two")].concat()).unwrap();
+
+        let mut tail = TailReader::open(&path, DefaultDeframer::new()).unwrap();
+        while tail.try_read_message().unwrap().is_some() {}
+
+        std::fs::write(&path, frame(b"new")).unwrap();
+        assert!(tail.try_read_message().unwrap().is_none());
+        assert_eq!(tail.generation(), 1);
+        assert_eq!(tail.try_read_message().unwrap().unwrap(), b"new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rotation_drains_the_old_file_then_switches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.bin");
+        std::fs::write(&path, frame(b"before")).unwrap();
+        let mut tail = TailReader::open(&path, DefaultDeframer::new()).unwrap();
+
+        append(&path, &frame(b"last in old"));
+        std::fs::rename(&path, dir.path().join("journal.bin.1")).unwrap();
+        assert!(tail.try_read_message().unwrap().is_some());
+        std::fs::write(&path, frame(b"after")).unwrap();
+
+        assert_eq!(tail.try_read_message().unwrap().unwrap(), b"last in old");
+        assert!(tail.try_read_message().unwrap().is_none());
+        assert_eq!(tail.generation(), 1);
+        assert_eq!(tail.try_read_message().unwrap().unwrap(), b"after");
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::error::Result;
 use crate::framing::Framer;
+use crate::index::{self, FrameIndex};
 use crate::policy::{MemoryPolicy, ReclamationInfo};
 use crate::traits::StreamSerialize;
 use flatbuffers::{DefaultAllocator, FlatBufferBuilder};
@@ -14,12 +15,67 @@ use std::io::Write;
 /// The factory closure exists because a reclaim must construct a *fresh*
 /// `FlatBufferBuilder`, and only the caller knows how to do that for a custom
 /// allocator. For the default allocator it is simply `FlatBufferBuilder::with_capacity`.
-struct PolicySlot<'a, A: flatbuffers::Allocator> {
+pub(crate) struct PolicySlot<'a, A: flatbuffers::Allocator> {
     policy: Box<dyn MemoryPolicy>,
-    baseline_capacity: usize,
+    pub(crate) baseline_capacity: usize,
     make_builder: Box<dyn FnMut(usize) -> FlatBufferBuilder<'a, A> + Send + 'a>,
 }
 
+impl<'a, A: flatbuffers::Allocator> PolicySlot<'a, A> {
+    pub(crate) fn new<P, M>(policy: P, make_builder: M) -> Self
+    where
+        P: MemoryPolicy + 'static,
+        M: FnMut(usize) -> FlatBufferBuilder<'a, A> + Send + 'a,
+    {
+        Self {
+            baseline_capacity: policy.baseline_capacity(),
+            policy: Box::new(policy),
+            make_builder: Box::new(make_builder),
+        }
+    }
+
+    /// Consults the policy after a successful simple-mode write and, when it
+    /// fires, replaces `builder` with a fresh one at the baseline capacity.
+    /// Returns whether a reclaim happened.
+    pub(crate) fn reclaim_if_needed(
+        &mut self,
+        builder: &mut FlatBufferBuilder<'a, A>,
+        last_message_size: usize,
+    ) -> bool {
+        // Capacity read: `FlatBufferBuilder` exposes no capacity() getter.
+        // mut_finished_buffer() returns (&mut backing_buffer, start_index);
+        // the slice length is the backing buffer size — our effective
+        // capacity. O(1), no allocation, and safe here because the builder
+        // is finished and the frame has been written.
+        let (buf, _start_idx) = builder.mut_finished_buffer();
+        let current_capacity = buf.len();
+
+        // At or below the policy's baseline there is nothing to reclaim —
+        // skip the policy entirely so its hysteresis state cannot churn
+        // (rebuilding a baseline-sized builder into an identical one would
+        // be pure allocator noise).
+        if current_capacity <= self.baseline_capacity {
+            return false;
+        }
+        let Some(reason) = self
+            .policy
+            .should_reset(last_message_size, current_capacity)
+        else {
+            return false;
+        };
+        // Drop the over-provisioned builder and rebuild at the baseline
+        // capacity — resets the stream's high-water mark.
+        *builder = (self.make_builder)(self.baseline_capacity);
+        self.policy.on_reclaim(&ReclamationInfo {
+            reason,
+            last_message_size,
+            capacity_before: current_capacity,
+            capacity_after: self.baseline_capacity,
+        });
+        true
+    }
+}
+
 /// A writer for streaming FlatBuffer messages.
 ///
 /// This writer is generic over a `Framer` strategy, which defines how
@@ -72,6 +128,12 @@ struct PolicySlot<'a, A: flatbuffers::Allocator> {
 /// To combine a custom allocator with a memory policy, use
 /// [`with_memory_policy_and_factory`](Self::with_memory_policy_and_factory) and
 /// supply the closure that rebuilds your builder on reclaim.
+///
+/// ## Frame Index
+///
+/// [`with_index`](Self::with_index) records the offset and payload length of
+/// every frame written, in both modes, into a [`FrameIndex`] that can be saved
+/// as a sidecar file for O(1) seeks on the read side.
 pub struct StreamWriter<'a, W: Write, F: Framer, A = DefaultAllocator>
 where
     A: flatbuffers::Allocator,
@@ -80,6 +142,7 @@ where
     framer: F,
     builder: FlatBufferBuilder<'a, A>,
     policy: Option<PolicySlot<'a, A>>,
+    index: Option<FrameIndex>,
 }
 
 impl<'a, W: Write, F: Framer> StreamWriter<'a, W, F> {
@@ -97,6 +160,7 @@ impl<'a, W: Write, F: Framer> StreamWriter<'a, W, F> {
             framer,
             builder: FlatBufferBuilder::new(),
             policy: None,
+            index: None,
         }
     }
 
@@ -108,6 +172,7 @@ impl<'a, W: Write, F: Framer> StreamWriter<'a, W, F> {
             framer,
             builder,
             policy: None,
+            index: None,
         }
     }
 
@@ -120,6 +185,7 @@ impl<'a, W: Write, F: Framer> StreamWriter<'a, W, F> {
             framer,
             builder: FlatBufferBuilder::with_capacity(capacity),
             policy: None,
+            index: None,
         }
     }
 
@@ -134,11 +200,7 @@ impl<'a, W: Write, F: Framer> StreamWriter<'a, W, F> {
     ///
     /// Has no effect on `write_finished()`, where the caller owns the builder.
     pub fn with_memory_policy<P: MemoryPolicy + 'static>(mut self, policy: P) -> Self {
-        self.policy = Some(PolicySlot {
-            baseline_capacity: policy.baseline_capacity(),
-            policy: Box::new(policy),
-            make_builder: Box::new(FlatBufferBuilder::with_capacity),
-        });
+        self.policy = Some(PolicySlot::new(policy, FlatBufferBuilder::with_capacity));
         self
     }
 }
@@ -169,6 +231,7 @@ where
             framer,
             builder,
             policy: None,
+            index: None,
         }
     }
 
@@ -183,14 +246,41 @@ where
         P: MemoryPolicy + 'static,
         M: FnMut(usize) -> FlatBufferBuilder<'a, A> + Send + 'a,
     {
-        self.policy = Some(PolicySlot {
-            baseline_capacity: policy.baseline_capacity(),
-            policy: Box::new(policy),
-            make_builder: Box::new(make_builder),
-        });
+        self.policy = Some(PolicySlot::new(policy, make_builder));
+        self
+    }
+
+    /// Records every frame written from now on into `index`.
+    ///
+    /// New frames are indexed from `index.end_offset()`, which must be the
+    /// sink's current position: `FrameIndex::new()` for a new stream, or the
+    /// index [`FrameIndex::scan_file`] returns (which leaves the file's cursor
+    /// at that offset) to resume appending to an existing one. Indexing costs
+    /// one predictable branch per write when not installed.
+    ///
+    /// A frame is recorded only once it has been written in full. After a
+    /// write error the stream may end in a torn frame; repair it and rebuild
+    /// the index with `scan_file` rather than trusting the recorded one.
+    ///
+    /// Writes through a framer that holds payloads back (see
+    /// [`Framer::holds_back_payloads`]), such as the block framer, fail with
+    /// `InvalidFrame` while an index is installed.
+    pub fn with_index(mut self, index: FrameIndex) -> Self {
+        self.index = Some(index);
         self
     }
 
+    /// Returns the index being recorded, if one is installed.
+    pub fn index(&self) -> Option<&FrameIndex> {
+        self.index.as_ref()
+    }
+
+    /// Removes and returns the index being recorded; later writes are not
+    /// indexed.
+    pub fn take_index(&mut self) -> Option<FrameIndex> {
+        self.index.take()
+    }
+
     /// Writes a serializable item to the stream using the internally managed builder.
     /// The builder is reset before serialization.
     ///
@@ -221,7 +311,10 @@ where
         let last_message_size = payload.len();
 
         // Delegate framing and writing to the strategy
-        self.framer.frame_and_write(&mut self.writer, payload)?;
+        match self.index.as_mut() {
+            None => self.framer.frame_and_write(&mut self.writer, payload)?,
+            Some(index) => index::frame_and_record(&self.framer, &mut self.writer, index, payload)?,
+        }
 
         // Evaluate the policy only after a successful write, so the payload we
         // just framed is never invalidated. One predictable branch when no
@@ -239,36 +332,8 @@ where
     /// writers without a policy.
     #[inline(never)]
     fn evaluate_memory_policy(&mut self, last_message_size: usize) {
-        let Some(slot) = self.policy.as_mut() else {
-            return;
-        };
-        // Capacity read: `FlatBufferBuilder` exposes no capacity() getter.
-        // mut_finished_buffer() returns (&mut backing_buffer, start_index);
-        // the slice length is the backing buffer size — our effective
-        // capacity. O(1), no allocation, and safe here because the builder
-        // is finished and the frame has been written.
-        let (buf, _start_idx) = self.builder.mut_finished_buffer();
-        let current_capacity = buf.len();
-
-        // At or below the policy's baseline there is nothing to reclaim —
-        // skip the policy entirely so its hysteresis state cannot churn
-        // (rebuilding a baseline-sized builder into an identical one would
-        // be pure allocator noise).
-        if current_capacity > slot.baseline_capacity {
-            if let Some(reason) = slot
-                .policy
-                .should_reset(last_message_size, current_capacity)
-            {
-                // Drop the over-provisioned builder and rebuild at the
-                // baseline capacity — resets the stream's high-water mark.
-                self.builder = (slot.make_builder)(slot.baseline_capacity);
-                slot.policy.on_reclaim(&ReclamationInfo {
-                    reason,
-                    last_message_size,
-                    capacity_before: current_capacity,
-                    capacity_after: slot.baseline_capacity,
-                });
-            }
+        if let Some(slot) = self.policy.as_mut() {
+            slot.reclaim_if_needed(&mut self.builder, last_message_size);
         }
     }
 
@@ -308,14 +373,26 @@ where
         builder: &mut FlatBufferBuilder<A2>,
     ) -> Result<()> {
         // Get the finished payload from the builder
-        let payload = builder.finished_data();
+        self.write_payload(builder.finished_data())
+    }
 
+    /// Frames and writes payload bytes that are already serialized — e.g.
+    /// payloads read from another stream being relayed or re-framed.
+    ///
+    /// The framer (and any recorded index) sees them exactly as it would a
+    /// finished builder's data.
+    pub fn write_payload(&mut self, payload: &[u8]) -> Result<()> {
         // Delegate framing and writing to the strategy
-        self.framer.frame_and_write(&mut self.writer, payload)
+        match self.index.as_mut() {
+            None => self.framer.frame_and_write(&mut self.writer, payload),
+            Some(index) => index::frame_and_record(&self.framer, &mut self.writer, index, payload),
+        }
     }
 
-    /// Flushes the underlying writer.
+    /// Writes out any frames the framer is holding back (see
+    /// [`Framer::flush_pending`]), then flushes the underlying writer.
     pub fn flush(&mut self) -> Result<()> {
+        self.framer.flush_pending(&mut self.writer)?;
         self.writer.flush()?;
         Ok(())
     }
@@ -55,6 +55,13 @@ fn generate_corpus_files() {
             let path = dir.join(format!("xxhash64_{label}.hex"));
             fs::write(path, to_hex(&out)).unwrap();
         }
+        for (label, payload) in [("empty", empty), ("small", small), ("medium", medium)] {
+            let mut out = Vec::new();
+            let framer = framing::HeaderChecksumFramer::new(XxHash64::new());
+            framer.frame_and_write(&mut out, payload).unwrap();
+            let path = dir.join(format!("hdr_xxhash64_{label}.hex"));
+            fs::write(path, to_hex(&out)).unwrap();
+        }
     }
 
     #[cfg(feature = "crc32")]
@@ -66,6 +73,31 @@ fn generate_corpus_files() {
             let path = dir.join(format!("crc32_{label}.hex"));
             fs::write(path, to_hex(&out)).unwrap();
         }
+        for (label, payload) in [("empty", empty), ("small", small), ("medium", medium)] {
+            let mut out = Vec::new();
+            let framer = framing::HeaderChecksumFramer::new(Crc32::new());
+            framer.frame_and_write(&mut out, payload).unwrap();
+            let path = dir.join(format!("hdr_crc32_{label}.hex"));
+            fs::write(path, to_hex(&out)).unwrap();
+        }
+    }
+
+    #[cfg(feature = "crc32c")]
+    {
+        for (label, payload) in [("empty", empty), ("small", small), ("medium", medium)] {
+            let mut out = Vec::new();
+            let framer = framing::ChecksumFramer::new(Crc32c::new());
+            framer.frame_and_write(&mut out, payload).unwrap();
+            let path = dir.join(format!("crc32c_{label}.hex"));
+            fs::write(path, to_hex(&out)).unwrap();
+        }
+        for (label, payload) in [("empty", empty), ("small", small), ("medium", medium)] {
+            let mut out = Vec::new();
+            let framer = framing::HeaderChecksumFramer::new(Crc32c::new());
+            framer.frame_and_write(&mut out, payload).unwrap();
+            let path = dir.join(format!("hdr_crc32c_{label}.hex"));
+            fs::write(path, to_hex(&out)).unwrap();
+        }
     }
 
     #[cfg(feature = "crc16")]
@@ -77,5 +109,12 @@ fn generate_corpus_files() {
             let path = dir.join(format!("crc16_{label}.hex"));
             fs::write(path, to_hex(&out)).unwrap();
         }
+        for (label, payload) in [("empty", empty), ("small", small), ("medium", medium)] {
+            let mut out = Vec::new();
+            let framer = framing::HeaderChecksumFramer::new(Crc16::new());
+            framer.frame_and_write(&mut out, payload).unwrap();
+            let path = dir.join(format!("hdr_crc16_{label}.hex"));
+            fs::write(path, to_hex(&out)).unwrap();
+        }
     }
 }
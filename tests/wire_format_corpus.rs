@@ -78,7 +78,12 @@ fn assert_golden<F: Framer, D: Deframer>(prefix: &str, framer: &F, make_deframer
 /// Reading a checksummed stream with the plain deframer yields a frame of the
 /// declared length whose bytes are wrong (checksum prefix + truncated payload)
 /// — silent mis-framing, not an error.
-#[cfg(any(feature = "xxhash", feature = "crc32", feature = "crc16"))]
+#[cfg(any(
+    feature = "xxhash",
+    feature = "crc32",
+    feature = "crc16",
+    feature = "crc32c"
+))]
 fn assert_cross_strategy_misframe(prefix: &str) {
     let bytes = golden(&format!("tests/corpus/{prefix}_medium.hex"));
     let (_, payload) = payloads().pop().expect("medium is last");
@@ -120,6 +125,17 @@ fn corpus_crc32_golden() {
     assert_cross_strategy_misframe("crc32");
 }
 
+#[cfg(feature = "crc32c")]
+#[test]
+fn corpus_crc32c_golden() {
+    assert_golden(
+        "crc32c",
+        &framing::ChecksumFramer::new(Crc32c::new()),
+        || framing::ChecksumDeframer::new(Crc32c::new()),
+    );
+    assert_cross_strategy_misframe("crc32c");
+}
+
 #[cfg(feature = "crc16")]
 #[test]
 fn corpus_crc16_golden() {
@@ -128,3 +144,77 @@ fn corpus_crc16_golden() {
     });
     assert_cross_strategy_misframe("crc16");
 }
+
+/// A flipped bit in the length prefix of a header-checked frame is a clean
+/// `ChecksumMismatch`, raised before the declared length sizes any buffer.
+#[cfg(any(
+    feature = "xxhash",
+    feature = "crc32",
+    feature = "crc16",
+    feature = "crc32c"
+))]
+fn assert_length_flip_detected<D: Deframer>(prefix: &str, deframer: D) {
+    let mut bytes = golden(&format!("tests/corpus/hdr_{prefix}_medium.hex"));
+    bytes[3] ^= 0x40;
+    let mut reader = StreamReader::with_capacity(Cursor::new(&bytes), deframer, 0);
+    let err = reader.read_message().unwrap_err();
+    assert!(
+        matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }),
+        "length flip in hdr_{prefix} not reported as a checksum mismatch: {err}"
+    );
+    assert_eq!(
+        reader.buffer_capacity(),
+        0,
+        "payload buffer sized from a corrupt length"
+    );
+}
+
+#[cfg(feature = "xxhash")]
+#[test]
+fn corpus_hdr_xxhash64_golden() {
+    assert_golden(
+        "hdr_xxhash64",
+        &framing::HeaderChecksumFramer::new(XxHash64::new()),
+        || framing::HeaderChecksumDeframer::new(XxHash64::new()),
+    );
+    assert_length_flip_detected(
+        "xxhash64",
+        framing::HeaderChecksumDeframer::new(XxHash64::new()),
+    );
+}
+
+#[cfg(feature = "crc32")]
+#[test]
+fn corpus_hdr_crc32_golden() {
+    assert_golden(
+        "hdr_crc32",
+        &framing::HeaderChecksumFramer::new(Crc32::new()),
+        || framing::HeaderChecksumDeframer::new(Crc32::new()),
+    );
+    assert_length_flip_detected("crc32", framing::HeaderChecksumDeframer::new(Crc32::new()));
+}
+
+#[cfg(feature = "crc32c")]
+#[test]
+fn corpus_hdr_crc32c_golden() {
+    assert_golden(
+        "hdr_crc32c",
+        &framing::HeaderChecksumFramer::new(Crc32c::new()),
+        || framing::HeaderChecksumDeframer::new(Crc32c::new()),
+    );
+    assert_length_flip_detected(
+        "crc32c",
+        framing::HeaderChecksumDeframer::new(Crc32c::new()),
+    );
+}
+
+#[cfg(feature = "crc16")]
+#[test]
+fn corpus_hdr_crc16_golden() {
+    assert_golden(
+        "hdr_crc16",
+        &framing::HeaderChecksumFramer::new(Crc16::new()),
+        || framing::HeaderChecksumDeframer::new(Crc16::new()),
+    );
+    assert_length_flip_detected("crc16", framing::HeaderChecksumDeframer::new(Crc16::new()));
+}
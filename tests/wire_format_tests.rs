@@ -117,3 +117,89 @@ fn checksumframer_layout_crc16() {
     let _cksum = u16::from_le_bytes(out[4..6].try_into().unwrap());
     assert_eq!(&out[6..], payload);
 }
+
+#[cfg(feature = "crc32")]
+#[test]
+fn headerchecksumframer_layout_crc32() {
+    // Purpose: Verify HeaderChecksumFramer::<Crc32> writes [4-byte LE length |
+    // 4-byte payload checksum | 4-byte checksum over the preceding 8 bytes |
+    // payload], and that every header byte is covered on both read paths.
+    use flatstream::checksum::Checksum;
+    use flatstream::framing::{HeaderChecksumDeframer, HeaderChecksumFramer};
+    let payload = b"abc";
+    let mut out = Vec::new();
+    HeaderChecksumFramer::new(Crc32::new())
+        .frame_and_write(&mut out, payload)
+        .unwrap();
+    assert_eq!(out.len(), 4 + 4 + 4 + payload.len());
+    assert_eq!(u32::from_le_bytes(out[..4].try_into().unwrap()), 3);
+    let payload_cksum = u32::from_le_bytes(out[4..8].try_into().unwrap());
+    assert_eq!(payload_cksum as u64, Crc32::new().calculate(payload));
+    let header_cksum = u32::from_le_bytes(out[8..12].try_into().unwrap());
+    assert_eq!(header_cksum as u64, Crc32::new().calculate(&out[..8]));
+    assert_eq!(&out[12..], payload);
+
+    let deframer = HeaderChecksumDeframer::new(Crc32::new());
+    let frame = deframer.deframe_slice(&out).unwrap().unwrap();
+    assert_eq!(&out[frame.payload], payload);
+    for i in 0..12 {
+        let mut corrupt = out.clone();
+        corrupt[i] ^= 0x01;
+        let err = deframer.deframe_slice(&corrupt).unwrap_err();
+        assert!(
+            matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }),
+            "byte {i}"
+        );
+        let mut buf = Vec::new();
+        let err = deframer
+            .read_and_deframe(&mut std::io::Cursor::new(&corrupt), &mut buf)
+            .unwrap_err();
+        assert!(
+            matches!(err.kind(), ErrorKind::ChecksumMismatch { .. }),
+            "byte {i}"
+        );
+    }
+}
+
+#[cfg(feature = "sha256")]
+#[test]
+fn checksumframer_layout_sha256() {
+    // Purpose: Verify ChecksumFramer::<Sha256> writes [4-byte LE length |
+    // 32-byte digest | payload], and that a corrupted payload is reported as
+    // DigestMismatch on the slice, read_and_deframe and read_after_length paths.
+    use flatstream::framing::{ChecksumDeframer, ChecksumFramer};
+    use std::io::Read;
+    let payload = b"abc";
+    let mut out = Vec::new();
+    ChecksumFramer::new(Sha256::new())
+        .frame_and_write(&mut out, payload)
+        .unwrap();
+    assert_eq!(out.len(), 4 + 32 + payload.len());
+    assert_eq!(u32::from_le_bytes(out[..4].try_into().unwrap()), 3);
+    assert_eq!(
+        out[4..8],
+        [0xba, 0x78, 0x16, 0xbf],
+        "digest in canonical byte order"
+    );
+    assert_eq!(&out[36..], payload);
+
+    let deframer = ChecksumDeframer::new(Sha256::new());
+    let frame = deframer.deframe_slice(&out).unwrap().unwrap();
+    assert_eq!(&out[frame.payload], payload);
+
+    let mut corrupt = out.clone();
+    *corrupt.last_mut().unwrap() ^= 0x01;
+    let err = deframer.deframe_slice(&corrupt).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::DigestMismatch { .. }));
+    let mut buf = Vec::new();
+    let err = deframer
+        .read_and_deframe(&mut std::io::Cursor::new(&corrupt), &mut buf)
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::DigestMismatch { .. }));
+    let mut cursor = std::io::Cursor::new(&corrupt);
+    cursor.read_exact(&mut [0u8; 4]).unwrap();
+    let err = deframer
+        .read_after_length(&mut cursor, &mut buf, payload.len())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::DigestMismatch { .. }));
+}